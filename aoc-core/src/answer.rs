@@ -0,0 +1,139 @@
+use std::fmt;
+
+/// What a puzzle part evaluates to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// A picture that has to be read by eye, like day 10's CRT.
+    Image(Image),
+    /// The part has no working solver.
+    Unsolved,
+    /// The solver ran out of [budget](crate::budget), with the best answer it
+    /// had found by then if it had one.
+    TimedOut(Option<Box<Answer>>),
+}
+
+/// A grid of lit and unlit pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    /// Build an image from rows of equal length.
+    pub fn from_rows(rows: &[Vec<bool>]) -> Self {
+        let width = rows.first().map_or(0, |r| r.len());
+        let mut image = Image::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &lit) in row.iter().enumerate() {
+                image.set(x, y, lit);
+            }
+        }
+        image
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, lit: bool) {
+        self.pixels[y * self.width + x] = lit;
+    }
+}
+
+/// Renders lit pixels as `#` and unlit ones as `.`, one row per line.
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for x in 0..self.width {
+                write!(f, "{}", if self.get(x, y) { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Image(image) => write!(f, "{}", image),
+            Answer::Unsolved => write!(f, "unsolved"),
+            Answer::TimedOut(None) => write!(f, "timed out"),
+            Answer::TimedOut(Some(best)) => write!(f, "timed out, best so far {}", best),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Number(i64::try_from(n).expect("answer does not fit in i64"))
+                }
+            }
+        )*
+    };
+}
+
+answer_from_int!(i32, i64, u8, u16, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+impl From<Image> for Answer {
+    fn from(image: Image) -> Self {
+        Answer::Image(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_display() {
+        let image = Image::from_rows(&[vec![true, false], vec![false, true]]);
+        assert_eq!(image.to_string(), "#.\n.#");
+        assert_eq!(Answer::from(image).to_string(), "#.\n.#");
+    }
+
+    #[test]
+    fn from_numbers() {
+        assert_eq!(Answer::from(42usize), Answer::Number(42));
+        assert_eq!(Answer::from(-3i32), Answer::Number(-3));
+    }
+}
@@ -0,0 +1,230 @@
+//! Limits on how long a solver may keep searching.
+//!
+//! A [`Budget`] is a time limit, a step limit, a cancel flag or any mix of
+//! them. [`Budget::run`] puts one in force on the current thread while a
+//! solver runs; the solver's loops call [`tick`] once per step and stop when it
+//! returns false, then hand back whatever they found through [`answer`], which
+//! marks it [`Answer::TimedOut`] if the budget ran out. Without a budget in
+//! force `tick` always returns true, so solvers behave as they always did.
+//!
+//! ```
+//! use aoc_core::budget::{self, Budget};
+//!
+//! let mut best = 0;
+//! let answer = Budget::unlimited().with_steps(100).run(|| {
+//!     while budget::tick() {
+//!         best += 1;
+//!     }
+//!     budget::answer(best)
+//! });
+//! assert_eq!(answer.to_string(), "timed out, best so far 100");
+//! ```
+
+use std::cell::RefCell;
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::answer::Answer;
+
+/// Environment variable holding a time limit in seconds, see [`Budget::from_env`].
+pub const TIMEOUT_VAR: &str = "AOC_TIMEOUT";
+
+/// How often, in steps, the clock and the cancel flag are looked at.
+const CHECK_EVERY: u64 = 256;
+
+/// How much a solver may do before it has to give up. Clones share the cancel
+/// flag, so one can be handed to another thread to stop a running solver.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    timeout: Option<Duration>,
+    steps: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Budget::default()
+    }
+
+    /// Give up `timeout` after [`run`](Budget::run) starts.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Give up after `steps` calls to [`tick`].
+    pub fn with_steps(mut self, steps: u64) -> Self {
+        self.steps = Some(steps);
+        self
+    }
+
+    /// A time limit of [`TIMEOUT_VAR`] seconds if it is set, else `default`.
+    pub fn from_env(default: Option<Duration>) -> Result<Self, String> {
+        let timeout = match env::var(TIMEOUT_VAR) {
+            Ok(secs) => Some(
+                secs.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|s| Duration::try_from_secs_f64(s).ok())
+                    .ok_or_else(|| format!("invalid {} {:?}", TIMEOUT_VAR, secs))?,
+            ),
+            Err(_) => default,
+        };
+        Ok(Budget {
+            timeout,
+            ..Budget::default()
+        })
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Make every solver running under this budget, or a clone of it, give up
+    /// at its next check.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Run `f` with this budget in force on the current thread, restoring
+    /// whatever was in force before once it returns or panics.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        let meter = Meter {
+            deadline: self.timeout.map(|t| Instant::now() + t),
+            steps_left: self.steps,
+            cancelled: self.cancelled.clone(),
+            ticks: 0,
+            exhausted: false,
+        };
+        let _restore = Restore(CURRENT.with(|c| c.replace(Some(meter))));
+        f()
+    }
+}
+
+/// The budget in force on a thread, as it gets used up.
+struct Meter {
+    deadline: Option<Instant>,
+    steps_left: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+    ticks: u64,
+    exhausted: bool,
+}
+
+impl Meter {
+    fn tick(&mut self) -> bool {
+        if self.exhausted {
+            return false;
+        }
+        self.ticks += 1;
+        if let Some(left) = &mut self.steps_left {
+            match left.checked_sub(1) {
+                Some(l) => *left = l,
+                None => self.exhausted = true,
+            }
+        }
+        if self.ticks.is_multiple_of(CHECK_EVERY) {
+            self.exhausted |= self.cancelled.load(Ordering::Relaxed)
+                || self.deadline.is_some_and(|d| Instant::now() >= d);
+        }
+        !self.exhausted
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Meter>> = const { RefCell::new(None) };
+}
+
+struct Restore(Option<Meter>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.with(|c| *c.borrow_mut() = self.0.take());
+    }
+}
+
+/// Count one step against the budget in force; false once it has run out, and
+/// from then on until the budget is lifted.
+pub fn tick() -> bool {
+    CURRENT.with(|c| c.borrow_mut().as_mut().is_none_or(Meter::tick))
+}
+
+/// Whether the budget in force has run out, without using any of it.
+pub fn exhausted() -> bool {
+    CURRENT.with(|c| c.borrow().as_ref().is_some_and(|m| m.exhausted))
+}
+
+/// `best`, marked as only the best found so far if the budget ran out.
+pub fn answer(best: impl Into<Answer>) -> Answer {
+    match exhausted() {
+        true => Answer::TimedOut(Some(Box::new(best.into()))),
+        false => best.into(),
+    }
+}
+
+/// What a search found, if anything; when it found nothing, whether that
+/// is because the budget ran out.
+pub fn found(answer: Option<impl Into<Answer>>) -> Answer {
+    match answer {
+        Some(answer) => answer.into(),
+        None if exhausted() => Answer::TimedOut(None),
+        None => Answer::Unsolved,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_run_out() {
+        let count = |budget: Budget| budget.run(|| (0..1000).take_while(|_| tick()).count());
+        assert_eq!(count(Budget::unlimited()), 1000);
+        assert_eq!(count(Budget::unlimited().with_steps(10)), 10);
+        assert!(!exhausted(), "budget still in force after run");
+
+        let best = Budget::unlimited().with_steps(0).run(|| {
+            tick();
+            answer(5)
+        });
+        assert_eq!(best, Answer::TimedOut(Some(Box::new(Answer::Number(5)))));
+        assert_eq!(Budget::unlimited().run(|| answer(5)), Answer::Number(5));
+        assert_eq!(found(None::<i64>), Answer::Unsolved);
+    }
+
+    #[test]
+    fn deadline_and_cancel() {
+        let budget = Budget::unlimited().with_timeout(Duration::from_millis(20));
+        let start = Instant::now();
+        budget.run(|| while tick() {});
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let budget = Budget::unlimited();
+        let remote = budget.clone();
+        let ticks = budget.run(|| {
+            let mut n = 0;
+            while tick() {
+                n += 1;
+                if n == 1000 {
+                    remote.cancel();
+                }
+            }
+            n
+        });
+        assert!((1000..1000 + CHECK_EVERY as usize).contains(&ticks));
+        assert!(budget.is_cancelled());
+    }
+
+    #[test]
+    fn nested_budgets() {
+        Budget::unlimited().with_steps(5).run(|| {
+            Budget::unlimited().run(|| assert!((0..100).all(|_| tick())));
+            assert_eq!((0..100).take_while(|_| tick()).count(), 5);
+        });
+    }
+}
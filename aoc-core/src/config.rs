@@ -0,0 +1,309 @@
+//! Puzzle parameters that are not part of the puzzle input, like the row day
+//! 15 asks about, kept in `aoc.toml` at the workspace root.
+//!
+//! ```toml
+//! [day15]            # real inputs
+//! row = 2_000_000
+//!
+//! [day15."in.test"]  # the example overrides them
+//! row = 10
+//! ```
+//!
+//! A day's table holds the values for real inputs, and a table under it named
+//! after an input file overrides them for that file. Solvers read them with
+//! [`Input::param_or`](crate::Input::param_or), falling back to the puzzle's
+//! value, once the runner or the test helper has attached them with
+//! [`Input::with_params`](crate::Input::with_params). Without a file, every
+//! day gets no parameters.
+//!
+//! Only the part of TOML this needs is understood: table headers, bare keys,
+//! and integer, float, boolean and basic string values.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::error::{ParseError, Result};
+use crate::input::Input;
+
+/// Name of the configuration file at the workspace root.
+pub const CONFIG_FILE: &str = "aoc.toml";
+/// Environment variable naming a configuration file to use instead.
+pub const CONFIG_VAR: &str = "AOC_CONFIG";
+
+/// Parameters for one input, each value as text: strings without their quotes,
+/// numbers without `_` separators.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Params {
+    values: BTreeMap<String, String>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Params::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|s| s.as_str())
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// Every table in a configuration file, keyed by day table and input name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    tables: BTreeMap<(String, Option<String>), Params>,
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Config::parse(&Input::from_path(path)?)
+    }
+
+    /// [`CONFIG_VAR`] if it is set, else [`CONFIG_FILE`] at the root of the
+    /// workspace this was built in, which may be missing.
+    pub fn workspace() -> Result<Self> {
+        if let Some(path) = env::var_os(CONFIG_VAR) {
+            return Config::load(path);
+        }
+        let path = workspace_file();
+        match path.exists() {
+            true => Config::load(path),
+            false => Ok(Config::default()),
+        }
+    }
+
+    pub fn parse(input: &Input) -> Result<Self> {
+        let mut tables = BTreeMap::new();
+        let mut table: Option<(String, Option<String>)> = None;
+        for (n, line) in input.numbered_lines() {
+            let content = strip_comment(line).trim();
+            if content.is_empty() {
+                continue;
+            }
+            if let Some(header) = content.strip_prefix('[') {
+                let header = header.strip_suffix(']').ok_or_else(|| {
+                    input.locate(ParseError::at(n, line, content, "expected `]`"))
+                })?;
+                let key = table_name(header)
+                    .map_err(|e| input.locate(ParseError::at(n, line, header, e)))?;
+                if tables.insert(key.clone(), Params::new()).is_some() {
+                    return Err(input.locate(ParseError::at(
+                        n,
+                        line,
+                        header,
+                        "table defined twice",
+                    )));
+                }
+                table = Some(key);
+                continue;
+            }
+            let Some((key, value)) = content.split_once('=') else {
+                return Err(input.locate(ParseError::at(
+                    n,
+                    line,
+                    content,
+                    "expected `key = value`",
+                )));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if !is_bare_key(key) {
+                return Err(input.locate(ParseError::at(n, line, key, "expected a bare key")));
+            }
+            let Some(name) = &table else {
+                return Err(input.locate(ParseError::at(
+                    n,
+                    line,
+                    key,
+                    "key outside of any [table]",
+                )));
+            };
+            let value =
+                parse_value(value).map_err(|e| input.locate(ParseError::at(n, line, value, e)))?;
+            let params = tables.get_mut(name).unwrap();
+            if params.get(key).is_some() {
+                return Err(input.locate(ParseError::at(n, line, key, "key defined twice")));
+            }
+            params.set(key, value);
+        }
+        Ok(Config { tables })
+    }
+
+    /// Parameters for `input` of `day`: the day's table, overridden by the
+    /// table for `input` if there is one.
+    pub fn params(&self, day: u8, input: Option<&str>) -> Params {
+        let day = format!("day{}", day);
+        let mut params = self
+            .tables
+            .get(&(day.clone(), None))
+            .cloned()
+            .unwrap_or_default();
+        if let Some(specific) = input.and_then(|i| self.tables.get(&(day, Some(i.to_string())))) {
+            for (key, value) in specific.iter() {
+                params.set(key, value);
+            }
+        }
+        params
+    }
+}
+
+/// `aoc.toml` next to the `aocN` crates.
+pub fn workspace_file() -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.parent().unwrap_or(&root).join(CONFIG_FILE)
+}
+
+/// `line` up to a `#` that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// `day15` or `day15."in.test"`.
+fn table_name(header: &str) -> std::result::Result<(String, Option<String>), String> {
+    let header = header.trim();
+    let (day, input) = match header.split_once('.') {
+        Some((day, input)) => (day.trim(), Some(input.trim())),
+        None => (header, None),
+    };
+    if !is_bare_key(day) {
+        return Err("expected a table name".to_string());
+    }
+    let input = match input {
+        Some(quoted) if quoted.starts_with('"') => Some(parse_string(quoted)?),
+        Some(bare) if is_bare_key(bare) => Some(bare.to_string()),
+        Some(_) => return Err("expected an input name, quoted if it has dots".to_string()),
+        None => None,
+    };
+    Ok((day.to_string(), input))
+}
+
+fn parse_value(value: &str) -> std::result::Result<String, String> {
+    if value.starts_with('"') {
+        return parse_string(value);
+    }
+    if value == "true" || value == "false" {
+        return Ok(value.to_string());
+    }
+    let digits = value.replace('_', "");
+    let number = digits.strip_prefix(['+', '-']).unwrap_or(&digits);
+    let well_placed = !value.starts_with('_') && !value.ends_with('_') && !value.contains("__");
+    let numeric = digits.parse::<i64>().is_ok() || digits.parse::<f64>().is_ok();
+    // Rules out `inf`, `nan` and `.5`, which `f64` would take
+    if well_placed && numeric && number.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(digits.trim_start_matches('+').to_string());
+    }
+    Err("expected a number, a quoted string, true or false".to_string())
+}
+
+/// A `"..."` string with `\"`, `\\`, `\n` and `\t` escapes, and nothing after it.
+fn parse_string(quoted: &str) -> std::result::Result<String, String> {
+    let mut chars = quoted.strip_prefix('"').ok_or("expected a string")?.chars();
+    let mut out = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => break,
+            Some('\\') => out.push(match chars.next() {
+                Some('"') => '"',
+                Some('\\') => '\\',
+                Some('n') => '\n',
+                Some('t') => '\t',
+                other => return Err(format!("unknown escape \\{}", other.unwrap_or(' '))),
+            }),
+            Some(c) => out.push(c),
+        }
+    }
+    match chars.as_str().trim() {
+        "" => Ok(out),
+        _ => Err("unexpected text after the string".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    const EXAMPLE: &str = "\
+# comment
+[day7]
+disk = 70_000_000   # trailing comment
+name = \"a # b \\\"c\\\"\"
+
+[day7.\"in.test\"]
+disk = 48
+flag = true
+ratio = -0.5
+";
+
+    #[test]
+    fn overrides() {
+        let config = Config::parse(&Input::from(EXAMPLE)).unwrap();
+        let real = config.params(7, Some("in.1"));
+        assert_eq!(real.get("disk"), Some("70000000"));
+        assert_eq!(real.get("name"), Some("a # b \"c\""));
+        assert_eq!(real.get("flag"), None);
+        let example = config.params(7, Some("in.test"));
+        assert_eq!(example.get("disk"), Some("48"));
+        assert_eq!(example.get("name"), Some("a # b \"c\""));
+        assert_eq!(example.get("ratio"), Some("-0.5"));
+        assert_eq!(config.params(7, None), real);
+        assert!(config.params(8, Some("in.test")).is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        for (text, line, column) in [
+            ("x = 1\n", 1, 1),
+            ("[day1]\nx = [1, 2]\n", 2, 5),
+            ("[day1]\nx = 1\nx = 2\n", 3, 1),
+            ("[day1]\n[day1]\n", 2, 2),
+            ("[day1.in.test]\n", 1, 2),
+            ("[day1]\ns = \"open\n", 2, 5),
+            ("[day1]\nn = 1__0\n", 2, 5),
+            ("[day1\n", 1, 1),
+        ] {
+            match Config::parse(&Input::from(text)) {
+                Err(Error::Parse { error, .. }) => {
+                    assert_eq!((error.line, error.column), (line, column), "{:?}", text)
+                }
+                other => panic!("unexpected {:?} for {:?}", other, text),
+            }
+        }
+    }
+
+    #[test]
+    fn workspace_file_parses() {
+        let config = Config::load(workspace_file()).unwrap();
+        assert_eq!(config.params(15, Some("in.test")).get("row"), Some("10"));
+    }
+}
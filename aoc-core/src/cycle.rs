@@ -0,0 +1,138 @@
+//! Finding where a simulation starts repeating, to skip ahead to far-off steps.
+//!
+//! Feed [`CycleDetector::push`] a fingerprint of the state after each step
+//! along with the number being tracked (a height, a score). Once a fingerprint
+//! comes round again the states from then on repeat with a fixed period, and
+//! the tracked number is assumed to grow by the same amount every period.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The state after `start` steps is the same as after `start + period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CycleDetector<K> {
+    seen: HashMap<K, usize>,
+    metrics: Vec<i64>,
+    cycle: Option<Cycle>,
+}
+
+impl<K: Eq + Hash> Default for CycleDetector<K> {
+    fn default() -> Self {
+        CycleDetector {
+            seen: HashMap::new(),
+            metrics: Vec::new(),
+            cycle: None,
+        }
+    }
+}
+
+impl<K: Eq + Hash> CycleDetector<K> {
+    pub fn new() -> Self {
+        CycleDetector::default()
+    }
+
+    /// Record the state after the next step, starting with the initial state
+    /// as step 0. Returns the cycle once one is found; later calls do nothing.
+    pub fn push(&mut self, key: K, metric: i64) -> Option<Cycle> {
+        if self.cycle.is_none() {
+            let step = self.metrics.len();
+            self.metrics.push(metric);
+            if let Some(&start) = self.seen.get(&key) {
+                self.cycle = Some(Cycle {
+                    start,
+                    period: step - start,
+                });
+            } else {
+                self.seen.insert(key, step);
+            }
+        }
+        self.cycle
+    }
+
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    /// Steps recorded so far.
+    pub fn steps(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// The metric after `step` steps: recorded if it has been, extrapolated
+    /// from the cycle if one was found, otherwise unknown.
+    pub fn metric_at(&self, step: usize) -> Option<i64> {
+        if let Some(&metric) = self.metrics.get(step) {
+            return Some(metric);
+        }
+        let Cycle { start, period } = self.cycle?;
+        let gain = self.metrics[start + period] - self.metrics[start];
+        let (periods, rest) = ((step - start) / period, (step - start) % period);
+        Some(self.metrics[start + rest] + periods as i64 * gain)
+    }
+}
+
+/// Run `step` on `state` until `target` steps are done or a cycle shows up,
+/// and return `metric` after `target` steps.
+pub fn extrapolate<S, K: Eq + Hash>(
+    mut state: S,
+    target: usize,
+    mut step: impl FnMut(&mut S),
+    mut key: impl FnMut(&S) -> K,
+    mut metric: impl FnMut(&S) -> i64,
+) -> i64 {
+    let mut detector = CycleDetector::new();
+    loop {
+        let found = detector.cycle().is_some();
+        if let Some(cycle) = detector.push(key(&state), metric(&state)) {
+            if !found {
+                crate::debug!("cycle found", start = cycle.start, period = cycle.period);
+            }
+        }
+        if let Some(value) = detector.metric_at(target) {
+            return value;
+        }
+        step(&mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_cycle() {
+        // 0, 1, 2, then 3 4 5 6 repeating; the metric is the sum so far.
+        let next = |s: u32| if s == 6 { 3 } else { s + 1 };
+        let mut detector = CycleDetector::new();
+        let (mut state, mut sum) = (0, 0);
+        while detector.push(state, sum).is_none() {
+            state = next(state);
+            sum += state as i64;
+        }
+        assert_eq!(detector.cycle(), Some(Cycle { start: 3, period: 4 }));
+        assert_eq!(detector.steps(), 8);
+
+        let (mut state, mut brute) = (0, vec![0]);
+        for _ in 0..50 {
+            state = next(state);
+            brute.push(brute.last().unwrap() + state as i64);
+        }
+        for (n, &want) in brute.iter().enumerate() {
+            assert_eq!(detector.metric_at(n), Some(want), "step {}", n);
+        }
+        assert_eq!(detector.metric_at(1_000_000_003), Some(6 + 18 * 250_000_000));
+    }
+
+    #[test]
+    fn no_cycle_needed() {
+        let value = extrapolate(0u64, 10, |s| *s += 1, |s| *s, |s| *s as i64 * 2);
+        assert_eq!(value, 20);
+        assert_eq!(CycleDetector::<u8>::new().metric_at(0), None);
+    }
+}
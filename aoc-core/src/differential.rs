@@ -0,0 +1,218 @@
+//! Running two implementations of the same thing side by side on generated
+//! inputs, to find out where they disagree.
+//!
+//! [`find`] goes through the scales from small to large, trying every seed at
+//! each, so the first disagreement it comes across is already a small one. It
+//! then drops lines from that input for as long as the two still disagree, and
+//! reports what is left. A variant that panics disagrees with one that does
+//! not; the panic message stands in for its answer.
+
+use std::any::Any;
+use std::fmt::{self, Debug};
+use std::ops::{Range, RangeInclusive};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::gen::Rng;
+use crate::input::Input;
+
+/// What a variant came up with: its answer, or the message it panicked with.
+pub type Outcome<T> = Result<T, String>;
+
+/// The smallest input found on which the two variants disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence<T> {
+    pub seed: u64,
+    pub scale: usize,
+    /// The generated input with every line that was not needed removed.
+    pub input: String,
+    pub left: Outcome<T>,
+    pub right: Outcome<T>,
+}
+
+impl<T: Debug> fmt::Display for Divergence<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "seed {} at scale {}, shrunk to {} lines:",
+            self.seed,
+            self.scale,
+            self.input.lines().count()
+        )?;
+        for line in self.input.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        writeln!(f, "left:  {}", describe(&self.left))?;
+        write!(f, "right: {}", describe(&self.right))
+    }
+}
+
+fn describe<T: Debug>(outcome: &Outcome<T>) -> String {
+    match outcome {
+        Ok(answer) => format!("{:?}", answer),
+        Err(message) => format!("panicked: {}", message),
+    }
+}
+
+/// Compare `left` and `right` on an input from `generate` for every scale in
+/// `scales` and every seed in `seeds`, smallest scale first. Inputs that
+/// `parse` turns down (`None`) are skipped, which is also how shrinking keeps
+/// to inputs that still make sense.
+pub fn find<P, T: PartialEq>(
+    seeds: Range<u64>,
+    scales: RangeInclusive<usize>,
+    generate: impl Fn(&mut Rng, usize) -> String,
+    parse: impl Fn(&Input) -> Option<P>,
+    left: impl Fn(&P) -> T,
+    right: impl Fn(&P) -> T,
+) -> Option<Divergence<T>> {
+    let compare = |text: &str| -> Option<(Outcome<T>, Outcome<T>)> {
+        let parsed = parse(&Input::from(text))?;
+        let (l, r) = (run(|| left(&parsed)), run(|| right(&parsed)));
+        let differ = match (&l, &r) {
+            (Ok(a), Ok(b)) => a != b,
+            (Err(_), Err(_)) => false,
+            _ => true,
+        };
+        differ.then_some((l, r))
+    };
+    for scale in scales {
+        for seed in seeds.clone() {
+            let text = generate(&mut Rng::new(seed), scale);
+            let Some(outcome) = compare(&text) else {
+                continue;
+            };
+            let (input, (left, right)) = shrink(&text, outcome, compare);
+            return Some(Divergence {
+                seed,
+                scale,
+                input,
+                left,
+                right,
+            });
+        }
+    }
+    None
+}
+
+/// Like [`find`], but panics with the report if the variants disagree.
+pub fn check<P, T: PartialEq + Debug>(
+    seeds: Range<u64>,
+    scales: RangeInclusive<usize>,
+    generate: impl Fn(&mut Rng, usize) -> String,
+    parse: impl Fn(&Input) -> Option<P>,
+    left: impl Fn(&P) -> T,
+    right: impl Fn(&P) -> T,
+) {
+    if let Some(divergence) = find(seeds, scales, generate, parse, left, right) {
+        panic!("the two variants disagree on {}", divergence);
+    }
+}
+
+fn run<T>(variant: impl FnOnce() -> T) -> Outcome<T> {
+    panic::catch_unwind(AssertUnwindSafe(variant)).map_err(|payload| message(&*payload))
+}
+
+fn message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(s) => s.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "a non-string panic".to_string()),
+    }
+}
+
+/// Remove runs of lines, halving the run length whenever nothing more can go,
+/// as long as `diverges` still holds on what is left.
+fn shrink<O>(text: &str, mut outcome: O, diverges: impl Fn(&str) -> Option<O>) -> (String, O) {
+    let join = |lines: &[&str]| lines.iter().map(|l| format!("{}\n", l)).collect::<String>();
+    let mut lines: Vec<&str> = text.lines().collect();
+    let mut chunk = lines.len().div_ceil(2).max(1);
+    loop {
+        let mut removed = false;
+        let mut start = 0;
+        while start < lines.len() {
+            let end = (start + chunk).min(lines.len());
+            let candidate = [&lines[..start], &lines[end..]].concat();
+            match diverges(&join(&candidate)) {
+                Some(o) => {
+                    lines = candidate;
+                    outcome = o;
+                    removed = true;
+                }
+                None => start += chunk,
+            }
+        }
+        if chunk == 1 && !removed {
+            return (join(&lines), outcome);
+        }
+        if !removed {
+            chunk /= 2;
+        }
+        chunk = chunk.min(lines.len()).max(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(rng: &mut Rng, scale: usize) -> String {
+        (0..scale)
+            .map(|_| format!("{}\n", rng.range(0..=9)))
+            .collect()
+    }
+
+    fn parse(input: &Input) -> Option<Vec<i64>> {
+        input.lines().map(|l| l.parse().ok()).collect()
+    }
+
+    #[test]
+    fn agreeing_variants() {
+        let found = find(
+            0..20,
+            1..=10,
+            numbers,
+            parse,
+            |v| v.iter().sum::<i64>(),
+            |v| v.iter().rev().sum::<i64>(),
+        );
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn shrinks_to_the_culprit() {
+        // Forgets about every 7 it sees
+        let found = find(
+            0..20,
+            1..=10,
+            numbers,
+            parse,
+            |v| v.iter().sum::<i64>(),
+            |v| v.iter().filter(|&&x| x != 7).sum::<i64>(),
+        )
+        .unwrap();
+        assert!(found.to_string().contains("shrunk to 1 lines"));
+        assert_eq!(found.input, "7\n");
+        assert_eq!((found.left, found.right), (Ok(7), Ok(0)));
+    }
+
+    #[test]
+    fn panics_count_as_disagreeing() {
+        let found = find(
+            0..20,
+            1..=10,
+            numbers,
+            parse,
+            |v| v.len(),
+            |v| {
+                assert!(v.len() < 3, "too long");
+                v.len()
+            },
+        )
+        .unwrap();
+        assert_eq!(found.scale, 3);
+        assert_eq!(found.input.lines().count(), 3);
+        assert_eq!(found.right, Err("too long".to_string()));
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use crate::input::Source;
+
+/// Errors returned by the shared helpers.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading `input` failed.
+    Io { input: Source, source: io::Error },
+    /// `input` is not valid UTF-8; `line` is the first line with bad bytes.
+    Utf8 { input: Source, line: usize },
+    /// Part of `input` could not be parsed.
+    Parse { input: Source, error: ParseError },
+    /// Puzzle parameter `key`, from the configuration file, is missing or
+    /// not what the day expects.
+    Param { input: Source, key: String, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { input, source } => write!(f, "{}: {}", input, source),
+            Error::Utf8 { input, line } => write!(f, "{}:{}: invalid UTF-8", input, line),
+            Error::Parse { input, error } => write!(f, "{}:{}", input, error),
+            Error::Param { input, key, message } => write!(
+                f,
+                "{}: parameter {:?} {}; set it in {}",
+                input,
+                key,
+                message,
+                crate::config::CONFIG_FILE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// `text`, found at `column` of `line` (both 1-based), could not be parsed.
+///
+/// Day parsers return this; [`Input::locate`](crate::Input::locate) ties it to
+/// the input it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    /// An error about the whole of `line`, whose text is `full`.
+    pub fn new(line: usize, full: &str, message: impl fmt::Display) -> Self {
+        ParseError {
+            line,
+            column: 1,
+            text: full.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// An error about `part` of `line`, whose text is `full`. `part` is best a
+    /// slice of `full`, so that the column is exact even if the same text
+    /// shows up earlier on the line.
+    pub fn at(line: usize, full: &str, part: &str, message: impl fmt::Display) -> Self {
+        ParseError {
+            line,
+            column: column_of(full, part),
+            text: part.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// 1-based character column at which `part` starts within `full`.
+fn column_of(full: &str, part: &str) -> usize {
+    let start = full.as_ptr() as usize;
+    let offset = (part.as_ptr() as usize)
+        .checked_sub(start)
+        .filter(|&o| o + part.len() <= full.len() && full.is_char_boundary(o))
+        .or_else(|| full.find(part))
+        .unwrap_or(0);
+    full[..offset].chars().count() + 1
+}
+
+/// Parse `part` of `line` (whose text is `full`) with `FromStr`.
+pub fn parse_at<T>(line: usize, full: &str, part: &str) -> std::result::Result<T, ParseError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    part.parse()
+        .map_err(|e| ParseError::at(line, full, part, e))
+}
+
+impl fmt::Display for ParseError {
+    /// `line:column: message: "text"`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {:?}",
+            self.line, self.column, self.message, self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns() {
+        let line = "move 3 from 3 to x";
+        let parts: Vec<&str> = line.split(' ').collect();
+        let err = parse_at::<usize>(7, line, parts[5]).unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (7, 18, "x"));
+        // A slice of the line is found where it is, not at the first match.
+        assert_eq!(ParseError::at(1, line, parts[3], "bad").column, 13);
+        // Other strings are searched for.
+        assert_eq!(ParseError::at(1, line, "from", "bad").column, 8);
+        assert_eq!(ParseError::at(1, "é=x", "x", "bad").column, 3);
+        assert_eq!(parse_at::<u8>(1, line, parts[1]), Ok(3));
+        assert_eq!(
+            ParseError::new(2, "??", "unknown op").to_string(),
+            "2:1: unknown op: \"??\""
+        );
+    }
+}
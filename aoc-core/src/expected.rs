@@ -0,0 +1,196 @@
+//! Known answers that the day tests assert against.
+//!
+//! Each day crate keeps an `answers.txt` next to its inputs:
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! [in.test]
+//! 1 = 24000
+//! 2 = 45000
+//!
+//! [in.1]
+//! 1 = CMZ
+//! 2 =
+//! | ##..##..
+//! | ###...##
+//! ```
+//!
+//! A section names an input file; each entry is a part and the answer's
+//! [`Display`](std::fmt::Display) form. Lines starting with `| ` continue the
+//! previous answer on a new line, which is how images are written. Parts
+//! without an entry are not checked.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::budget::Budget;
+use crate::config::Config;
+use crate::error::{Error, ParseError, Result};
+use crate::input::Input;
+use crate::solution::{Part, Solution};
+
+/// Name of the answers file in each day crate.
+pub const ANSWERS_FILE: &str = "answers.txt";
+
+/// Expected answers for one day, keyed by input file name and part.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expected {
+    answers: BTreeMap<(String, Part), String>,
+}
+
+impl Expected {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Expected::parse(&Input::from_path(path)?)
+    }
+
+    pub fn parse(input: &Input) -> Result<Self> {
+        let mut answers = BTreeMap::new();
+        let mut section: Option<&str> = None;
+        let mut last: Option<(String, Part)> = None;
+        for (i, line) in input.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix('|') {
+                let key = last.as_ref().ok_or_else(|| {
+                    input.parse_error(i + 1, line, "continuation without an answer")
+                })?;
+                let answer: &mut String = answers.get_mut(key).unwrap();
+                if !answer.is_empty() {
+                    answer.push('\n');
+                }
+                answer.push_str(rest.strip_prefix(' ').unwrap_or(rest));
+                continue;
+            }
+            if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                section = Some(name.trim());
+                last = None;
+                continue;
+            }
+            let (part, answer) = trimmed
+                .split_once('=')
+                .ok_or_else(|| input.parse_error(i + 1, line, "expected `<part> = <answer>`"))?;
+            let part: Part = part
+                .trim()
+                .parse()
+                .map_err(|e| input.parse_error(i + 1, line, e))?;
+            let name = section.ok_or_else(|| {
+                input.parse_error(i + 1, line, "answer before any [input] section")
+            })?;
+            let key = (name.to_string(), part);
+            if answers
+                .insert(key.clone(), answer.trim().to_string())
+                .is_some()
+            {
+                return Err(input.parse_error(i + 1, line, "duplicate answer"));
+            }
+            last = Some(key);
+        }
+        Ok(Expected { answers })
+    }
+
+    pub fn get(&self, input: &str, part: Part) -> Option<&str> {
+        self.answers
+            .get(&(input.to_string(), part))
+            .map(|s| s.as_str())
+    }
+
+    /// Input file names that have at least one answer, in order.
+    pub fn inputs(&self) -> Vec<&str> {
+        let mut inputs: Vec<&str> = self.answers.keys().map(|(name, _)| name.as_str()).collect();
+        inputs.dedup();
+        inputs
+    }
+}
+
+/// Time each part gets in [`check`] unless [`TIMEOUT_VAR`](crate::budget::TIMEOUT_VAR) says otherwise.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Test helper: solve `input` (relative to the crate directory) with `S` and
+/// assert every part against [`ANSWERS_FILE`]. A missing input file is
+/// reported as skipped rather than failing, since real inputs are personal.
+/// The input gets its parameters from the workspace [`config`](crate::config).
+/// Each part runs within [`CHECK_TIMEOUT`], so a solver that hangs fails its
+/// test instead of stalling the whole run.
+pub fn check<S: Solution>(input: &str) {
+    let expected = Expected::load(ANSWERS_FILE).unwrap_or_else(|e| panic!("{}", e));
+    let budget = Budget::from_env(Some(CHECK_TIMEOUT)).unwrap_or_else(|e| panic!("{}", e));
+    let text = match Input::from_path(input) {
+        Ok(text) => text,
+        Err(Error::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+            println!("skipped: day {} {} not found", S::DAY, input);
+            return;
+        }
+        Err(e) => panic!("{}", e),
+    };
+    let config = Config::workspace().unwrap_or_else(|e| panic!("{}", e));
+    let params = config.params(S::DAY, text.name());
+    let text = text.with_params(params);
+    let parsed = S::parse(&text).unwrap_or_else(|e| panic!("{}", e));
+
+    let mut checked = 0;
+    for part in Part::ALL {
+        let Some(want) = expected.get(input, part) else {
+            continue;
+        };
+        let got = budget.run(|| match part {
+            Part::One => S::part1(&parsed),
+            Part::Two => S::part2(&parsed),
+        });
+        assert_eq!(
+            got.to_string(),
+            want,
+            "day {} {} part {}",
+            S::DAY,
+            input,
+            part
+        );
+        checked += 1;
+    }
+    assert!(checked > 0, "{} has no answers for {}", ANSWERS_FILE, input);
+}
+
+/// Test helper: parse `text` with `S`, which should reject it, and return the
+/// error for the test to check where it points.
+pub fn parse_error<S: Solution>(text: &str) -> ParseError {
+    match S::parse(&Input::from(text)) {
+        Err(Error::Parse { error, .. }) => error,
+        Err(e) => panic!("day {}: expected a parse error, got {}", S::DAY, e),
+        Ok(_) => panic!("day {} accepted {:?}", S::DAY, text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sections() {
+        let input =
+            Input::from("# day 10\n[in.test]\n1 = 13140\n2 =\n| #.\n| .#\n\n[in.1]\n2 = CMZ\n");
+        let expected = Expected::parse(&input).unwrap();
+        assert_eq!(expected.get("in.test", Part::One), Some("13140"));
+        assert_eq!(expected.get("in.test", Part::Two), Some("#.\n.#"));
+        assert_eq!(expected.get("in.1", Part::One), None);
+        assert_eq!(expected.get("in.1", Part::Two), Some("CMZ"));
+        assert_eq!(expected.inputs(), vec!["in.1", "in.test"]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (text, line) in [
+            ("1 = 2\n", 1),
+            ("[a]\n1 = 2\n1 = 3\n", 3),
+            ("[a]\n\n3 = 1\n", 3),
+            ("[a]\n| x\n", 2),
+        ] {
+            match Expected::parse(&Input::from(text)) {
+                Err(Error::Parse { error, .. }) => assert_eq!(error.line, line, "{:?}", text),
+                other => panic!("unexpected {:?} for {:?}", other, text),
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Seeded random puzzle inputs, for fuzzing the parsers and timing solvers on
+//! inputs far bigger than the real ones.
+//!
+//! Days build their input in [`Solution::generate`](crate::Solution::generate)
+//! from an [`Rng`]; the same seed and size always give the same input, on
+//! every platform.
+
+use std::ops::RangeInclusive;
+
+/// SplitMix64: small, fast and good enough for made-up puzzle inputs.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`. Panics if `n` is 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "below(0)");
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Uniform in `range`. Panics if it is empty.
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (lo, hi) = range.into_inner();
+        assert!(lo <= hi, "empty range {}..={}", lo, hi);
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        (lo as i128 + ((self.next_u64() as u128 * span) >> 64) as i128) as i64
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.f64() < p
+    }
+
+    /// Panics if `items` is empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    /// `len` characters drawn from `letters`.
+    pub fn word(&mut self, len: usize, letters: RangeInclusive<char>) -> String {
+        let (lo, hi) = (*letters.start() as i64, *letters.end() as i64);
+        (0..len)
+            .map(|_| char::from_u32(self.range(lo..=hi) as u32).unwrap_or(*letters.start()))
+            .collect()
+    }
+}
+
+/// `n`, the length of one side of something with `dims` dimensions, grown so
+/// that its area or volume is `size` times bigger.
+pub fn scaled(n: usize, size: usize, dims: u32) -> usize {
+    (n as f64 * (size as f64).powf(1.0 / dims as f64)).round() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let draw = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..8).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(7), draw(7));
+        assert_ne!(draw(7), draw(8));
+        // Pinned, so that generated inputs stay the same from one build to the next.
+        assert_eq!(Rng::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = Rng::new(1);
+        let mut seen = [false; 5];
+        for _ in 0..1000 {
+            let v = rng.range(-2..=2);
+            seen[(v + 2) as usize] = true;
+            assert!(rng.below(3) < 3);
+            assert!((0.0..1.0).contains(&rng.f64()));
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert_eq!(rng.range(i64::MIN..=i64::MIN), i64::MIN);
+        rng.range(i64::MIN..=i64::MAX);
+
+        let mut items: Vec<u32> = (0..50).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+
+        let word = rng.word(6, 'a'..='c');
+        assert_eq!(word.len(), 6);
+        assert!(word.chars().all(|c| ('a'..='c').contains(&c)));
+        assert_eq!(scaled(100, 4, 2), 200);
+        assert_eq!(scaled(20, 8, 3), 40);
+    }
+}
@@ -0,0 +1,404 @@
+//! Points and compass directions.
+//!
+//! Coordinates follow the screen and [`Grid`](crate::Grid) convention: `x`
+//! grows to the right and `y` grows downwards, so north is towards smaller
+//! `y`. A point `(x, y)` is the grid cell `(row, column) = (y, x)`.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::grid::Pos;
+
+/// Integer types that points can be built from.
+pub trait Num:
+    Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+/// [`Num`]s with a negative, needed for directions and neighbours.
+pub trait Signed: Num + Neg<Output = Self> {}
+
+macro_rules! num {
+    ($($t:ty),*) => {$(
+        impl Num for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+        }
+    )*};
+}
+
+num!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl Signed for i8 {}
+impl Signed for i16 {}
+impl Signed for i32 {}
+impl Signed for i64 {}
+impl Signed for isize {}
+
+fn abs_diff<T: Num>(a: T, b: T) -> T {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+fn larger<T: Num>(a: T, b: T) -> T {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Num> Point2<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Point2 { x, y }
+    }
+
+    pub fn manhattan(self, other: Self) -> T {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y)
+    }
+
+    /// Distance when diagonal steps are allowed.
+    pub fn chebyshev(self, other: Self) -> T {
+        larger(abs_diff(self.x, other.x), abs_diff(self.y, other.y))
+    }
+}
+
+impl<T: Signed> Point2<T> {
+    /// One step in `dir`.
+    pub fn step(self, dir: Dir8) -> Self {
+        self + dir.delta()
+    }
+
+    pub fn neighbours4(self) -> [Self; 4] {
+        Dir4::ALL.map(|d| self + d.delta())
+    }
+
+    pub fn neighbours8(self) -> [Self; 8] {
+        Dir8::ALL.map(|d| self + d.delta())
+    }
+}
+
+impl<T: Num + TryFrom<usize>> Point2<T> {
+    /// The point for a grid cell, if it fits in `T`.
+    pub fn from_pos((row, col): Pos) -> Option<Self> {
+        Some(Point2::new(col.try_into().ok()?, row.try_into().ok()?))
+    }
+}
+
+impl<T: Num + TryInto<usize>> Point2<T> {
+    /// The grid cell for this point, if neither coordinate is negative.
+    pub fn to_pos(self) -> Option<Pos> {
+        Some((self.y.try_into().ok()?, self.x.try_into().ok()?))
+    }
+}
+
+impl<T> From<(T, T)> for Point2<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Point2 { x, y }
+    }
+}
+
+impl<T: Num> Add for Point2<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Num> Sub for Point2<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Point2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Num> AddAssign for Point2<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Num> SubAssign for Point2<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<T: Num> Mul<T> for Point2<T> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        Point2::new(self.x * k, self.y * k)
+    }
+}
+
+impl<T: Signed> Neg for Point2<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point2::new(-self.x, -self.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Num> Point3<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Point3 { x, y, z }
+    }
+
+    pub fn manhattan(self, other: Self) -> T {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y) + abs_diff(self.z, other.z)
+    }
+
+    pub fn chebyshev(self, other: Self) -> T {
+        larger(
+            larger(abs_diff(self.x, other.x), abs_diff(self.y, other.y)),
+            abs_diff(self.z, other.z),
+        )
+    }
+}
+
+impl<T: Signed> Point3<T> {
+    /// The six points sharing a face with this one.
+    pub fn neighbours6(self) -> [Self; 6] {
+        let (o, l) = (T::ZERO, T::ONE);
+        [
+            Point3::new(l, o, o),
+            Point3::new(-l, o, o),
+            Point3::new(o, l, o),
+            Point3::new(o, -l, o),
+            Point3::new(o, o, l),
+            Point3::new(o, o, -l),
+        ]
+        .map(|d| self + d)
+    }
+}
+
+impl<T> From<(T, T, T)> for Point3<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Point3 { x, y, z }
+    }
+}
+
+impl<T: Num> Add for Point3<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Num> Sub for Point3<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Point3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Num> AddAssign for Point3<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Num> SubAssign for Point3<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<T: Num> Mul<T> for Point3<T> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        Point3::new(self.x * k, self.y * k, self.z * k)
+    }
+}
+
+impl<T: Signed> Neg for Point3<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// The four orthogonal directions, in clockwise order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dir4 {
+    N,
+    E,
+    S,
+    W,
+}
+
+impl Dir4 {
+    pub const ALL: [Dir4; 4] = [Dir4::N, Dir4::E, Dir4::S, Dir4::W];
+
+    /// Quarter turns clockwise from north.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn turn_right(self) -> Self {
+        Dir4::ALL[(self.index() + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Self {
+        Dir4::ALL[(self.index() + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Self {
+        Dir4::ALL[(self.index() + 2) % 4]
+    }
+
+    pub fn delta<T: Signed>(self) -> Point2<T> {
+        Dir8::from(self).delta()
+    }
+
+    /// `(row, column)` step for [`Grid::offset`](crate::Grid::offset).
+    pub fn offset(self) -> (isize, isize) {
+        Dir8::from(self).offset()
+    }
+}
+
+/// The four orthogonal and four diagonal directions, clockwise from north.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dir8 {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Dir8 {
+    pub const ALL: [Dir8; 8] = [
+        Dir8::N,
+        Dir8::NE,
+        Dir8::E,
+        Dir8::SE,
+        Dir8::S,
+        Dir8::SW,
+        Dir8::W,
+        Dir8::NW,
+    ];
+
+    /// Eighth turns clockwise from north.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Turn 45 degrees clockwise.
+    pub fn rotate_cw(self) -> Self {
+        Dir8::ALL[(self.index() + 1) % 8]
+    }
+
+    /// Turn 45 degrees counter-clockwise.
+    pub fn rotate_ccw(self) -> Self {
+        Dir8::ALL[(self.index() + 7) % 8]
+    }
+
+    pub fn reverse(self) -> Self {
+        Dir8::ALL[(self.index() + 4) % 8]
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self.index() % 2 == 1
+    }
+
+    /// This direction and its two diagonal neighbours, e.g. N, NE and NW.
+    pub fn fan(self) -> [Dir8; 3] {
+        [self, self.rotate_cw(), self.rotate_ccw()]
+    }
+
+    pub fn delta<T: Signed>(self) -> Point2<T> {
+        let (o, l) = (T::ZERO, T::ONE);
+        match self {
+            Dir8::N => Point2::new(o, -l),
+            Dir8::NE => Point2::new(l, -l),
+            Dir8::E => Point2::new(l, o),
+            Dir8::SE => Point2::new(l, l),
+            Dir8::S => Point2::new(o, l),
+            Dir8::SW => Point2::new(-l, l),
+            Dir8::W => Point2::new(-l, o),
+            Dir8::NW => Point2::new(-l, -l),
+        }
+    }
+
+    /// `(row, column)` step for [`Grid::offset`](crate::Grid::offset).
+    pub fn offset(self) -> (isize, isize) {
+        let d = self.delta::<isize>();
+        (d.y, d.x)
+    }
+}
+
+impl From<Dir4> for Dir8 {
+    fn from(dir: Dir4) -> Self {
+        Dir8::ALL[dir.index() * 2]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let (a, b) = (Point2::new(1, -2), Point2::new(-3, 5));
+        assert_eq!(a.manhattan(b), 11);
+        assert_eq!(a.chebyshev(b), 7);
+        assert_eq!(a + b, Point2::new(-2, 3));
+        assert_eq!(-(a - b) * 2, Point2::new(-8, 14));
+        assert_eq!(Point2::<u8>::new(2, 9).manhattan(Point2::new(5, 1)), 11);
+        assert_eq!(Point3::new(1, 2, 3).manhattan(Point3::new(0, 0, 0)), 6);
+        assert_eq!(Point3::new(1, 2, 3).neighbours6().len(), 6);
+    }
+
+    #[test]
+    fn turning() {
+        assert_eq!(Dir4::N.turn_right(), Dir4::E);
+        assert_eq!(Dir4::N.turn_left(), Dir4::W);
+        assert_eq!(Dir4::W.reverse(), Dir4::E);
+        assert_eq!(Dir8::NW.rotate_cw(), Dir8::N);
+        assert_eq!(Dir8::N.rotate_ccw(), Dir8::NW);
+        assert_eq!(Dir8::S.fan(), [Dir8::S, Dir8::SW, Dir8::SE]);
+        assert_eq!(Dir8::from(Dir4::S), Dir8::S);
+        assert!(Dir8::SE.is_diagonal());
+        assert_eq!(Dir4::N.delta::<i32>(), Point2::new(0, -1));
+        assert_eq!(Dir8::SW.offset(), (1, -1));
+    }
+
+    #[test]
+    fn grid_positions() {
+        let p = Point2::<i64>::from_pos((3, 7)).unwrap();
+        assert_eq!(p, Point2::new(7, 3));
+        assert_eq!(p.to_pos(), Some((3, 7)));
+        assert_eq!(Point2::new(-1i64, 0).to_pos(), None);
+        let grid = crate::Grid::new(4, 4, ());
+        assert_eq!(grid.offset((0, 0), Dir4::E.offset()), Some((0, 1)));
+        assert_eq!(grid.offset((0, 0), Dir4::N.offset()), None);
+    }
+}
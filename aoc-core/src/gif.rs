@@ -0,0 +1,227 @@
+//! A minimal animated GIF encoder: one global colour table, full-size frames,
+//! LZW compression and a looping animation.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::render::Rgb;
+
+/// Largest code LZW may use; the table is reset once it is full.
+const MAX_CODE: u16 = 4095;
+
+/// Packs variable-width codes least significant bit first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: Vec::new(),
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, size: u32) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+/// LZW-compress colour indices that all fit in `min_size` bits.
+pub(crate) fn lzw(min_size: u32, indices: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << min_size;
+    let end = clear + 1;
+    let mut out = BitWriter::new();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut size = min_size + 1;
+    let mut next = end + 1;
+    out.write(clear, size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        out.write(end, size);
+        return out.finish();
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        out.write(prefix, size);
+        if next <= MAX_CODE {
+            // The decoder widens its codes once its table reaches the current
+            // limit, which it does one code after we do.
+            if next == 1 << size {
+                size += 1;
+            }
+            table.insert((prefix, index), next);
+            next += 1;
+        } else {
+            out.write(clear, size);
+            table.clear();
+            size = min_size + 1;
+            next = end + 1;
+        }
+        prefix = index as u16;
+    }
+    out.write(prefix, size);
+    out.write(end, size);
+    out.finish()
+}
+
+/// Write `frames`, each `width * height` indices into `colours`, as a looping
+/// animation showing each frame for `delay` hundredths of a second.
+pub fn write(
+    mut w: impl Write,
+    width: u16,
+    height: u16,
+    colours: &[Rgb],
+    frames: &[Vec<u8>],
+    delay: u16,
+) -> io::Result<()> {
+    assert!(
+        (1..=256).contains(&colours.len()),
+        "a GIF needs 1 to 256 colours"
+    );
+    // The colour table holds a power of two entries, at least 4 so that the
+    // LZW minimum code size is at least 2.
+    let table_bits = (colours.len().max(4) as u32)
+        .next_power_of_two()
+        .trailing_zeros();
+
+    w.write_all(b"GIF89a")?;
+    w.write_all(&width.to_le_bytes())?;
+    w.write_all(&height.to_le_bytes())?;
+    // Global colour table present, 8 bits per primary, its size.
+    w.write_all(&[0x80 | 0x70 | (table_bits as u8 - 1), 0, 0])?;
+    for i in 0..1 << table_bits {
+        w.write_all(colours.get(i).unwrap_or(&[0, 0, 0]))?;
+    }
+    // Loop forever.
+    w.write_all(&[0x21, 0xff, 11])?;
+    w.write_all(b"NETSCAPE2.0")?;
+    w.write_all(&[3, 1, 0, 0, 0])?;
+
+    for frame in frames {
+        assert_eq!(frame.len(), width as usize * height as usize);
+        // Graphic control: no disposal, the delay, no transparency.
+        w.write_all(&[0x21, 0xf9, 4, 0])?;
+        w.write_all(&delay.to_le_bytes())?;
+        w.write_all(&[0, 0])?;
+        // Image descriptor covering the whole screen.
+        w.write_all(&[0x2c, 0, 0, 0, 0])?;
+        w.write_all(&width.to_le_bytes())?;
+        w.write_all(&height.to_le_bytes())?;
+        w.write_all(&[0, table_bits as u8])?;
+        for block in lzw(table_bits, frame).chunks(255) {
+            w.write_all(&[block.len() as u8])?;
+            w.write_all(block)?;
+        }
+        w.write_all(&[0])?;
+    }
+    w.write_all(&[0x3b])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Straight from the GIF specification, independent of the encoder.
+    fn unlzw(min_size: u32, data: &[u8]) -> Vec<u8> {
+        let (clear, end) = (1u16 << min_size, (1u16 << min_size) + 1);
+        let reset = || -> Vec<Vec<u8>> {
+            let mut table: Vec<Vec<u8>> = (0..clear).map(|i| vec![i as u8]).collect();
+            table.extend([vec![], vec![]]);
+            table
+        };
+        let mut table = reset();
+        let mut size = min_size + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        let (mut acc, mut bits, mut bytes) = (0u32, 0u32, data.iter());
+        loop {
+            while bits < size {
+                acc |= (*bytes.next().expect("ran out of data") as u32) << bits;
+                bits += 8;
+            }
+            let code = (acc & ((1 << size) - 1)) as u16;
+            acc >>= size;
+            bits -= size;
+            if code == clear {
+                table = reset();
+                size = min_size + 1;
+                prev = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code as usize), &prev) {
+                (Some(e), _) => e.clone(),
+                (None, Some(p)) => [p.clone(), vec![p[0]]].concat(),
+                (None, None) => panic!("bad first code {}", code),
+            };
+            out.extend(&entry);
+            if let Some(p) = prev {
+                if table.len() <= MAX_CODE as usize {
+                    table.push([p, vec![entry[0]]].concat());
+                }
+            }
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let mut seed = 7u32;
+        let mut noise = || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as u8
+        };
+        let cases: Vec<(u32, Vec<u8>)> = vec![
+            (2, vec![]),
+            (2, vec![1]),
+            (2, vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            (2, (0..5000).map(|i| (i % 7 % 4) as u8).collect()),
+            (8, (0..50_000).map(|_| noise()).collect()),
+            (3, (0..50_000).map(|_| noise() % 8).collect()),
+        ];
+        for (min_size, indices) in cases {
+            let packed = lzw(min_size, &indices);
+            assert_eq!(unlzw(min_size, &packed), indices, "min size {}", min_size);
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let mut out = Vec::new();
+        let colours = [[0, 0, 0], [255, 255, 255]];
+        write(&mut out, 2, 1, &colours, &[vec![0, 1], vec![1, 0]], 10).unwrap();
+        assert_eq!(&out[..6], b"GIF89a");
+        assert_eq!(&out[6..10], &[2, 0, 1, 0]);
+        // Four colours, padded with black.
+        assert_eq!(out[10], 0xf1);
+        assert_eq!(&out[13..25], &[0, 0, 0, 255, 255, 255, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(out.iter().filter(|&&b| b == 0x2c).count(), 2);
+        assert_eq!(out.last(), Some(&0x3b));
+    }
+}
@@ -0,0 +1,299 @@
+//! Rectangular grids parsed from character maps.
+//!
+//! Cells are addressed by `(row, column)`, with `(0, 0)` the top-left
+//! character of the map, matching how the puzzles lay out their input.
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::error::{ParseError, Result};
+use crate::input::Input;
+
+/// `(row, column)` of a cell.
+pub type Pos = (usize, usize);
+
+/// Steps to the four orthogonal neighbours: up, down, left, right.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Steps to all eight neighbours, clockwise from up.
+pub const ALL_AROUND: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Parse the character map at the start of `input`, up to the first blank
+    /// line, mapping each character with `cell`. Rows shorter than the widest
+    /// one are padded as if they ended in spaces. A character that `cell`
+    /// rejects is reported with its line.
+    pub fn parse(input: &Input, mut cell: impl FnMut(char) -> Option<T>) -> Result<Self> {
+        let lines: Vec<&str> = input.lines().take_while(|l| !l.is_empty()).collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            let padded = line.chars().chain(std::iter::repeat(' ')).take(width);
+            for (j, c) in padded.enumerate() {
+                let value = cell(c).ok_or_else(|| {
+                    input.locate(ParseError {
+                        line: i + 1,
+                        column: j + 1,
+                        text: c.to_string(),
+                        message: "unexpected character".to_string(),
+                    })
+                })?;
+                cells.push(value);
+            }
+        }
+        Ok(Grid {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+
+    /// Build a grid by calling `f` for every position, row by row.
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|r| (0..width).map(move |c| (r, c)))
+            .map(&mut f)
+            .collect();
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (r, c): Pos) -> bool {
+        r < self.height && c < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        match self.contains(pos) {
+            true => Some(&self.cells[pos.0 * self.width + pos.1]),
+            false => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        match self.contains(pos) {
+            true => Some(&mut self.cells[pos.0 * self.width + pos.1]),
+            false => None,
+        }
+    }
+
+    /// The cell one `step` away from `pos`, if it is inside the grid.
+    pub fn offset(&self, (r, c): Pos, (dr, dc): (isize, isize)) -> Option<Pos> {
+        let pos = (r.checked_add_signed(dr)?, c.checked_add_signed(dc)?);
+        self.contains(pos).then_some(pos)
+    }
+
+    /// Every position, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |r| (0..width).map(move |c| (r, c)))
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// Up, down, left and right of `pos`, skipping those outside the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ORTHOGONAL
+            .into_iter()
+            .filter_map(move |step| self.offset(pos, step))
+    }
+
+    /// All eight cells around `pos`, skipping those outside the grid.
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ALL_AROUND
+            .into_iter()
+            .filter_map(move |step| self.offset(pos, step))
+    }
+
+    /// Cells walking from `pos` (exclusive) in `step`s until the edge, e.g.
+    /// `(0, -1)` looks left along the row and `(1, 1)` down a diagonal.
+    pub fn ray(&self, pos: Pos, step: (isize, isize)) -> impl Iterator<Item = Pos> + '_ {
+        std::iter::successors(self.offset(pos, step), move |&p| self.offset(p, step))
+    }
+
+    pub fn row(&self, r: usize) -> &[T] {
+        &self.cells[r * self.width..(r + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1)).take(self.height)
+    }
+
+    pub fn column(&self, c: usize) -> impl Iterator<Item = &T> {
+        self.cells
+            .iter()
+            .skip(c)
+            .step_by(self.width.max(1))
+            .take(self.height)
+    }
+
+    /// Positions of the down-right diagonal through `pos`, top to bottom.
+    pub fn diagonal(&self, pos: Pos) -> Vec<Pos> {
+        let back = pos.0.min(pos.1);
+        let start = (pos.0 - back, pos.1 - back);
+        std::iter::once(start)
+            .chain(self.ray(start, (1, 1)))
+            .collect()
+    }
+
+    /// Positions of the down-left diagonal through `pos`, top to bottom.
+    pub fn anti_diagonal(&self, pos: Pos) -> Vec<Pos> {
+        let back = pos.0.min(self.width - 1 - pos.1);
+        let start = (pos.0 - back, pos.1 + back);
+        std::iter::once(start)
+            .chain(self.ray(start, (1, -1)))
+            .collect()
+    }
+
+    /// First position, row by row, whose cell matches.
+    pub fn find(&self, mut pred: impl FnMut(&T) -> bool) -> Option<Pos> {
+        self.iter().find(|(_, t)| pred(t)).map(|(pos, _)| pos)
+    }
+
+    /// Every position whose cell matches, row by row.
+    pub fn find_all(&self, mut pred: impl FnMut(&T) -> bool) -> Vec<Pos> {
+        self.iter()
+            .filter(|(_, t)| pred(t))
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Text with one line per row, drawing each cell with `f`.
+    pub fn render(&self, mut f: impl FnMut(&T) -> char) -> String {
+        self.rows()
+            .map(|row| row.iter().map(&mut f).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    /// Panics if `pos` is outside the grid.
+    fn index(&self, pos: Pos) -> &T {
+        assert!(
+            self.contains(pos),
+            "{:?} is outside a {}x{} grid",
+            pos,
+            self.width,
+            self.height
+        );
+        &self.cells[pos.0 * self.width + pos.1]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        assert!(
+            self.contains(pos),
+            "{:?} is outside a {}x{} grid",
+            pos,
+            self.width,
+            self.height
+        );
+        &mut self.cells[pos.0 * self.width + pos.1]
+    }
+}
+
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(|&c| c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Grid<char> {
+        Grid::parse(&Input::from("Sab\ncdE\nfg\n\nnot part of the map\n"), Some).unwrap()
+    }
+
+    #[test]
+    fn parse_and_render() {
+        let grid = sample();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid[(2, 2)], ' ');
+        assert_eq!(grid.find(|&c| c == 'S'), Some((0, 0)));
+        assert_eq!(grid.find(|&c| c == 'E'), Some((1, 2)));
+        assert_eq!(grid.to_string(), "Sab\ncdE\nfg ");
+
+        let err = Grid::parse(&Input::from("12\n3x\n"), |c| c.to_digit(10)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "<memory>:2:2: unexpected character: \"x\""
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = sample();
+        let mut n4: Vec<Pos> = grid.neighbours4((0, 0)).collect();
+        n4.sort();
+        assert_eq!(n4, vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 8);
+        assert_eq!(grid.neighbours8((2, 2)).count(), 3);
+    }
+
+    #[test]
+    fn lines() {
+        let grid = sample();
+        assert_eq!(grid.row(1), &['c', 'd', 'E']);
+        assert_eq!(grid.rows().count(), 3);
+        assert_eq!(grid.column(1).collect::<String>(), "adg");
+        assert_eq!(
+            grid.ray((2, 0), (-1, 1)).collect::<Vec<Pos>>(),
+            vec![(1, 1), (0, 2)]
+        );
+        assert_eq!(grid.diagonal((1, 2)), vec![(0, 1), (1, 2)]);
+        assert_eq!(grid.anti_diagonal((1, 1)), vec![(0, 2), (1, 1), (2, 0)]);
+    }
+}
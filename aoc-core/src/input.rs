@@ -0,0 +1,294 @@
+//! Loading puzzle input.
+//!
+//! An [`Input`] holds the whole buffer and remembers where it came from, so
+//! errors raised while reading or parsing it can name the file and line.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::config::Params;
+use crate::error::{Error, ParseError, Result};
+
+/// Where an [`Input`] was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Path(PathBuf),
+    Stdin,
+    Memory,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Path(path) => write!(f, "{}", path.display()),
+            Source::Stdin => write!(f, "<stdin>"),
+            Source::Memory => write!(f, "<memory>"),
+        }
+    }
+}
+
+/// A run of non-blank lines, as separated by blank lines in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block<'a> {
+    /// 1-based line number of the first line in the block.
+    pub first_line: usize,
+    pub lines: Vec<&'a str>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Input {
+    source: Source,
+    text: String,
+    params: Params,
+}
+
+impl Input {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let source = Source::Path(path.as_ref().to_path_buf());
+        match fs::read(path.as_ref()) {
+            Ok(bytes) => Input::from_bytes(source, bytes),
+            Err(e) => Err(Error::Io { input: source, source: e }),
+        }
+    }
+
+    pub fn from_stdin() -> Result<Self> {
+        let mut bytes = Vec::new();
+        match io::stdin().lock().read_to_end(&mut bytes) {
+            Ok(_) => Input::from_bytes(Source::Stdin, bytes),
+            Err(e) => Err(Error::Io { input: Source::Stdin, source: e }),
+        }
+    }
+
+    pub fn from_bytes(source: Source, bytes: Vec<u8>) -> Result<Self> {
+        match String::from_utf8(bytes) {
+            Ok(text) => Ok(Input {
+                source,
+                text,
+                params: Params::new(),
+            }),
+            Err(e) => {
+                let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+                let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+                Err(Error::Utf8 { input: source, line })
+            }
+        }
+    }
+
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// The file name this was read from, which is how the configuration
+    /// file refers to inputs.
+    pub fn name(&self) -> Option<&str> {
+        match &self.source {
+            Source::Path(path) => path.file_name()?.to_str(),
+            _ => None,
+        }
+    }
+
+    /// Attach the puzzle parameters that go with this input, see [`config`](crate::config).
+    pub fn with_params(mut self, params: Params) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Puzzle parameter `key`, parsed with `FromStr`.
+    pub fn param<T>(&self, key: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let error = |message: String| Error::Param {
+            input: self.source.clone(),
+            key: key.to_string(),
+            message,
+        };
+        let value = self.params.get(key).ok_or_else(|| error("is not set".to_string()))?;
+        value
+            .parse()
+            .map_err(|e| error(format!("is {:?}: {}", value, e)))
+    }
+
+    /// Puzzle parameter `key`, or `default` when it is not set. A value that
+    /// is set but does not parse is still an error.
+    pub fn param_or<T>(&self, key: &str, default: T) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        match self.params.get(key) {
+            Some(_) => self.param(key),
+            None => Ok(default),
+        }
+    }
+
+    /// The whole buffer, untouched.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Lines without their terminators (`\n` or `\r\n`).
+    pub fn lines(&self) -> std::str::Lines<'_> {
+        self.text.lines()
+    }
+
+    /// Lines with their 1-based line numbers, for reporting [`ParseError`]s.
+    pub fn numbered_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.lines().enumerate().map(|(i, line)| (i + 1, line))
+    }
+
+    /// Groups of lines separated by one or more blank lines.
+    pub fn blocks(&self) -> Vec<Block<'_>> {
+        let mut blocks = Vec::new();
+        let mut cur: Option<Block> = None;
+        for (i, line) in self.lines().enumerate() {
+            match line.trim().is_empty() {
+                true => blocks.extend(cur.take()),
+                false => cur
+                    .get_or_insert(Block { first_line: i + 1, lines: Vec::new() })
+                    .lines
+                    .push(line),
+            }
+        }
+        blocks.extend(cur);
+        blocks
+    }
+
+    /// Parse every line with `FromStr`, reporting the first line that fails.
+    pub fn parse_lines<T>(&self) -> Result<Vec<T>>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.numbered_lines()
+            .map(|(n, line)| {
+                crate::error::parse_at(n, line, line.trim()).map_err(|e| self.locate(e))
+            })
+            .collect()
+    }
+
+    /// Build an [`Error::Parse`] for `text` on 1-based `line` of this input.
+    pub fn parse_error(&self, line: usize, text: &str, message: impl fmt::Display) -> Error {
+        self.locate(ParseError::new(line, text, message))
+    }
+
+    /// The line `error` is about, with the offending text underlined:
+    ///
+    /// ```text
+    ///    3 | 498,4 -> 49x,6
+    ///      |          ^^^
+    /// ```
+    pub fn excerpt(&self, error: &ParseError) -> Option<String> {
+        let line = self.lines().nth(error.line.checked_sub(1)?)?;
+        let before = error.column.saturating_sub(1).min(line.chars().count());
+        let width = error.text.chars().count().clamp(1, line.chars().count() - before + 1);
+        let number = error.line.to_string();
+        Some(format!(
+            "{} | {}\n{} | {}{}",
+            number,
+            line,
+            " ".repeat(number.len()),
+            " ".repeat(before),
+            "^".repeat(width)
+        ))
+    }
+
+    /// Tie `error`, from parsing this input, to where the input came from.
+    pub fn locate(&self, error: ParseError) -> Error {
+        Error::Parse {
+            input: self.source.clone(),
+            error,
+        }
+    }
+}
+
+impl From<&str> for Input {
+    fn from(text: &str) -> Self {
+        Input::from(text.to_string())
+    }
+}
+
+impl From<String> for Input {
+    fn from(text: String) -> Self {
+        Input {
+            source: Source::Memory,
+            text,
+            params: Params::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks() {
+        let input = Input::from("1\n2\n\n3\n\n\n4\n5\n");
+        let blocks = input.blocks();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0], Block { first_line: 1, lines: vec!["1", "2"] });
+        assert_eq!(blocks[1], Block { first_line: 4, lines: vec!["3"] });
+        assert_eq!(blocks[2], Block { first_line: 7, lines: vec!["4", "5"] });
+    }
+
+    #[test]
+    fn parse_lines_reports_line() {
+        let input = Input::from("1\r\n2\r\nx3\r\n");
+        match input.parse_lines::<u32>() {
+            Err(Error::Parse { error, .. }) => {
+                assert_eq!(error.line, 3);
+                assert_eq!(error.text, "x3");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn excerpt_underlines_text() {
+        let input = Input::from("498,4 -> 498,6\n503,4 -> 49x,4\n");
+        let error = ParseError::at(2, "503,4 -> 49x,4", "49x", "bad");
+        assert_eq!(input.excerpt(&error).unwrap(), "2 | 503,4 -> 49x,4\n  |          ^^^");
+        let error = ParseError::new(2, "", "nothing");
+        assert_eq!(input.excerpt(&error).unwrap(), "2 | 503,4 -> 49x,4\n  | ^");
+        assert_eq!(input.excerpt(&ParseError::new(3, "", "past the end")), None);
+    }
+
+    #[test]
+    fn missing_file_names_path() {
+        let err = Input::from_path("does/not/exist").unwrap_err();
+        assert!(err.to_string().starts_with("does/not/exist: "));
+    }
+
+    #[test]
+    fn params() {
+        let mut params = Params::new();
+        params.set("row", "10");
+        let input = Input::from("").with_params(params);
+        assert_eq!(input.param::<i64>("row").unwrap(), 10);
+        let err = input.param::<u8>("max").unwrap_err();
+        assert_eq!(err.to_string(), "<memory>: parameter \"max\" is not set; set it in aoc.toml");
+        let err = input.param::<bool>("row").unwrap_err();
+        assert!(err.to_string().contains("is \"10\": "), "{}", err);
+        assert_eq!(input.param_or("row", 20).unwrap(), 10);
+        assert_eq!(input.param_or("max", 20).unwrap(), 20);
+        assert!(input.param_or("row", true).is_err());
+    }
+
+    #[test]
+    fn invalid_utf8_line() {
+        let err = Input::from_bytes(Source::Memory, b"ok\nok\n\xff\n".to_vec()).unwrap_err();
+        match err {
+            Error::Utf8 { line, .. } => assert_eq!(line, 3),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Sets of integers stored as sorted, disjoint inclusive ranges.
+
+use std::fmt;
+
+use crate::geom::Num;
+
+/// A set of integers kept as sorted, non-overlapping `(first, last)` pairs,
+/// both ends included. Ranges that touch are merged, so `(1, 3)` and `(4, 6)`
+/// become `(1, 6)`, and every gap between two stored ranges is non-empty.
+///
+/// Ends are stepped past with `+ 1` and `- 1`, so ranges touching the limits
+/// of `T` can overflow.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: Vec<(T, T)>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+}
+
+impl<T: Num + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet::default()
+    }
+
+    /// The set of `first..=last`; empty if `first > last`.
+    pub fn single(first: T, last: T) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(first, last);
+        set
+    }
+
+    /// Add `first..=last`, merging it with any ranges it overlaps or touches.
+    pub fn insert(&mut self, first: T, last: T) {
+        if first > last {
+            return;
+        }
+        // Ranges entirely before, then those to merge, then the rest.
+        let start = self.ranges.partition_point(|&(_, l)| l + T::ONE < first);
+        let end = self.ranges.partition_point(|&(f, _)| f <= last + T::ONE);
+        let (mut first, mut last) = (first, last);
+        if start < end {
+            first = first.min(self.ranges[start].0);
+            last = last.max(self.ranges[end - 1].1);
+        }
+        self.ranges.splice(start..end, [(first, last)]);
+    }
+
+    pub fn ranges(&self) -> &[(T, T)] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers in the set.
+    pub fn covered(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::ZERO, |n, &(f, l)| n + (l - f) + T::ONE)
+    }
+
+    pub fn first(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.0)
+    }
+
+    pub fn last(&self) -> Option<T> {
+        self.ranges.last().map(|r| r.1)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.ranges.partition_point(|&(_, l)| l < value);
+        i < self.ranges.len() && self.ranges[i].0 <= value
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut set = self.clone();
+        for &(f, l) in &other.ranges {
+            set.insert(f, l);
+        }
+        set
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+            let (first, last) = (a.0.max(b.0), a.1.min(b.1));
+            if first <= last {
+                ranges.push((first, last));
+            }
+            if a.1 < b.1 {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    /// Values in `self` but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for &(first, last) in &self.ranges {
+            let mut from = first;
+            while j < other.ranges.len() && other.ranges[j].1 < from {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].0 <= last {
+                let (f, l) = other.ranges[k];
+                if f > from {
+                    ranges.push((from, f - T::ONE));
+                }
+                if l >= last {
+                    break;
+                }
+                from = l + T::ONE;
+                k += 1;
+            }
+            if k == other.ranges.len() || other.ranges[k].0 > last {
+                ranges.push((from, last));
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        other.is_superset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    /// The part of the set inside `first..=last`.
+    pub fn clip(&self, first: T, last: T) -> Self {
+        self.intersection(&IntervalSet::single(first, last))
+    }
+
+    /// Ranges missing between the first and last value of the set.
+    pub fn gaps(&self) -> Vec<(T, T)> {
+        self.ranges
+            .windows(2)
+            .map(|w| (w[0].1 + T::ONE, w[1].0 - T::ONE))
+            .collect()
+    }
+}
+
+impl<T: Num + Ord> FromIterator<(T, T)> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = (T, T)>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for (f, l) in iter {
+            set.insert(f, l);
+        }
+        set
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for IntervalSet<T> {
+    /// `{1..=3, 7..=7}`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (first, last)) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}..={}", first, last)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[(i32, i32)]) -> IntervalSet<i32> {
+        ranges.iter().copied().collect()
+    }
+
+    #[test]
+    fn insert_merges() {
+        let s = set(&[(5, 8), (1, 2), (3, 3), (12, 14), (10, 10), (7, 9)]);
+        assert_eq!(s.ranges(), &[(1, 3), (5, 10), (12, 14)]);
+        assert_eq!(s.covered(), 12);
+        assert!(s.contains(10) && !s.contains(11) && !s.contains(0));
+        assert_eq!(s.gaps(), vec![(4, 4), (11, 11)]);
+        assert_eq!(s.to_string(), "{1..=3, 5..=10, 12..=14}");
+        assert!(set(&[(3, 1)]).is_empty());
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[(0, 10), (20, 30)]);
+        let b = set(&[(5, 25), (40, 41)]);
+        assert_eq!(a.union(&b), set(&[(0, 30), (40, 41)]));
+        assert_eq!(a.intersection(&b), set(&[(5, 10), (20, 25)]));
+        assert_eq!(a.difference(&b), set(&[(0, 4), (26, 30)]));
+        assert_eq!(b.difference(&a), set(&[(11, 19), (40, 41)]));
+        assert_eq!(a.difference(&set(&[(0, 30)])), set(&[]));
+        assert_eq!(a.clip(8, 22), set(&[(8, 10), (20, 22)]));
+        assert!(a.is_superset(&set(&[(2, 4), (22, 22)])));
+        assert!(!a.is_superset(&b));
+        assert!(set(&[(21, 29)]).is_subset(&a));
+        assert!(a.is_disjoint(&set(&[(11, 19)])));
+        assert!(!a.is_disjoint(&b));
+    }
+}
@@ -0,0 +1,169 @@
+//! Writing JSON, for the tools that read what the runner and the tracer say.
+//!
+//! Only writing: a [`Value`] is built up in code and `Display` prints it on
+//! one line.
+//!
+//! ```
+//! use aoc_core::json::{self, Value};
+//!
+//! let value = json::object([("day", Value::from(5)), ("answer", Value::from("CMZ"))]);
+//! assert_eq!(value.to_string(), r#"{"day":5,"answer":"CMZ"}"#);
+//! ```
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    /// Written as `null` unless finite, which JSON numbers have to be.
+    Float(f64),
+    Str(String),
+    Array(Vec<Value>),
+    /// Fields in the order they are written.
+    Object(Vec<(String, Value)>),
+}
+
+/// An object with `fields`, in order.
+pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+/// Append `s` to `out` as a JSON string, quotes included.
+pub fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl Value {
+    /// Append the value to `out`.
+    pub fn write(&self, out: &mut String) {
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Int(n) => out.push_str(&n.to_string()),
+            Value::Float(x) if x.is_finite() => out.push_str(&x.to_string()),
+            Value::Float(_) => out.push_str("null"),
+            Value::Str(s) => write_string(out, s),
+            Value::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.write(out);
+                }
+                out.push(']');
+            }
+            Value::Object(fields) => {
+                out.push('{');
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(out, name);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.write(&mut out);
+        f.write_str(&out)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+macro_rules! value_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(n: $t) -> Self {
+                    Value::Int(i64::try_from(n).expect("number does not fit in i64"))
+                }
+            }
+        )*
+    };
+}
+
+value_from_int!(i32, i64, u8, u16, u32, u64, usize);
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Float(x)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes() {
+        let value = object([
+            ("s", Value::from("a\"b\\c\n\u{1}é")),
+            ("n", Value::from(-3)),
+            ("x", Value::from(0.25)),
+            ("inf", Value::from(f64::INFINITY)),
+            ("none", Value::from(None::<u8>)),
+            ("list", Value::from(vec![true, false])),
+            ("empty", object([])),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            r#"{"s":"a\"b\\c\n\u0001é","n":-3,"x":0.25,"inf":null,"none":null,"list":[true,false],"empty":{}}"#
+        );
+    }
+}
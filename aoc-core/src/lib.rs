@@ -0,0 +1,32 @@
+//! Pieces shared by the `aocN` day crates.
+//!
+//! Every day used to carry its own copy of the same helpers; they live here
+//! once and the day crates depend on this crate through the workspace.
+
+pub mod answer;
+pub mod budget;
+pub mod config;
+pub mod cycle;
+pub mod differential;
+pub mod error;
+pub mod expected;
+pub mod gen;
+pub mod geom;
+pub mod gif;
+pub mod grid;
+pub mod input;
+pub mod interval;
+pub mod json;
+pub mod render;
+pub mod search;
+pub mod simulation;
+pub mod solution;
+pub mod trace;
+
+pub use answer::{Answer, Image};
+pub use error::{Error, ParseError, Result};
+pub use expected::Expected;
+pub use grid::Grid;
+pub use input::Input;
+pub use interval::IntervalSet;
+pub use solution::{Part, Registry, Solution, Solver};
@@ -0,0 +1,263 @@
+//! Frames drawn by the simulation days, and writers for ASCII, PPM and GIF.
+//!
+//! A frame is a [`Grid<char>`] drawn the way the puzzle text draws its maps.
+//! Days hand frames to a [`Recorder`] as they go; the writers turn the
+//! recorded frames into text or images, colouring each character through a
+//! [`Palette`].
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::gif;
+use crate::grid::Grid;
+
+pub type Rgb = [u8; 3];
+
+/// Somewhere for a simulation to send its frames.
+pub trait Recorder {
+    /// Whether the next frame will be kept. Days skip drawing when it won't.
+    fn wants_frame(&mut self) -> bool {
+        true
+    }
+
+    fn record(&mut self, frame: Grid<char>);
+}
+
+impl dyn Recorder + '_ {
+    /// Draw and record a frame, if one is wanted.
+    pub fn capture(&mut self, draw: impl FnOnce() -> Grid<char>) {
+        if self.wants_frame() {
+            self.record(draw());
+        }
+    }
+}
+
+/// Keeps nothing; what the plain part solvers record into.
+pub struct Discard;
+
+impl Recorder for Discard {
+    fn wants_frame(&mut self) -> bool {
+        false
+    }
+
+    fn record(&mut self, _frame: Grid<char>) {}
+}
+
+/// Keeps every `every`th frame offered, starting with the first.
+#[derive(Debug, Clone)]
+pub struct Frames {
+    every: usize,
+    offered: usize,
+    frames: Vec<Grid<char>>,
+}
+
+impl Frames {
+    pub fn new(every: usize) -> Self {
+        Frames {
+            every: every.max(1),
+            offered: 0,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn frames(&self) -> &[Grid<char>] {
+        &self.frames
+    }
+
+    pub fn into_frames(self) -> Vec<Grid<char>> {
+        self.frames
+    }
+}
+
+impl Recorder for Frames {
+    fn wants_frame(&mut self) -> bool {
+        self.offered += 1;
+        (self.offered - 1).is_multiple_of(self.every)
+    }
+
+    fn record(&mut self, frame: Grid<char>) {
+        self.frames.push(frame);
+    }
+}
+
+/// Colours for the characters frames are drawn with.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colours: HashMap<char, Rgb>,
+    /// For blank space, padding, and characters with no colour of their own.
+    background: Rgb,
+    other: Rgb,
+}
+
+impl Palette {
+    pub fn new(background: Rgb, other: Rgb) -> Self {
+        Palette {
+            colours: HashMap::new(),
+            background,
+            other,
+        }
+    }
+
+    pub fn with(mut self, chars: &str, colour: Rgb) -> Self {
+        for c in chars.chars() {
+            self.colours.insert(c, colour);
+        }
+        self
+    }
+
+    pub fn colour(&self, c: char) -> Rgb {
+        match self.colours.get(&c) {
+            Some(&colour) => colour,
+            None if c == ' ' => self.background,
+            None => self.other,
+        }
+    }
+}
+
+/// Dark background, grey walls and rock, and a colour for each of the
+/// characters the days draw moving things with.
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new([16, 16, 24], [230, 230, 230])
+            .with(".", [16, 16, 24])
+            .with("#|-", [120, 120, 130])
+            .with("o", [230, 190, 90])
+            .with("+~", [250, 120, 60])
+            .with("@H", [240, 80, 80])
+            .with("ET", [90, 220, 120])
+            .with("<>^v", [90, 150, 240])
+            .with("23456789", [150, 110, 230])
+    }
+}
+
+/// Frames one after another, each followed by a blank line.
+pub fn write_ascii(mut w: impl Write, frames: &[Grid<char>]) -> io::Result<()> {
+    for frame in frames {
+        writeln!(w, "{}\n", frame)?;
+    }
+    Ok(())
+}
+
+/// One frame as a binary PPM, each cell drawn as a `scale` pixel square.
+pub fn write_ppm(
+    mut w: impl Write,
+    frame: &Grid<char>,
+    palette: &Palette,
+    scale: usize,
+) -> io::Result<()> {
+    let scale = scale.max(1);
+    write!(
+        w,
+        "P6\n{} {}\n255\n",
+        frame.width() * scale,
+        frame.height() * scale
+    )?;
+    for row in frame.rows() {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|&c| std::iter::repeat_n(palette.colour(c), scale))
+            .flatten()
+            .collect();
+        for _ in 0..scale {
+            w.write_all(&line)?;
+        }
+    }
+    Ok(())
+}
+
+/// All frames as a looping animated GIF, `delay` hundredths of a second
+/// apart. Frames smaller than the largest are padded at the bottom right.
+/// Fails if the frames use more than 256 colours or are too large for a GIF.
+pub fn write_gif(
+    w: impl Write,
+    frames: &[Grid<char>],
+    palette: &Palette,
+    scale: usize,
+    delay: u16,
+) -> io::Result<()> {
+    let scale = scale.max(1);
+    let cols = frames.iter().map(|f| f.width()).max().unwrap_or(0);
+    let rows = frames.iter().map(|f| f.height()).max().unwrap_or(0);
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let width = u16::try_from(cols * scale).map_err(|_| invalid("frames too wide for a GIF"))?;
+    let height = u16::try_from(rows * scale).map_err(|_| invalid("frames too tall for a GIF"))?;
+
+    let mut colours = vec![palette.background];
+    let mut index: HashMap<Rgb, u8> = HashMap::from([(palette.background, 0)]);
+    let mut pixels = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let mut indices = Vec::with_capacity(width as usize * height as usize);
+        for r in 0..rows {
+            let mut line = Vec::with_capacity(width as usize);
+            for c in 0..cols {
+                let colour = frame
+                    .get((r, c))
+                    .map_or(palette.background, |&ch| palette.colour(ch));
+                let i = match index.get(&colour) {
+                    Some(&i) => i,
+                    None if colours.len() < 256 => {
+                        colours.push(colour);
+                        index.insert(colour, colours.len() as u8 - 1);
+                        colours.len() as u8 - 1
+                    }
+                    None => return Err(invalid("more than 256 colours")),
+                };
+                line.extend(std::iter::repeat_n(i, scale));
+            }
+            for _ in 0..scale {
+                indices.extend(&line);
+            }
+        }
+        pixels.push(indices);
+    }
+    gif::write(w, width, height, &colours, &pixels, delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Input;
+
+    fn frame(text: &str) -> Grid<char> {
+        Grid::parse(&Input::from(text), Some).unwrap()
+    }
+
+    #[test]
+    fn sampling() {
+        let mut frames = Frames::new(3);
+        let recorder: &mut dyn Recorder = &mut frames;
+        for i in 0..7 {
+            recorder.capture(|| Grid::new(1, 1, char::from(b'0' + i)));
+        }
+        let kept: String = frames.frames().iter().map(|f| f[(0, 0)]).collect();
+        assert_eq!(kept, "036");
+
+        let mut discard = Discard;
+        let recorder: &mut dyn Recorder = &mut discard;
+        recorder.capture(|| panic!("should not draw"));
+    }
+
+    #[test]
+    fn writers() {
+        let frames = [frame("#.\n.o\n"), frame("#o.\n")];
+        let mut text = Vec::new();
+        write_ascii(&mut text, &frames).unwrap();
+        assert_eq!(String::from_utf8(text).unwrap(), "#.\n.o\n\n#o.\n\n");
+
+        let palette = Palette::new([0, 0, 0], [9, 9, 9]).with("#", [1, 2, 3]);
+        let mut ppm = Vec::new();
+        write_ppm(&mut ppm, &frames[0], &palette, 2).unwrap();
+        let header = b"P6\n4 4\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 4 * 4 * 3);
+        assert_eq!(
+            &ppm[header.len()..header.len() + 9],
+            &[1, 2, 3, 1, 2, 3, 9, 9, 9]
+        );
+
+        let mut gif = Vec::new();
+        write_gif(&mut gif, &frames, &palette, 1, 5).unwrap();
+        // Padded to the larger of 3x1 and 2x2.
+        assert_eq!(&gif[6..10], &[3, 0, 2, 0]);
+    }
+}
@@ -0,0 +1,301 @@
+//! Shortest paths over implicit graphs: breadth-first, Dijkstra and A*.
+//!
+//! States can be anything hashable; the graph is given as a function from a
+//! state to its neighbours (with step costs for the weighted searches). All
+//! searches take several start states at once, stop at the first state the
+//! `goal` function accepts, and keep enough to rebuild the path to any state
+//! they reached. Pass `|_| false` as the goal to explore everything reachable.
+//!
+//! Every state expanded counts as one step of the [`budget`](crate::budget)
+//! in force; a search that runs out stops where it is, without a goal.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::budget;
+use crate::geom::Num;
+
+/// How much work a search did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// States taken off the frontier and had their neighbours generated.
+    pub expanded: usize,
+    /// Neighbours generated, including ones already reached.
+    pub generated: usize,
+    /// Largest the frontier got.
+    pub max_frontier: usize,
+    /// The search ran out of budget before it was done.
+    pub timed_out: bool,
+}
+
+/// What a search found: distances to every state it reached, the goal if
+/// one was found, and the predecessor of each state on its shortest path.
+#[derive(Debug, Clone)]
+pub struct Search<S, C> {
+    dist: HashMap<S, C>,
+    parent: HashMap<S, S>,
+    goal: Option<S>,
+    pub stats: Stats,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> Search<S, C> {
+    fn new() -> Self {
+        Search {
+            dist: HashMap::new(),
+            parent: HashMap::new(),
+            goal: None,
+            stats: Stats::default(),
+        }
+    }
+
+    fn log(&self, kind: &str) {
+        crate::debug!(
+            "search finished",
+            kind = kind,
+            found = self.goal.is_some(),
+            timed_out = self.stats.timed_out,
+            reached = self.dist.len(),
+            expanded = self.stats.expanded,
+            generated = self.stats.generated,
+            max_frontier = self.stats.max_frontier,
+        );
+    }
+
+    /// The goal state the search stopped at.
+    pub fn goal(&self) -> Option<&S> {
+        self.goal.as_ref()
+    }
+
+    /// Cost from the nearest start to the goal.
+    pub fn cost(&self) -> Option<C> {
+        self.goal.as_ref().and_then(|g| self.distance(g))
+    }
+
+    pub fn distance(&self, state: &S) -> Option<C> {
+        self.dist.get(state).copied()
+    }
+
+    /// Every state reached. When the search stopped at a goal, states still
+    /// on the frontier are included with their best cost so far.
+    pub fn distances(&self) -> &HashMap<S, C> {
+        &self.dist
+    }
+
+    /// States from a start to the goal, both included.
+    pub fn path(&self) -> Option<Vec<S>> {
+        self.path_to(self.goal.as_ref()?)
+    }
+
+    /// States from a start to `state`, both included.
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        if !self.dist.contains_key(state) {
+            return None;
+        }
+        let mut path = vec![state.clone()];
+        while let Some(prev) = self.parent.get(path.last().unwrap()) {
+            path.push(prev.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Breadth-first search where every step costs one.
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbours: impl FnMut(&S) -> I,
+    mut goal: impl FnMut(&S) -> bool,
+) -> Search<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut search = Search::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if let Entry::Vacant(e) = search.dist.entry(start.clone()) {
+            e.insert(0);
+            queue.push_back(start);
+        }
+    }
+    while let Some(cur) = queue.pop_front() {
+        if goal(&cur) {
+            search.goal = Some(cur);
+            break;
+        }
+        if !budget::tick() {
+            search.stats.timed_out = true;
+            break;
+        }
+        search.stats.expanded += 1;
+        let next = search.dist[&cur] + 1;
+        for n in neighbours(&cur) {
+            search.stats.generated += 1;
+            if let Entry::Vacant(e) = search.dist.entry(n.clone()) {
+                e.insert(next);
+                search.parent.insert(n.clone(), cur.clone());
+                queue.push_back(n);
+            }
+        }
+        search.stats.max_frontier = search.stats.max_frontier.max(queue.len());
+    }
+    search.log("bfs");
+    search
+}
+
+/// Dijkstra's algorithm; step costs must not be negative.
+pub fn dijkstra<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    neighbours: impl FnMut(&S) -> I,
+    goal: impl FnMut(&S) -> bool,
+) -> Search<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Num + Ord,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(starts, neighbours, |_| C::ZERO, goal)
+}
+
+/// A* search. `heuristic` must never overestimate the remaining cost to a
+/// goal, or the path found may not be the cheapest.
+pub fn astar<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbours: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut goal: impl FnMut(&S) -> bool,
+) -> Search<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Num + Ord,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut search = Search::new();
+    // The heap holds indices into `states` so that `S` need not be `Ord`;
+    // the index also breaks ties in favour of states pushed first.
+    let mut states = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for start in starts {
+        if let Entry::Vacant(e) = search.dist.entry(start.clone()) {
+            e.insert(C::ZERO);
+            heap.push(Reverse((heuristic(&start), C::ZERO, states.len())));
+            states.push(start);
+        }
+    }
+    while let Some(Reverse((_, cost, index))) = heap.pop() {
+        let cur = states[index].clone();
+        if search.dist[&cur] < cost {
+            continue;
+        }
+        if goal(&cur) {
+            search.goal = Some(cur);
+            break;
+        }
+        if !budget::tick() {
+            search.stats.timed_out = true;
+            break;
+        }
+        search.stats.expanded += 1;
+        for (n, step) in neighbours(&cur) {
+            search.stats.generated += 1;
+            let next = cost + step;
+            if search.dist.get(&n).is_some_and(|&d| d <= next) {
+                continue;
+            }
+            search.dist.insert(n.clone(), next);
+            search.parent.insert(n.clone(), cur.clone());
+            heap.push(Reverse((next + heuristic(&n), next, states.len())));
+            states.push(n);
+        }
+        search.stats.max_frontier = search.stats.max_frontier.max(heap.len());
+    }
+    search.log("astar");
+    search
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::Budget;
+    use crate::grid::{Grid, Pos};
+    use crate::input::Input;
+
+    fn maze() -> Grid<char> {
+        let text = "S..#....\n.#.#.##.\n.#...#..\n.####.#.\n......#E\n";
+        Grid::parse(&Input::from(text), Some).unwrap()
+    }
+
+    fn open(grid: &Grid<char>, pos: Pos) -> Vec<Pos> {
+        grid.neighbours4(pos).filter(|&p| grid[p] != '#').collect()
+    }
+
+    #[test]
+    fn bfs_path() {
+        let grid = maze();
+        let (start, end) = ((0, 0), (4, 7));
+        let search = bfs([start], |&p| open(&grid, p), |&p| p == end);
+        assert_eq!(search.cost(), Some(15));
+        let path = search.path().unwrap();
+        assert_eq!(path.len(), 16);
+        assert_eq!((path[0], path[15]), (start, end));
+        for step in path.windows(2) {
+            assert!(open(&grid, step[0]).contains(&step[1]));
+        }
+        assert!(search.stats.expanded > 0);
+
+        let all = bfs([start], |&p| open(&grid, p), |_| false);
+        assert_eq!(all.goal(), None);
+        assert_eq!(all.distances().len(), 27);
+        assert_eq!(all.distance(&end), Some(15));
+        assert_eq!(all.path_to(&(0, 4)).map(|p| p.len()), Some(9));
+        assert_eq!(all.path_to(&(0, 3)), None);
+    }
+
+    #[test]
+    fn multi_source() {
+        let grid = maze();
+        let starts = [(0, 0), (0, 7)];
+        let search = bfs(starts, |&p| open(&grid, p), |&p| p == (4, 7));
+        assert_eq!(search.cost(), Some(4));
+        assert_eq!(search.path().unwrap()[0], (0, 7));
+    }
+
+    #[test]
+    fn out_of_budget() {
+        let grid = maze();
+        let search = Budget::unlimited()
+            .with_steps(5)
+            .run(|| bfs([(0, 0)], |&p| open(&grid, p), |&p| p == (4, 7)));
+        assert!(search.stats.timed_out);
+        assert_eq!((search.goal(), search.stats.expanded), (None, 5));
+    }
+
+    #[test]
+    fn weighted() {
+        // Going round the long way is cheaper than the expensive edge.
+        let edges = |&n: &u8| -> Vec<(u8, u32)> {
+            match n {
+                0 => vec![(1, 10), (2, 1)],
+                2 => vec![(3, 1)],
+                3 => vec![(1, 1)],
+                1 => vec![(4, 1)],
+                _ => vec![],
+            }
+        };
+        let search = dijkstra([0], edges, |&n| n == 4);
+        assert_eq!(search.cost(), Some(4));
+        assert_eq!(search.path(), Some(vec![0, 2, 3, 1, 4]));
+
+        let grid = maze();
+        let end = (4, 7);
+        let weighted = |&p: &Pos| open(&grid, p).into_iter().map(|n| (n, 1));
+        let manhattan = |&(r, c): &Pos| r.abs_diff(end.0) + c.abs_diff(end.1);
+        let plain = dijkstra([(0, 0)], weighted, |&p| p == end);
+        let guided = astar([(0, 0)], weighted, manhattan, |&p| p == end);
+        assert_eq!(plain.cost(), Some(15));
+        assert_eq!(guided.cost(), Some(15));
+        assert!(guided.stats.expanded <= plain.stats.expanded);
+    }
+}
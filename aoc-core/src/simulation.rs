@@ -0,0 +1,227 @@
+//! Driving the simulation days one step at a time.
+//!
+//! Days 11, 14, 17, 23 and 24 each keep a `Session` that moves on in steps: a
+//! round of monkey business, a grain of sand, a rock, a round of elves, a
+//! minute of blizzards. [`Simulation`] is what they have in common, so a
+//! caller can run one exactly as far as it needs and look at it in between.
+//! [`Observed`] hangs observers on one that see it after every step; that is
+//! how the days draw frames for `aoc render`.
+//!
+//! ```
+//! use aoc_core::simulation::{Observed, Simulation};
+//!
+//! /// Counts to 5, then stops
+//! struct Count(usize);
+//!
+//! impl Simulation for Count {
+//!     type Snapshot = usize;
+//!
+//!     fn step(&mut self) -> bool {
+//!         if self.0 == 5 {
+//!             return false;
+//!         }
+//!         self.0 += 1;
+//!         true
+//!     }
+//!
+//!     fn steps(&self) -> usize {
+//!         self.0
+//!     }
+//!
+//!     fn snapshot(&self) -> usize {
+//!         self.0
+//!     }
+//! }
+//!
+//! let mut seen = Vec::new();
+//! let mut count = Observed::new(Count(0)).observe(|c| seen.push(c.snapshot()));
+//! assert_eq!(count.run_to(2), 2);
+//! assert_eq!(count.run_until(|c| c.snapshot() == 4), 2);
+//! drop(count);
+//! assert_eq!(seen, [1, 2, 3, 4]);
+//! ```
+
+use crate::grid::Grid;
+use crate::render::Recorder;
+
+/// Something that moves on one step at a time.
+pub trait Simulation {
+    /// A copy of the state, as [`snapshot`](Simulation::snapshot) takes it.
+    type Snapshot;
+
+    /// Move on by one step. False once there is nothing left to do, in which
+    /// case nothing changed.
+    fn step(&mut self) -> bool;
+
+    /// Steps that changed something so far.
+    fn steps(&self) -> usize;
+
+    fn snapshot(&self) -> Self::Snapshot;
+
+    /// Step until `done` holds, checked before every step, or nothing is left
+    /// to do. Returns the steps taken.
+    fn run_until(&mut self, mut done: impl FnMut(&Self) -> bool) -> usize
+    where
+        Self: Sized,
+    {
+        let mut taken = 0;
+        while !done(self) && self.step() {
+            taken += 1;
+        }
+        taken
+    }
+
+    /// Step until [`steps`](Simulation::steps) reaches `step`, or nothing is
+    /// left to do. Returns the steps taken.
+    fn run_to(&mut self, step: usize) -> usize
+    where
+        Self: Sized,
+    {
+        self.run_until(|s| s.steps() >= step)
+    }
+
+    /// Step until nothing is left to do; never returns for a simulation that
+    /// always has more. Returns the steps taken.
+    fn run_to_end(&mut self) -> usize
+    where
+        Self: Sized,
+    {
+        self.run_until(|_| false)
+    }
+}
+
+/// Called with the simulation after a step.
+pub type Observer<'a, S> = Box<dyn FnMut(&S) + 'a>;
+
+/// A simulation along with observers that see it after every step that
+/// changed something. It is a [`Simulation`] itself, so it runs the same way.
+pub struct Observed<'a, S> {
+    sim: S,
+    observers: Vec<Observer<'a, S>>,
+}
+
+impl<'a, S: Simulation> Observed<'a, S> {
+    pub fn new(sim: S) -> Self {
+        Observed {
+            sim,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Call `observer` after every step from now on, after the observers
+    /// added before it.
+    pub fn observe(mut self, observer: impl FnMut(&S) + 'a) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    pub fn get(&self) -> &S {
+        &self.sim
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sim
+    }
+}
+
+impl<'a, S: Simulation<Snapshot = Grid<char>>> Observed<'a, S> {
+    /// Record a frame of `sim` as it is now, and another after every step.
+    pub fn recording(sim: S, recorder: &'a mut dyn Recorder) -> Self {
+        recorder.capture(|| sim.snapshot());
+        Observed::new(sim).observe(move |s| recorder.capture(|| s.snapshot()))
+    }
+}
+
+impl<S: Simulation> Simulation for Observed<'_, S> {
+    type Snapshot = S::Snapshot;
+
+    fn step(&mut self) -> bool {
+        let stepped = self.sim.step();
+        if stepped {
+            for observer in &mut self.observers {
+                observer(&self.sim);
+            }
+        }
+        stepped
+    }
+
+    fn steps(&self) -> usize {
+        self.sim.steps()
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.sim.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Frames;
+
+    /// A bar that grows by one cell a step up to `len`
+    struct Bar {
+        filled: usize,
+        len: usize,
+    }
+
+    impl Simulation for Bar {
+        type Snapshot = Grid<char>;
+
+        fn step(&mut self) -> bool {
+            let grows = self.filled < self.len;
+            self.filled += grows as usize;
+            grows
+        }
+
+        fn steps(&self) -> usize {
+            self.filled
+        }
+
+        fn snapshot(&self) -> Grid<char> {
+            Grid::from_fn(
+                self.len,
+                1,
+                |(_, c)| if c < self.filled { '#' } else { '.' },
+            )
+        }
+    }
+
+    #[test]
+    fn runs() {
+        let mut bar = Bar { filled: 0, len: 4 };
+        assert_eq!(bar.run_to(3), 3);
+        assert_eq!(bar.run_to(3), 0);
+        assert_eq!(bar.run_to(10), 1);
+        assert!(!bar.step());
+        assert_eq!(bar.steps(), 4);
+
+        let mut bar = Bar { filled: 0, len: 4 };
+        assert_eq!(bar.run_until(|b| b.filled * 2 >= b.len), 2);
+        assert_eq!(bar.run_to_end(), 2);
+    }
+
+    #[test]
+    fn observers_see_every_step() {
+        let (mut first, mut second) = (Vec::new(), 0);
+        let mut bar = Observed::new(Bar { filled: 1, len: 3 })
+            .observe(|b| first.push(b.steps()))
+            .observe(|_| second += 1);
+        assert_eq!(bar.run_to_end(), 2);
+        assert_eq!(bar.get().filled, 3);
+        assert!(!bar.step());
+        drop(bar);
+        assert_eq!((first, second), (vec![2, 3], 2));
+    }
+
+    #[test]
+    fn recording() {
+        let mut frames = Frames::new(1);
+        let mut bar = Observed::recording(Bar { filled: 0, len: 2 }, &mut frames);
+        bar.run_to_end();
+        let bar = bar.into_inner();
+        let drawn: Vec<String> = frames.frames().iter().map(|f| f.to_string()).collect();
+        assert_eq!(drawn, ["..", "#.", "##"]);
+        assert_eq!(bar.snapshot(), *frames.frames().last().unwrap());
+    }
+}
@@ -0,0 +1,214 @@
+//! The interface every day implements, and a registry to look days up by
+//! number.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::answer::Answer;
+use crate::error::Result;
+use crate::gen::Rng;
+use crate::input::Input;
+use crate::render::Recorder;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const ALL: [Part; 2] = [Part::One, Part::Two];
+
+    /// 1 or 2.
+    pub fn number(self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::One => write!(f, "1"),
+            Part::Two => write!(f, "2"),
+        }
+    }
+}
+
+impl FromStr for Part {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            _ => Err(format!("part must be 1 or 2, got {:?}", s)),
+        }
+    }
+}
+
+/// One day's puzzle: parse the input once, then answer both parts from it.
+pub trait Solution {
+    const DAY: u8;
+
+    type Input;
+
+    fn parse(input: &Input) -> Result<Self::Input>;
+
+    fn part1(input: &Self::Input) -> Answer;
+
+    fn part2(_input: &Self::Input) -> Answer {
+        Answer::Unsolved
+    }
+
+    /// Solve `part` while drawing frames of the simulation to `recorder`.
+    /// `None` for days with nothing to draw.
+    fn record(_input: &Self::Input, _part: Part, _recorder: &mut dyn Recorder) -> Option<Answer> {
+        None
+    }
+
+    /// A random input about `size` times as big as a real one, decided
+    /// entirely by `rng`. `None` for days without a generator.
+    fn generate(_rng: &mut Rng, _size: usize) -> Option<String> {
+        None
+    }
+}
+
+/// Parsed input of some day, as handed around by [`Solver`].
+pub struct Parsed(Box<dyn Any>);
+
+/// Object-safe view of a [`Solution`], so days with different input types
+/// can sit in one [`Registry`].
+pub trait Solver: Send + Sync {
+    fn day(&self) -> u8;
+
+    fn parse(&self, input: &Input) -> Result<Parsed>;
+
+    /// Panics if `parsed` came from a different day's `parse`.
+    fn solve(&self, parsed: &Parsed, part: Part) -> Answer;
+
+    /// See [`Solution::record`]; panics like `solve`.
+    fn record(&self, parsed: &Parsed, part: Part, recorder: &mut dyn Recorder) -> Option<Answer>;
+
+    /// See [`Solution::generate`].
+    fn generate(&self, rng: &mut Rng, size: usize) -> Option<String>;
+
+    fn run(&self, input: &Input, part: Part) -> Result<Answer> {
+        Ok(self.solve(&self.parse(input)?, part))
+    }
+}
+
+struct Entry<S>(PhantomData<fn() -> S>);
+
+impl<S: Solution> Entry<S>
+where
+    S::Input: 'static,
+{
+    fn input(parsed: &Parsed) -> &S::Input {
+        parsed
+            .0
+            .downcast_ref::<S::Input>()
+            .unwrap_or_else(|| panic!("input was not parsed by day {}", S::DAY))
+    }
+}
+
+impl<S> Solver for Entry<S>
+where
+    S: Solution,
+    S::Input: 'static,
+{
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn parse(&self, input: &Input) -> Result<Parsed> {
+        Ok(Parsed(Box::new(S::parse(input)?)))
+    }
+
+    fn solve(&self, parsed: &Parsed, part: Part) -> Answer {
+        let input = Self::input(parsed);
+        match part {
+            Part::One => S::part1(input),
+            Part::Two => S::part2(input),
+        }
+    }
+
+    fn record(&self, parsed: &Parsed, part: Part, recorder: &mut dyn Recorder) -> Option<Answer> {
+        S::record(Self::input(parsed), part, recorder)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> Option<String> {
+        S::generate(rng, size)
+    }
+}
+
+/// Days keyed by number.
+#[derive(Default)]
+pub struct Registry {
+    solvers: BTreeMap<u8, Box<dyn Solver>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Add `S`, replacing anything already registered for its day.
+    pub fn register<S>(&mut self)
+    where
+        S: Solution + 'static,
+        S::Input: 'static,
+    {
+        self.solvers
+            .insert(S::DAY, Box::new(Entry::<S>(PhantomData)));
+    }
+
+    pub fn get(&self, day: u8) -> Option<&dyn Solver> {
+        self.solvers.get(&day).map(|s| s.as_ref())
+    }
+
+    /// Registered days in ascending order.
+    pub fn days(&self) -> impl Iterator<Item = u8> + '_ {
+        self.solvers.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Solver> + '_ {
+        self.solvers.values().map(|s| s.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sum;
+
+    impl Solution for Sum {
+        const DAY: u8 = 1;
+        type Input = Vec<i64>;
+
+        fn parse(input: &Input) -> Result<Self::Input> {
+            input.parse_lines()
+        }
+
+        fn part1(input: &Self::Input) -> Answer {
+            input.iter().sum::<i64>().into()
+        }
+    }
+
+    #[test]
+    fn registry_dispatch() {
+        let mut registry = Registry::new();
+        registry.register::<Sum>();
+        assert_eq!(registry.days().collect::<Vec<_>>(), vec![1]);
+        let solver = registry.get(1).unwrap();
+        let input = Input::from("1\n2\n3\n");
+        assert_eq!(solver.run(&input, Part::One).unwrap(), Answer::Number(6));
+        assert_eq!(solver.run(&input, Part::Two).unwrap(), Answer::Unsolved);
+        assert!(registry.get(2).is_none());
+    }
+}
@@ -0,0 +1,533 @@
+//! Structured debug events that can be switched on without editing source.
+//!
+//! Solvers emit events with [`event!`](crate::event) or one of the level
+//! shorthands ([`debug!`](crate::debug), [`trace!`](crate::trace), ...):
+//!
+//! ```
+//! aoc_core::debug!("grain settled", x = 500, y = 8);
+//! aoc_core::trace!("pair compared", pair = 3, order = ?std::cmp::Ordering::Less);
+//! ```
+//!
+//! Each event has a level, a target (the module it came from, so `aoc14` for
+//! day 14), a message and named fields; `?value` records a field through its
+//! `Debug` output. Nothing is formatted unless a [`Tracer`] is installed whose
+//! [`Filter`] lets the event through. The `AOC_TRACE` environment variable
+//! installs one the first time an event is checked, with a filter such as
+//! `debug` or `aoc14=trace,aoc20=debug`, and `AOC_TRACE_FORMAT=json` switches
+//! the output on stderr from text to JSON lines.
+
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::json;
+
+/// Environment variable holding the filter.
+pub const FILTER_VAR: &str = "AOC_TRACE";
+/// Environment variable holding the format, `text` or `json`.
+pub const FORMAT_VAR: &str = "AOC_TRACE_FORMAT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("unknown trace level {:?}", s)),
+        }
+    }
+}
+
+/// Which events to keep: a default level and overrides for targets.
+///
+/// Written as comma-separated directives, each either a level (the default)
+/// or `target=level`; `off` works as a level too. A target matches itself and
+/// the modules inside it, and a bare day number `14` stands for `aoc14`. The
+/// longest matching target wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    default: Option<Level>,
+    targets: Vec<(String, Option<Level>)>,
+}
+
+impl Filter {
+    /// Let through everything at `level` or more severe.
+    pub fn level(level: Level) -> Self {
+        Filter {
+            default: Some(level),
+            targets: Vec::new(),
+        }
+    }
+
+    pub fn with_target(mut self, target: &str, level: Option<Level>) -> Self {
+        self.targets.push((target.to_string(), level));
+        self
+    }
+
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        let within = |prefix: &str| {
+            target
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        };
+        let max = self
+            .targets
+            .iter()
+            .filter(|(prefix, _)| within(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |&(_, level)| level);
+        max.is_some_and(|max| level <= max)
+    }
+
+    /// The most verbose level any target lets through.
+    fn max_level(&self) -> Option<Level> {
+        let targets = self.targets.iter().filter_map(|&(_, level)| level);
+        self.default.into_iter().chain(targets).max()
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let level = |s: &str| match s {
+            "off" => Ok(None),
+            _ => s.parse().map(Some),
+        };
+        let mut filter = Filter::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, lvl)) => {
+                    let target = match target.parse::<u8>() {
+                        Ok(day) => format!("aoc{}", day),
+                        Err(_) => target.to_string(),
+                    };
+                    filter.targets.push((target, level(lvl)?));
+                }
+                None => filter.default = level(directive)?,
+            }
+        }
+        Ok(filter)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// `DEBUG aoc14: grain settled x=500 y=8`
+    #[default]
+    Text,
+    /// `{"level":"debug","target":"aoc14","message":"grain settled","x":500,"y":8}`
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown trace format {:?}", s)),
+        }
+    }
+}
+
+/// A field value. Numbers and booleans stay typed so JSON output keeps them
+/// unquoted; anything else is recorded as text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl Value {
+    /// The `Debug` output of `value`, what `name = ?value` records.
+    pub fn debug(value: &impl fmt::Debug) -> Self {
+        Value::Str(format!("{:?}", value))
+    }
+}
+
+macro_rules! value_from {
+    ($variant:ident as $as:ty: $($t:ty),*) => {$(
+        impl From<$t> for Value {
+            fn from(v: $t) -> Self {
+                Value::$variant(v as $as)
+            }
+        }
+    )*};
+}
+
+value_from!(Int as i64: i8, i16, i32, i64, isize);
+value_from!(Uint as u64: u8, u16, u32, u64, usize);
+value_from!(Float as f64: f32, f64);
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Str(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::Str(v)
+    }
+}
+
+impl<T: Into<Value> + Copy> From<&T> for Value {
+    fn from(v: &T) -> Self {
+        (*v).into()
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Uint(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Str(v) => f.write_str(v),
+        }
+    }
+}
+
+/// One event, as handed to the installed [`Tracer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event<'a> {
+    pub level: Level,
+    pub target: &'a str,
+    pub message: &'a str,
+    pub fields: &'a [(&'static str, Value)],
+}
+
+impl Event<'_> {
+    /// The event as one line, without the newline.
+    pub fn format(&self, format: Format) -> String {
+        match format {
+            Format::Text => {
+                let mut line = format!(
+                    "{:>5} {}: {}",
+                    self.level.as_str().to_ascii_uppercase(),
+                    self.target,
+                    self.message
+                );
+                for (name, value) in self.fields {
+                    line.push_str(&format!(" {}={}", name, value));
+                }
+                line
+            }
+            Format::Json => {
+                let mut line = String::from("{\"level\":");
+                json::write_string(&mut line, self.level.as_str());
+                line.push_str(",\"target\":");
+                json::write_string(&mut line, self.target);
+                line.push_str(",\"message\":");
+                json::write_string(&mut line, self.message);
+                for (name, value) in self.fields {
+                    line.push(',');
+                    json::write_string(&mut line, name);
+                    line.push(':');
+                    match value {
+                        Value::Float(v) if !v.is_finite() => line.push_str("null"),
+                        Value::Str(s) => json::write_string(&mut line, s),
+                        v => line.push_str(&v.to_string()),
+                    }
+                }
+                line.push('}');
+                line
+            }
+        }
+    }
+}
+
+/// Where events go: a filter, a format and something to write lines to.
+pub struct Tracer {
+    filter: Filter,
+    format: Format,
+    sink: Box<dyn Write + Send>,
+}
+
+impl Tracer {
+    /// A tracer writing to stderr.
+    pub fn new(filter: Filter, format: Format) -> Self {
+        Tracer {
+            filter,
+            format,
+            sink: Box::new(io::stderr()),
+        }
+    }
+
+    pub fn with_sink(mut self, sink: impl Write + Send + 'static) -> Self {
+        self.sink = Box::new(sink);
+        self
+    }
+
+    /// The tracer `AOC_TRACE` and `AOC_TRACE_FORMAT` ask for, if any.
+    pub fn from_env() -> Result<Option<Tracer>, String> {
+        let Ok(filter) = env::var(FILTER_VAR) else {
+            return Ok(None);
+        };
+        let format = match env::var(FORMAT_VAR) {
+            Ok(format) => format.parse()?,
+            Err(_) => Format::Text,
+        };
+        Ok(Some(Tracer::new(filter.parse()?, format)))
+    }
+}
+
+/// The most verbose level the installed tracer could want, 0 for none. Lets
+/// disabled events cost a single load.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+static TRACER: Mutex<Option<Tracer>> = Mutex::new(None);
+static FROM_ENV: OnceLock<()> = OnceLock::new();
+
+fn load_env() {
+    FROM_ENV.get_or_init(|| match Tracer::from_env() {
+        Ok(Some(tracer)) => set(Some(tracer)),
+        Ok(None) => {}
+        Err(e) => eprintln!("{}: {}", FILTER_VAR, e),
+    });
+}
+
+fn set(tracer: Option<Tracer>) {
+    let mut current = TRACER.lock().unwrap_or_else(|e| e.into_inner());
+    let max = tracer.as_ref().and_then(|t| t.filter.max_level());
+    MAX_LEVEL.store(max.map_or(0, |l| l as u8), Ordering::Relaxed);
+    *current = tracer;
+}
+
+/// Send events to `tracer` from now on, replacing any tracer set before or
+/// taken from the environment.
+pub fn install(tracer: Tracer) {
+    load_env();
+    set(Some(tracer));
+}
+
+/// Stop tracing, returning the tracer that was installed.
+pub fn uninstall() -> Option<Tracer> {
+    load_env();
+    let mut current = TRACER.lock().unwrap_or_else(|e| e.into_inner());
+    MAX_LEVEL.store(0, Ordering::Relaxed);
+    current.take()
+}
+
+/// Whether an event at `level` from `target` would be written.
+pub fn enabled(level: Level, target: &str) -> bool {
+    load_env();
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    let current = TRACER.lock().unwrap_or_else(|e| e.into_inner());
+    current
+        .as_ref()
+        .is_some_and(|t| t.filter.enabled(level, target))
+}
+
+/// Write `event` if the installed tracer wants it. Write errors are ignored:
+/// tracing must not change what a solver does.
+pub fn emit(event: &Event) {
+    let mut current = TRACER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(tracer) = current.as_mut() {
+        if tracer.filter.enabled(event.level, event.target) {
+            let _ = writeln!(tracer.sink, "{}", event.format(tracer.format));
+        }
+    }
+}
+
+/// Emit an event at a [`Level`]: a message, then `name = value` fields where
+/// `value` converts into a [`Value`], or `name = ?value` to record its
+/// `Debug` output. Fields are only evaluated when the event is enabled.
+#[macro_export]
+macro_rules! event {
+    ($level:expr, $message:expr $(, $($fields:tt)*)?) => {{
+        let level = $level;
+        if $crate::trace::enabled(level, module_path!()) {
+            $crate::trace::emit(&$crate::trace::Event {
+                level,
+                target: module_path!(),
+                message: &$message,
+                fields: &$crate::__event_fields!([] $($($fields)*)?),
+            });
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __event_fields {
+    ([$($done:expr,)*]) => { [$($done,)*] };
+    ([$($done:expr,)*] $name:ident = ?$value:expr $(, $($rest:tt)*)?) => {
+        $crate::__event_fields!(
+            [$($done,)* (stringify!($name), $crate::trace::Value::debug(&$value)),]
+            $($($rest)*)?
+        )
+    };
+    ([$($done:expr,)*] $name:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__event_fields!(
+            [$($done,)* (stringify!($name), $crate::trace::Value::from($value)),]
+            $($($rest)*)?
+        )
+    };
+}
+
+/// [`event!`] at [`Level::Error`].
+#[macro_export]
+macro_rules! error {
+    ($($args:tt)*) => { $crate::event!($crate::trace::Level::Error, $($args)*) };
+}
+
+/// [`event!`] at [`Level::Warn`].
+#[macro_export]
+macro_rules! warn {
+    ($($args:tt)*) => { $crate::event!($crate::trace::Level::Warn, $($args)*) };
+}
+
+/// [`event!`] at [`Level::Info`].
+#[macro_export]
+macro_rules! info {
+    ($($args:tt)*) => { $crate::event!($crate::trace::Level::Info, $($args)*) };
+}
+
+/// [`event!`] at [`Level::Debug`].
+#[macro_export]
+macro_rules! debug {
+    ($($args:tt)*) => { $crate::event!($crate::trace::Level::Debug, $($args)*) };
+}
+
+/// [`event!`] at [`Level::Trace`].
+#[macro_export]
+macro_rules! trace {
+    ($($args:tt)*) => { $crate::event!($crate::trace::Level::Trace, $($args)*) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn filters() {
+        let filter: Filter = "info, aoc14=trace, 20=off, aoc_core::search=debug"
+            .parse()
+            .unwrap();
+        assert!(filter.enabled(Level::Info, "aoc1"));
+        assert!(!filter.enabled(Level::Debug, "aoc1"));
+        assert!(filter.enabled(Level::Trace, "aoc14"));
+        assert!(filter.enabled(Level::Trace, "aoc14::sand"));
+        assert!(!filter.enabled(Level::Debug, "aoc140"));
+        assert!(!filter.enabled(Level::Error, "aoc20"));
+        assert!(filter.enabled(Level::Debug, "aoc_core::search"));
+        assert!(!filter.enabled(Level::Debug, "aoc_core::grid"));
+        assert_eq!(filter.max_level(), Some(Level::Trace));
+
+        assert_eq!("off".parse::<Filter>().unwrap().max_level(), None);
+        assert!("aoc14=loud".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn formats() {
+        let fields = [
+            ("x", Value::from(500)),
+            ("ok", Value::from(true)),
+            ("state", Value::debug(&"a\"b")),
+        ];
+        let event = Event {
+            level: Level::Debug,
+            target: "aoc14",
+            message: "grain settled",
+            fields: &fields,
+        };
+        assert_eq!(
+            event.format(Format::Text),
+            "DEBUG aoc14: grain settled x=500 ok=true state=\"a\\\"b\""
+        );
+        assert_eq!(
+            event.format(Format::Json),
+            r#"{"level":"debug","target":"aoc14","message":"grain settled","x":500,"ok":true,"state":"\"a\\\"b\""}"#
+        );
+    }
+
+    /// A sink the test can read back after the tracer is gone.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn macros() {
+        let buffer = Buffer::default();
+        let filter = Filter::default().with_target(module_path!(), Some(Level::Debug));
+        install(Tracer::new(filter, Format::Text).with_sink(buffer.clone()));
+        let mut evaluated = 0;
+        crate::debug!("start");
+        crate::info!("queue", size = 3usize, front = ?(1, 2),);
+        crate::trace!(
+            "skipped",
+            n = {
+                evaluated += 1;
+                evaluated
+            }
+        );
+        uninstall();
+        crate::error!("after");
+
+        let out = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            out,
+            "DEBUG aoc_core::trace::tests: start\n \
+             INFO aoc_core::trace::tests: queue size=3 front=(1, 2)\n"
+        );
+        assert_eq!(evaluated, 0);
+    }
+}
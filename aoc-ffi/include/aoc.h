@@ -0,0 +1,60 @@
+/*
+ * The Advent of Code 2022 solvers, from C.
+ *
+ * Link against libaoc_ffi.so, built by `cargo build --release -p aoc-ffi`
+ * into target/release. See aoc-ffi/src/lib.rs for the details.
+ *
+ * Ownership: the caller owns the input and the name, which are only read
+ * during the call. Every string set through `answer` belongs to the caller,
+ * who frees it with aoc_free() exactly once, never with free().
+ *
+ * Calls are independent and may be made from several threads at once.
+ */
+
+#ifndef AOC_H
+#define AOC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* What aoc_solve() returns. Negative codes are failures. */
+#define AOC_OK 0                 /* *answer is the answer */
+#define AOC_UNSOLVED 1           /* the part has no working solver */
+#define AOC_TIMED_OUT 2          /* *answer has the best answer so far, if any */
+#define AOC_INVALID_ARGUMENT -1  /* null pointer, part not 1 or 2, name not UTF-8 */
+#define AOC_UNREGISTERED -2      /* nothing is registered for the day */
+#define AOC_BAD_INPUT -3         /* *answer has the parse error and its line */
+#define AOC_PANIC -4             /* *answer has the panic message */
+
+/*
+ * Solve `part` (1 or 2) of `day` for the `input_len` bytes at `input`.
+ *
+ * `name` is the input's file name, such as "in.test", which picks its table
+ * in aoc.toml; NULL for the day's own table. `timeout_ms` limits how long the
+ * part may take, 0 for no limit.
+ *
+ * Sets *answer to a new NUL-terminated string, for every return code: the
+ * answer for AOC_OK, a message otherwise. It is only left alone when `answer`
+ * is NULL, which returns AOC_INVALID_ARGUMENT.
+ */
+int aoc_solve(uint8_t day, uint8_t part, const uint8_t *input, size_t input_len,
+              const char *name, uint64_t timeout_ms, char **answer);
+
+/* Free a string from aoc_solve(). NULL is ignored. */
+void aoc_free(char *s);
+
+/*
+ * Write up to `capacity` registered days, in order, to `days`, and return how
+ * many there are in all. `days` may be NULL when `capacity` is 0.
+ */
+size_t aoc_days(uint8_t *days, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AOC_H */
@@ -0,0 +1,276 @@
+//! The solvers as C functions, for harnesses in C, Python (`ctypes`) and
+//! anything else that can load a shared library. `include/aoc.h` declares
+//! them for C.
+//!
+//! Memory ownership is kept simple:
+//!
+//! - The caller owns the input and the input name; they are only read during
+//!   the call, and nothing keeps a pointer to them afterwards.
+//! - Every string handed back through `answer` was allocated here and belongs
+//!   to the caller, who must give it back with [`aoc_free`] exactly once, and
+//!   not with C's `free`.
+//!
+//! Every call is independent, and calls may be made from several threads at
+//! once.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+use std::slice;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use aoc_core::budget::Budget;
+use aoc_core::config::Config;
+use aoc_core::input::Source;
+use aoc_core::{Answer, Input, Part, Registry};
+use aoc_runner::run::{self, Failure};
+
+/// The answer is in `answer`.
+pub const AOC_OK: c_int = 0;
+/// The part has no working solver; `answer` says `unsolved`.
+pub const AOC_UNSOLVED: c_int = 1;
+/// The part ran out of time; `answer` says so, with the best answer found by
+/// then if there was one.
+pub const AOC_TIMED_OUT: c_int = 2;
+/// A pointer was null that may not be, the part was not 1 or 2, or the name
+/// was not UTF-8; `answer` says which, unless it was `answer` itself.
+pub const AOC_INVALID_ARGUMENT: c_int = -1;
+/// Nothing is registered for the day.
+pub const AOC_UNREGISTERED: c_int = -2;
+/// The input, or the parameters from `aoc.toml`, could not be used; `answer`
+/// holds the error, with the line it was found on for a parse error.
+pub const AOC_BAD_INPUT: c_int = -3;
+/// The solver panicked; `answer` holds the panic message.
+pub const AOC_PANIC: c_int = -4;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(aoc_runner::registry)
+}
+
+fn config() -> Result<&'static Config, String> {
+    static CONFIG: OnceLock<Result<Config, String>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| Config::workspace().map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| e.clone())
+}
+
+/// Solve `part` of `day` for the `input_len` bytes at `input`.
+///
+/// `name` is the input's file name, as in `in.test`, which picks its table in
+/// `aoc.toml`; null for the day's own table. `timeout_ms` limits how long the
+/// part may take, 0 for no limit.
+///
+/// Returns one of the `AOC_` codes, non-negative when the solver gave an
+/// answer. `*answer` is then set to a new NUL-terminated string for the caller
+/// to free with [`aoc_free`]: the answer for [`AOC_OK`], a message otherwise.
+/// It is only left null when `answer` itself is null.
+///
+/// # Safety
+///
+/// `input` must point to `input_len` readable bytes, or may be null if
+/// `input_len` is 0. `name` must be null or point to a NUL-terminated string.
+/// `answer` must be null or point to writable space for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_solve(
+    day: u8,
+    part: u8,
+    input: *const u8,
+    input_len: usize,
+    name: *const c_char,
+    timeout_ms: u64,
+    answer: *mut *mut c_char,
+) -> c_int {
+    if answer.is_null() {
+        return AOC_INVALID_ARGUMENT;
+    }
+    let (status, text) = match arguments(input, input_len, name) {
+        Ok((bytes, name)) => run::catch(|| solve(day, part, bytes, name, timeout_ms))
+            .unwrap_or_else(|e| (AOC_PANIC, e.to_string())),
+        Err(e) => (AOC_INVALID_ARGUMENT, e.to_string()),
+    };
+    *answer = c_string(text);
+    status
+}
+
+/// Give back a string from [`aoc_solve`]. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string from [`aoc_solve`] that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Write the registered days, in order, to `days`, at most `capacity` of them,
+/// and return how many there are in all. `days` may be null when `capacity` is
+/// 0, to ask how many there are.
+///
+/// # Safety
+///
+/// `days` must point to `capacity` writable bytes, or may be null if
+/// `capacity` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_days(days: *mut u8, capacity: usize) -> usize {
+    let registered: Vec<u8> = registry().days().collect();
+    if !days.is_null() {
+        let n = registered.len().min(capacity);
+        ptr::copy_nonoverlapping(registered.as_ptr(), days, n);
+    }
+    registered.len()
+}
+
+/// Copies of what the pointers point to.
+unsafe fn arguments<'a>(
+    input: *const u8,
+    input_len: usize,
+    name: *const c_char,
+) -> Result<(Vec<u8>, Option<&'a str>), &'static str> {
+    let bytes = match (input.is_null(), input_len) {
+        (_, 0) => Vec::new(),
+        (true, _) => return Err("input is null"),
+        (false, len) => slice::from_raw_parts(input, len).to_vec(),
+    };
+    let name = match name.is_null() {
+        true => None,
+        false => Some(
+            CStr::from_ptr(name)
+                .to_str()
+                .map_err(|_| "name is not UTF-8")?,
+        ),
+    };
+    Ok((bytes, name))
+}
+
+fn solve(
+    day: u8,
+    part: u8,
+    bytes: Vec<u8>,
+    name: Option<&str>,
+    timeout_ms: u64,
+) -> (c_int, String) {
+    let part = match part {
+        1 => Part::One,
+        2 => Part::Two,
+        _ => {
+            return (
+                AOC_INVALID_ARGUMENT,
+                format!("part must be 1 or 2, got {}", part),
+            )
+        }
+    };
+    let Some(solver) = registry().get(day) else {
+        return (AOC_UNREGISTERED, Failure::Unregistered(day).to_string());
+    };
+    let config = match config() {
+        Ok(config) => config,
+        Err(e) => return (AOC_BAD_INPUT, e),
+    };
+    let input = match Input::from_bytes(Source::Memory, bytes) {
+        Ok(input) => input.with_params(config.params(day, name)),
+        Err(e) => return (AOC_BAD_INPUT, e.to_string()),
+    };
+    let budget = match timeout_ms {
+        0 => Budget::unlimited(),
+        ms => Budget::unlimited().with_timeout(Duration::from_millis(ms)),
+    };
+    let solved = run::parse(solver, &input)
+        .and_then(|parsed| run::solve(solver, &parsed.value, part, &budget));
+    match solved {
+        Ok(answer) => {
+            let status = match answer.value {
+                Answer::Unsolved => AOC_UNSOLVED,
+                Answer::TimedOut(_) => AOC_TIMED_OUT,
+                _ => AOC_OK,
+            };
+            (status, answer.value.to_string())
+        }
+        Err(failure) => {
+            let status = match failure {
+                Failure::Unregistered(_) => AOC_UNREGISTERED,
+                Failure::Input(_) => AOC_BAD_INPUT,
+                Failure::Panic(_) => AOC_PANIC,
+            };
+            (status, failure.describe(&input))
+        }
+    }
+}
+
+/// `text` as a C string owned by the caller. C strings end at the first NUL,
+/// so any in `text` become U+FFFD.
+fn c_string(text: String) -> *mut c_char {
+    let text = match text.contains('\0') {
+        true => text.replace('\0', "\u{fffd}"),
+        false => text,
+    };
+    CString::new(text).expect("NULs replaced").into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Call [`aoc_solve`] as C would and take back the string
+    fn call(day: u8, part: u8, input: &[u8], name: Option<&CStr>) -> (c_int, String) {
+        let mut answer = ptr::null_mut();
+        let name = name.map_or(ptr::null(), |n| n.as_ptr());
+        let status =
+            unsafe { aoc_solve(day, part, input.as_ptr(), input.len(), name, 0, &mut answer) };
+        let text = unsafe { CStr::from_ptr(answer) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { aoc_free(answer) };
+        (status, text)
+    }
+
+    #[test]
+    fn solves() {
+        let example = b"1000\n2000\n\n3000\n";
+        assert_eq!(call(1, 1, example, None), (AOC_OK, "3000".to_string()));
+        assert_eq!(call(1, 2, example, None), (AOC_OK, "6000".to_string()));
+
+        let example = include_bytes!("../../aoc15/in.test");
+        assert_eq!(
+            call(15, 1, example, Some(c"in.test")),
+            (AOC_OK, "26".to_string())
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(call(1, 3, b"", None).0, AOC_INVALID_ARGUMENT);
+        assert_eq!(
+            call(19, 1, b"", None),
+            (
+                AOC_UNREGISTERED,
+                "no solution registered for day 19".to_string()
+            )
+        );
+        let (status, text) = call(1, 1, b"1\nx\n", None);
+        assert_eq!(status, AOC_BAD_INPUT);
+        assert!(text.ends_with("2 | x\n  | ^"), "{}", text);
+        assert_eq!(call(1, 1, b"\xff", None).0, AOC_BAD_INPUT);
+        assert_eq!(call(1, 1, b"", Some(c"\xff")).0, AOC_INVALID_ARGUMENT);
+
+        let mut answer = ptr::null_mut();
+        let status = unsafe { aoc_solve(1, 1, ptr::null(), 3, ptr::null(), 0, &mut answer) };
+        assert_eq!(status, AOC_INVALID_ARGUMENT);
+        unsafe { aoc_free(answer) };
+        let status = unsafe { aoc_solve(1, 1, ptr::null(), 0, ptr::null(), 0, ptr::null_mut()) };
+        assert_eq!(status, AOC_INVALID_ARGUMENT);
+    }
+
+    #[test]
+    fn days() {
+        let total = unsafe { aoc_days(ptr::null_mut(), 0) };
+        assert_eq!(total, 23);
+        let mut days = [0u8; 3];
+        assert_eq!(unsafe { aoc_days(days.as_mut_ptr(), days.len()) }, total);
+        assert_eq!(days, [1, 2, 3]);
+    }
+}
@@ -0,0 +1,56 @@
+//! Builds `tests/c/solve.c` against the shared library and runs it.
+
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where cargo put `libaoc_ffi.so` for this build: next to this test in
+/// `deps`, or the directory above it once `cargo build` has copied it there.
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    let dir = [deps, deps.parent().unwrap()]
+        .into_iter()
+        .find(|dir| dir.join("libaoc_ffi.so").exists())
+        .unwrap_or(deps);
+    dir.to_path_buf()
+}
+
+#[test]
+fn c_program() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib = library_dir();
+    assert!(
+        lib.join("libaoc_ffi.so").exists(),
+        "no libaoc_ffi.so in {}",
+        lib.display()
+    );
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let exe = Path::new(env!("CARGO_TARGET_TMPDIR")).join("solve");
+    let built = Command::new(&cc)
+        .arg(root.join("tests/c/solve.c"))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&lib)
+        .arg("-laoc_ffi")
+        .arg(format!("-Wl,-rpath,{}", lib.display()))
+        .arg("-o")
+        .arg(&exe)
+        .status();
+    match built {
+        Ok(status) => assert!(status.success(), "{} failed to build solve.c", cc),
+        Err(e) => {
+            eprintln!("skipped: cannot run {}: {}", cc, e);
+            return;
+        }
+    }
+    let output = Command::new(&exe).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "solve.c failed:\n{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,62 @@
+/*
+ * Calls the solvers through aoc.h as a C harness would, and checks what
+ * comes back. `cargo test -p aoc-ffi` builds and runs it on Linux; by hand:
+ *
+ *     cargo build -p aoc-ffi
+ *     cc aoc-ffi/tests/c/solve.c -Iaoc-ffi/include -Ltarget/debug -laoc_ffi \
+ *         -Wl,-rpath,target/debug -o solve && ./solve
+ */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "aoc.h"
+
+static int failures = 0;
+
+/* Solve and compare both the code and the string, then free the string. */
+static void check(uint8_t day, uint8_t part, const char *input, const char *name,
+                  int want_status, const char *want_answer) {
+    char *answer = NULL;
+    int status = aoc_solve(day, part, (const uint8_t *)input, strlen(input), name, 0, &answer);
+    if (status != want_status || answer == NULL || strcmp(answer, want_answer) != 0) {
+        fprintf(stderr, "day %d part %d: got %d \"%s\", want %d \"%s\"\n", day, part, status,
+                answer ? answer : "(null)", want_status, want_answer);
+        failures++;
+    }
+    aoc_free(answer);
+}
+
+int main(void) {
+    const char *calories = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+    check(1, 1, calories, NULL, AOC_OK, "24000");
+    check(1, 2, calories, NULL, AOC_OK, "45000");
+    check(2, 1, "A Y\nB X\nC Z\n", NULL, AOC_OK, "15");
+    check(1, 3, calories, NULL, AOC_INVALID_ARGUMENT, "part must be 1 or 2, got 3");
+    check(19, 1, "", NULL, AOC_UNREGISTERED, "no solution registered for day 19");
+    check(1, 1, "1000\n2x00\n", NULL, AOC_BAD_INPUT,
+          "<memory>:2:1: invalid digit found in string: \"2x00\"\n2 | 2x00\n  | ^^^^");
+
+    char *answer = NULL;
+    if (aoc_solve(1, 1, NULL, 4, NULL, 0, &answer) != AOC_INVALID_ARGUMENT) {
+        fprintf(stderr, "null input was accepted\n");
+        failures++;
+    }
+    aoc_free(answer);
+    if (aoc_solve(1, 1, NULL, 0, NULL, 0, NULL) != AOC_INVALID_ARGUMENT) {
+        fprintf(stderr, "null answer was accepted\n");
+        failures++;
+    }
+
+    uint8_t days[32];
+    size_t total = aoc_days(days, sizeof days);
+    if (total != 23 || days[0] != 1 || days[total - 1] != 24) {
+        fprintf(stderr, "unexpected days: %zu\n", total);
+        failures++;
+    }
+
+    if (failures == 0) {
+        printf("ok\n");
+    }
+    return failures == 0 ? 0 : 1;
+}
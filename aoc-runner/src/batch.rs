@@ -0,0 +1,502 @@
+//! Running one day over many inputs, to compare what it makes of each.
+//!
+//! Inputs come from files, directories (every file in them) and globs with
+//! `*` and `?` in the file name, as in `inputs/day16-*`. Each input is run as
+//! a day of its own by [`calendar::run`], so they share the pool of threads,
+//! and each gets the parameters `aoc.toml` has for its file name.
+//!
+//! A [`Batch`] prints as a table to read, or as CSV or JSON to sort and
+//! filter: inputs that time out, or get answers no one expected, are the ones
+//! that break an assumption a solver makes about its input.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use aoc_core::budget::Budget;
+use aoc_core::config::Config;
+use aoc_core::json::{self, Value};
+use aoc_core::{Answer, Input, Part, Solver};
+
+use crate::calendar;
+use crate::run::{self, DayRun, Failure};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format {:?}", s)),
+        }
+    }
+}
+
+/// What `aoc batch` was asked to do.
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub day: u8,
+    /// Both parts when not given.
+    pub part: Option<Part>,
+    /// Files, directories and globs, as given.
+    pub inputs: Vec<String>,
+    pub timeout: Option<Duration>,
+    pub jobs: Option<usize>,
+    pub format: Format,
+    /// Stdout when not given.
+    pub out: Option<String>,
+}
+
+impl Args {
+    pub fn parts(&self) -> Vec<Part> {
+        match self.part {
+            Some(part) => vec![part],
+            None => Part::ALL.to_vec(),
+        }
+    }
+}
+
+/// What one input came to.
+#[derive(Debug)]
+pub struct Row {
+    pub path: PathBuf,
+    pub run: Result<DayRun, Failure>,
+}
+
+/// What [`run()`] found out about every input.
+#[derive(Debug)]
+pub struct Batch {
+    pub day: u8,
+    pub parts: Vec<Part>,
+    /// One row per input, in the order they were given.
+    pub rows: Vec<Row>,
+    pub wall_time: Duration,
+    pub threads: usize,
+}
+
+/// The files `patterns` name: a file as it is, every file in a directory, and
+/// every file a glob matches, each group sorted by name. A glob may only have
+/// wildcards in its last component, and has to match something.
+pub fn inputs(patterns: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        let (dir, name) = if pattern.contains(['*', '?']) {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            if !name.contains(['*', '?']) || dir.to_string_lossy().contains(['*', '?']) {
+                return Err(format!("{}: wildcards only work in the file name", pattern));
+            }
+            (dir, Some(name))
+        } else if path.is_dir() {
+            (path, None)
+        } else {
+            paths.push(path.to_path_buf());
+            continue;
+        };
+        let mut found = files(dir, name).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        if found.is_empty() {
+            return Err(format!("{}: no input files", pattern));
+        }
+        found.sort();
+        paths.extend(found);
+    }
+    Ok(paths)
+}
+
+/// Files in `dir` whose names match `glob`, or all of them; names starting
+/// with `.` only if the glob does too, as in a shell.
+fn files(dir: &Path, glob: Option<&str>) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let wanted = match glob {
+            Some(glob) => matches(glob, &name) && (!name.starts_with('.') || glob.starts_with('.')),
+            None => !name.starts_with('.'),
+        };
+        if wanted && entry.path().is_file() {
+            found.push(entry.path());
+        }
+    }
+    Ok(found)
+}
+
+/// Whether `name` matches `glob`, where `*` is any run of characters and `?`
+/// any one character.
+fn matches(glob: &str, name: &str) -> bool {
+    fn go(glob: &[char], name: &[char]) -> bool {
+        match (glob.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => go(&glob[1..], name) || (!name.is_empty() && go(glob, &name[1..])),
+            (Some('?'), Some(_)) => go(&glob[1..], &name[1..]),
+            (Some(g), Some(n)) if g == n => go(&glob[1..], &name[1..]),
+            _ => false,
+        }
+    }
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    go(&glob, &name)
+}
+
+/// The input at `path` for `day`, with the parameters for its file name.
+fn load(day: u8, path: &Path, config: &Config) -> Result<Input, Failure> {
+    let input = Input::from_path(path)?;
+    let params = config.params(day, input.name());
+    Ok(input.with_params(params))
+}
+
+/// Answer `parts` of `solver`'s day for every input in `paths`, on `threads`
+/// threads, each part within `budget`.
+pub fn run(
+    solver: &dyn Solver,
+    paths: &[PathBuf],
+    config: &Config,
+    parts: &[Part],
+    budget: &Budget,
+    threads: usize,
+) -> Batch {
+    let mut unreadable = Vec::new();
+    let mut ready = Vec::new();
+    for path in paths {
+        match load(solver.day(), path, config) {
+            Ok(input) => {
+                ready.push((solver, input));
+                unreadable.push(None);
+            }
+            Err(e) => unreadable.push(Some(e)),
+        }
+    }
+    let calendar = calendar::run(&ready, parts, budget, threads);
+
+    let mut runs = calendar.days.into_iter();
+    let rows = paths
+        .iter()
+        .zip(unreadable)
+        .map(|(path, unreadable)| Row {
+            path: path.clone(),
+            run: match unreadable {
+                Some(e) => Err(e),
+                None => runs.next().expect("a run for every input read"),
+            },
+        })
+        .collect();
+    Batch {
+        day: solver.day(),
+        parts: parts.to_vec(),
+        rows,
+        wall_time: calendar.wall_time,
+        threads: calendar.threads,
+    }
+}
+
+impl Batch {
+    /// Inputs that could not be answered.
+    pub fn failed(&self) -> usize {
+        self.rows.iter().filter(|r| r.run.is_err()).count()
+    }
+
+    /// Inputs with a part that ran out of budget.
+    pub fn timed_out(&self) -> usize {
+        self.rows.iter().flat_map(|r| &r.run).filter(|d| d.timed_out().next().is_some()).count()
+    }
+
+    /// Every input answered, and in time.
+    pub fn succeeded(&self) -> bool {
+        self.failed() == 0 && self.timed_out() == 0
+    }
+
+    /// Write in `format` to the file at `path`, or to stdout.
+    pub fn save(&self, format: Format, path: Option<&str>) -> Result<(), String> {
+        let written = match path {
+            Some(path) => File::create(path).and_then(|f| {
+                let mut w = BufWriter::new(f);
+                self.write(format, &mut w)?;
+                w.flush()
+            }),
+            None => self.write(format, &mut io::stdout().lock()),
+        };
+        written.map_err(|e| format!("{}: {}", path.unwrap_or("stdout"), e))
+    }
+
+    pub fn write(&self, format: Format, out: &mut impl Write) -> io::Result<()> {
+        match format {
+            Format::Table => self.write_table(out),
+            Format::Csv => self.write_csv(out),
+            Format::Json => writeln!(out, "{}", self.to_json()),
+        }
+    }
+
+    /// Aligned columns with a summary line under them.
+    fn write_table(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut header = vec!["input".to_string(), "parse".to_string()];
+        for part in &self.parts {
+            header.extend([format!("part {}", part), "time".to_string()]);
+        }
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut cells = vec![row.path.display().to_string()];
+                match &row.run {
+                    Ok(day) => {
+                        cells.push(format!("{:.2?}", day.parse_time));
+                        for part in &day.parts {
+                            cells.push(cell(&part.answer.value));
+                            cells.push(format!("{:.2?}", part.answer.elapsed));
+                        }
+                    }
+                    Err(e) => cells.push(format!("failed: {}", error(e))),
+                }
+                cells
+            })
+            .collect();
+
+        // A failure's message runs past the columns rather than widening them
+        let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+        for cells in &rows {
+            let answered = cells.len() == header.len();
+            for (i, cell) in cells.iter().enumerate().filter(|&(i, _)| i == 0 || answered) {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+        for cells in std::iter::once(&header).chain(&rows) {
+            let line: Vec<String> = cells.iter().zip(&widths).map(|(c, &w)| format!("{:<w$}", c, w = w)).collect();
+            writeln!(out, "{}", line.join("  ").trim_end())?;
+        }
+        writeln!(
+            out,
+            "{} inputs, {} failed, {} timed out, wall {:.2?} on {} threads",
+            self.rows.len(),
+            self.failed(),
+            self.timed_out(),
+            self.wall_time,
+            self.threads
+        )
+    }
+
+    /// One line per input: its path, the error if it failed, then the parse
+    /// time and, for each part, the answer, `ok`, `unsolved` or `timed_out`,
+    /// and the time. Times are in milliseconds.
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut header = vec!["input".to_string(), "error".to_string(), "parse_ms".to_string()];
+        for part in &self.parts {
+            header.extend([format!("part{}", part), format!("part{}_status", part), format!("part{}_ms", part)]);
+        }
+        writeln!(out, "{}", header.join(","))?;
+        for row in &self.rows {
+            let mut cells = vec![row.path.display().to_string()];
+            match &row.run {
+                Ok(day) => {
+                    cells.extend([String::new(), format!("{:.6}", millis(day.parse_time))]);
+                    for part in &day.parts {
+                        let answer = &part.answer.value;
+                        cells.push(run::answer_text(answer).unwrap_or_default());
+                        cells.push(status(answer).to_string());
+                        cells.push(format!("{:.6}", millis(part.answer.elapsed)));
+                    }
+                }
+                Err(e) => {
+                    cells.push(error(e));
+                    cells.resize(header.len(), String::new());
+                }
+            }
+            let cells: Vec<String> = cells.iter().map(|c| csv_field(c)).collect();
+            writeln!(out, "{}", cells.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// `{"day":6,"inputs":[...]}`, an input being
+    /// `{"input":"in.1","parse_ms":0.1,"parts":[{"part":1,"answer":"7","timed_out":false,"solve_ms":0.2}]}`,
+    /// or, if it failed, `{"input":"in.2","error":"..."}` with the `line` and
+    /// `column` of a parse error.
+    pub fn to_json(&self) -> Value {
+        let inputs = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut fields = vec![("input", Value::from(row.path.display().to_string()))];
+                match &row.run {
+                    Ok(day) => {
+                        let parts = day
+                            .parts
+                            .iter()
+                            .map(|part| {
+                                let answer = &part.answer.value;
+                                json::object([
+                                    ("part", Value::from(part.part.number())),
+                                    ("answer", Value::from(run::answer_text(answer))),
+                                    ("timed_out", Value::from(matches!(answer, Answer::TimedOut(_)))),
+                                    ("solve_ms", Value::from(millis(part.answer.elapsed))),
+                                ])
+                            })
+                            .collect();
+                        fields.push(("parse_ms", Value::from(millis(day.parse_time))));
+                        fields.push(("parts", Value::Array(parts)));
+                    }
+                    Err(e) => {
+                        fields.push(("error", Value::from(error(e))));
+                        if let Failure::Input(aoc_core::Error::Parse { error, .. }) = e {
+                            fields.push(("line", Value::from(error.line)));
+                            fields.push(("column", Value::from(error.column)));
+                        }
+                    }
+                }
+                json::object(fields)
+            })
+            .collect();
+        json::object([("day", Value::from(self.day)), ("inputs", Value::Array(inputs))])
+    }
+}
+
+/// `answer` on one line, for the table.
+fn cell(answer: &Answer) -> String {
+    match answer {
+        Answer::Image(image) => format!("[{}x{} image]", image.width(), image.height()),
+        other => other.to_string(),
+    }
+}
+
+fn status(answer: &Answer) -> &'static str {
+    match answer {
+        Answer::Unsolved => "unsolved",
+        Answer::TimedOut(_) => "timed_out",
+        _ => "ok",
+    }
+}
+
+/// Why an input failed. A parse error leaves out the path, which its row
+/// already gives.
+fn error(failure: &Failure) -> String {
+    match failure {
+        Failure::Input(aoc_core::Error::Parse { error, .. }) => error.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn millis(elapsed: Duration) -> f64 {
+    elapsed.as_secs_f64() * 1000.0
+}
+
+/// `field`, quoted if it has to be.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc_core::{Registry, Solution};
+
+    /// Sums its lines; an empty input has no answer for part 2
+    struct Sums;
+
+    impl Solution for Sums {
+        const DAY: u8 = 6;
+        type Input = Vec<i64>;
+
+        fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+            input.parse_lines()
+        }
+
+        fn part1(input: &Self::Input) -> Answer {
+            input.iter().sum::<i64>().into()
+        }
+
+        fn part2(input: &Self::Input) -> Answer {
+            match input.is_empty() {
+                true => Answer::Unsolved,
+                false => Answer::Text(format!("{},{}", input.len(), input[0])),
+            }
+        }
+    }
+
+    /// A fresh directory holding `files`
+    fn dir_with(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-batch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for (file, text) in files {
+            fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn globs() {
+        assert!(matches("in.*", "in.test.2"));
+        assert!(matches("in.?", "in.1"));
+        assert!(!matches("in.?", "in.10"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "aXbYbc"));
+        assert!(!matches("a*b", "ab.txt"));
+    }
+
+    #[test]
+    fn finds_inputs() {
+        let dir = dir_with("finds", &[("in.2", ""), ("in.1", ""), ("notes.md", ""), (".hidden", "")]);
+        let s = |p: &Path| p.to_string_lossy().into_owned();
+        let names = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths.iter().map(|p| p.strip_prefix(&dir).map_or_else(|_| s(p), s)).collect()
+        };
+
+        assert_eq!(names(inputs(&[s(&dir)]).unwrap()), ["in.1", "in.2", "notes.md"]);
+        assert_eq!(names(inputs(&[s(&dir.join("in.*")), s(&dir.join("gone"))]).unwrap()), ["in.1", "in.2", "gone"]);
+        assert!(inputs(&[s(&dir.join("*.txt"))]).is_err());
+        assert!(inputs(&[s(&dir.join("*").join("in.1"))]).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tabulates() {
+        let dir = dir_with("tabulates", &[("a", "1\n2\n"), ("b", "1\nx, y\n"), ("c", "")]);
+        let mut registry = Registry::new();
+        registry.register::<Sums>();
+        let paths: Vec<PathBuf> = ["a", "b", "c", "d"].iter().map(|f| dir.join(f)).collect();
+        let batch = run(registry.get(6).unwrap(), &paths, &Config::default(), &Part::ALL, &Budget::unlimited(), 2);
+        assert_eq!((batch.failed(), batch.timed_out()), (2, 0));
+
+        let mut csv = Vec::new();
+        batch.write(Format::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap().replace(&format!("{}/", dir.display()), "");
+        let csv: Vec<&str> = csv.lines().collect();
+        assert_eq!(csv[0], "input,error,parse_ms,part1,part1_status,part1_ms,part2,part2_status,part2_ms");
+        assert!(csv[1].starts_with("a,,") && csv[1].contains(",3,ok,") && csv[1].contains(",\"2,1\",ok,"), "{}", csv[1]);
+        assert_eq!(csv[2], "b,\"2:1: invalid digit found in string: \"\"x, y\"\"\",,,,,,,");
+        assert!(csv[3].contains(",0,ok,") && csv[3].contains(",,unsolved,"), "{}", csv[3]);
+        assert!(csv[4].starts_with("d,") && csv[4].ends_with(",,,,,,,"), "{}", csv[4]);
+
+        let json = batch.to_json().to_string().replace(&format!("{}/", dir.display()), "");
+        assert!(json.starts_with(r#"{"day":6,"inputs":[{"input":"a","parse_ms":"#), "{}", json);
+        assert!(json.contains(r#"{"part":2,"answer":"2,1","timed_out":false,"solve_ms":"#), "{}", json);
+        assert!(json.contains(r#""line":2,"column":1}"#), "{}", json);
+        assert!(json.contains(r#"{"part":2,"answer":null,"timed_out":false"#), "{}", json);
+
+        let mut table = Vec::new();
+        batch.write(Format::Table, &mut table).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0].split_whitespace().collect::<Vec<_>>(), ["input", "parse", "part", "1", "time", "part", "2", "time"]);
+        assert!(lines[2].contains("  failed: 2:1: invalid digit"), "{}", lines[2]);
+        assert!(lines[5].starts_with("4 inputs, 2 failed, 0 timed out"), "{}", lines[5]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+//! Repeated timing of parse, part 1 and part 2, with saved baselines.
+//!
+//! Results are written one record per line, tab separated:
+//! `day stage median_ns mad_ns min_ns max_ns runs`. The same file can be
+//! read back as a baseline to compare a later run against.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use aoc_core::budget::Budget;
+use aoc_core::{Input, Part, Solver};
+
+use crate::run::{self, Failure};
+
+/// What is being timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Parse,
+    Part(Part),
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part(part) => write!(f, "part{}", part),
+        }
+    }
+}
+
+impl FromStr for Stage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "parse" => Ok(Stage::Parse),
+            "part1" => Ok(Stage::Part(Part::One)),
+            "part2" => Ok(Stage::Part(Part::Two)),
+            _ => Err(format!("unknown stage {:?}", s)),
+        }
+    }
+}
+
+/// Summary of repeated timings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub runs: usize,
+    pub median: Duration,
+    /// Median absolute deviation from `median`.
+    pub mad: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+fn median(sorted: &[Duration]) -> Duration {
+    let mid = sorted.len() / 2;
+    match sorted.len() % 2 {
+        0 => (sorted[mid - 1] + sorted[mid]) / 2,
+        _ => sorted[mid],
+    }
+}
+
+impl Stats {
+    /// Panics if `samples` is empty.
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        let med = median(&samples);
+        let mut deviations: Vec<Duration> = samples.iter().map(|&s| s.abs_diff(med)).collect();
+        deviations.sort();
+        Stats {
+            runs: samples.len(),
+            median: med,
+            mad: median(&deviations),
+            min: samples[0],
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// How many times to run each stage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// Untimed runs before sampling.
+    pub warmup: usize,
+    pub runs: usize,
+    /// Stop sampling a stage early once it has taken this long, as long as
+    /// it has `MIN_RUNS` samples; the slow days would otherwise take minutes.
+    pub max_time: Duration,
+}
+
+pub const MIN_RUNS: usize = 3;
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            warmup: 1,
+            runs: 10,
+            max_time: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Timings of one stage of one day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub day: u8,
+    pub stage: Stage,
+    pub stats: Stats,
+}
+
+fn sample(options: &Options, mut f: impl FnMut() -> Result<Duration, Failure>) -> Result<Stats, Failure> {
+    for _ in 0..options.warmup {
+        f()?;
+    }
+    let start = Instant::now();
+    let mut samples = Vec::with_capacity(options.runs);
+    while samples.len() < options.runs.max(1) {
+        samples.push(f()?);
+        if samples.len() >= MIN_RUNS && start.elapsed() >= options.max_time {
+            break;
+        }
+    }
+    Ok(Stats::from_samples(samples))
+}
+
+/// Time parsing `input` and each of `parts`, each stage on its own.
+pub fn bench_day(solver: &dyn Solver, input: &Input, parts: &[Part], options: &Options) -> Result<Vec<Record>, Failure> {
+    let day = solver.day();
+    let mut records = vec![Record {
+        day,
+        stage: Stage::Parse,
+        stats: sample(options, || run::parse(solver, input).map(|p| p.elapsed))?,
+    }];
+    let parsed = run::parse(solver, input)?.value;
+    for &part in parts {
+        records.push(Record {
+            day,
+            stage: Stage::Part(part),
+            stats: sample(options, || run::solve(solver, &parsed, part, &Budget::unlimited()).map(|a| a.elapsed))?,
+        });
+    }
+    Ok(records)
+}
+
+pub fn write_records(mut w: impl Write, records: &[Record]) -> io::Result<()> {
+    writeln!(w, "# day\tstage\tmedian_ns\tmad_ns\tmin_ns\tmax_ns\truns")?;
+    for r in records {
+        let s = &r.stats;
+        writeln!(
+            w,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            r.day,
+            r.stage,
+            s.median.as_nanos(),
+            s.mad.as_nanos(),
+            s.min.as_nanos(),
+            s.max.as_nanos(),
+            s.runs
+        )?;
+    }
+    Ok(())
+}
+
+/// Read what [`write_records`] wrote.
+pub fn read_records(input: &Input) -> aoc_core::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| input.parse_error(i + 1, line, msg);
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return Err(err("expected 7 tab-separated fields"));
+        }
+        let nanos = |s: &str| s.parse::<u64>().map(Duration::from_nanos).map_err(|_| err("invalid duration"));
+        records.push(Record {
+            day: fields[0].parse().map_err(|_| err("invalid day"))?,
+            stage: fields[1].parse().map_err(|e: String| err(&e))?,
+            stats: Stats {
+                median: nanos(fields[2])?,
+                mad: nanos(fields[3])?,
+                min: nanos(fields[4])?,
+                max: nanos(fields[5])?,
+                runs: fields[6].parse().map_err(|_| err("invalid run count"))?,
+            },
+        });
+    }
+    Ok(records)
+}
+
+/// Medians of a saved run, to compare against.
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    medians: BTreeMap<(u8, Stage), Duration>,
+}
+
+impl Baseline {
+    pub fn new(records: &[Record]) -> Self {
+        Baseline {
+            medians: records.iter().map(|r| ((r.day, r.stage), r.stats.median)).collect(),
+        }
+    }
+
+    /// Relative change of `record`'s median against the baseline, e.g. `0.1`
+    /// for 10% slower. `None` if the baseline has no such record.
+    pub fn change(&self, record: &Record) -> Option<f64> {
+        let base = self.medians.get(&(record.day, record.stage))?;
+        if base.is_zero() {
+            return None;
+        }
+        Some(record.stats.median.as_secs_f64() / base.as_secs_f64() - 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(v: &[u64]) -> Vec<Duration> {
+        v.iter().map(|&m| Duration::from_millis(m)).collect()
+    }
+
+    #[test]
+    fn stats() {
+        let stats = Stats::from_samples(ms(&[5, 1, 3, 100, 4]));
+        assert_eq!(stats.runs, 5);
+        assert_eq!(stats.median, Duration::from_millis(4));
+        assert_eq!(stats.mad, Duration::from_millis(1));
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert_eq!(Stats::from_samples(ms(&[2, 4])).median, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let records = vec![
+            Record { day: 3, stage: Stage::Parse, stats: Stats::from_samples(ms(&[1, 2, 3])) },
+            Record { day: 3, stage: Stage::Part(Part::Two), stats: Stats::from_samples(ms(&[10])) },
+        ];
+        let mut out = Vec::new();
+        write_records(&mut out, &records).unwrap();
+        let read = read_records(&Input::from(String::from_utf8(out).unwrap())).unwrap();
+        assert_eq!(read, records);
+
+        let mut slower = records[1].clone();
+        slower.stats.median = Duration::from_millis(15);
+        let baseline = Baseline::new(&read);
+        assert_eq!(baseline.change(&slower), Some(0.5));
+        slower.day = 4;
+        assert_eq!(baseline.change(&slower), None);
+    }
+}
@@ -0,0 +1,93 @@
+//! `aoc-serve`: answer puzzles over HTTP, for tools that cannot link Rust.
+
+use std::env;
+use std::net::TcpListener;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use aoc_core::budget::Budget;
+use aoc_core::config::Config;
+use aoc_runner::serve::{Server, DEFAULT_THREADS};
+
+const USAGE: &str = "\
+usage: aoc-serve [--addr HOST:PORT] [--timeout SECS] [--threads N]
+
+Listens on --addr (default 127.0.0.1:8080) and answers
+  POST /day/<n>/part/<p>[?name=FILE]   the request body is the puzzle input
+  GET  /days                           the days that can be answered
+with JSON. `name` picks the input's table in aoc.toml.
+`--timeout` (or AOC_TIMEOUT) gives each part that many seconds.
+`--threads` (default 4) connections are answered at once; more wait their turn.";
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    addr: String,
+    timeout: Option<Duration>,
+    threads: usize,
+}
+
+/// None for `--help`.
+fn parse_args(args: &[String]) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        addr: "127.0.0.1:8080".to_string(),
+        timeout: None,
+        threads: DEFAULT_THREADS,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--addr" => parsed.addr = value()?.clone(),
+            "--timeout" => {
+                let secs = value()?;
+                let secs: f64 = secs.parse().map_err(|_| format!("invalid {} {:?}", arg, secs))?;
+                parsed.timeout = Some(Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())?);
+            }
+            "--threads" => {
+                let threads = value()?;
+                parsed.threads = match threads.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid {} {:?}", arg, threads)),
+                };
+            }
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("unexpected argument {:?}", other)),
+        }
+    }
+    Ok(Some(parsed))
+}
+
+fn serve(args: Args) -> Result<(), String> {
+    let budget = match args.timeout {
+        Some(timeout) => Budget::unlimited().with_timeout(timeout),
+        None => Budget::from_env(None)?,
+    };
+    let config = Config::workspace().map_err(|e| e.to_string())?;
+    let listener = TcpListener::bind(&args.addr).map_err(|e| format!("cannot listen on {}: {}", args.addr, e))?;
+    let server = Server::new(listener, aoc_runner::registry(), config, budget).with_threads(args.threads);
+    if let Ok(addr) = server.local_addr() {
+        eprintln!("aoc-serve: listening on http://{}", addr);
+    }
+    server.serve().map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(Some(args)) => match serve(args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("aoc-serve: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Ok(None) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("aoc-serve: {}\n\n{}", e, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
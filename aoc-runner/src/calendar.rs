@@ -0,0 +1,242 @@
+//! Running many days at once on a pool of threads.
+//!
+//! Every part of every day is a job of its own. A parsed input stays on the
+//! thread that made it (day 7's tree is made of `Rc`s), so each job parses the
+//! day's input for itself; past that the two parts of a day have nothing to
+//! share and run side by side like any two days. Results come back in the
+//! order the days were given, whatever order the jobs finish in.
+//!
+//! A day's CPU time is what the OS says its jobs' threads ran for, where it
+//! says (Linux), so that more threads than cores do not count time spent
+//! waiting for one; elsewhere it is the time the jobs took.
+
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use aoc_core::budget::Budget;
+use aoc_core::{Input, Part, Solver};
+
+use crate::run::{self, DayRun, Failure, PartRun, Timed};
+
+/// What [`run()`] found out about every day.
+#[derive(Debug)]
+pub struct Calendar {
+    /// One entry per day, in the order they were given.
+    pub days: Vec<Result<DayRun, Failure>>,
+    /// From starting the first job to finishing the last.
+    pub wall_time: Duration,
+    pub threads: usize,
+}
+
+impl Calendar {
+    /// Time the threads spent on the days that could be answered, added up.
+    pub fn cpu_time(&self) -> Duration {
+        self.days.iter().flatten().map(|d| d.cpu_time).sum()
+    }
+}
+
+/// As many threads as the machine can run at once.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Answer `parts` of each day on `threads` threads, each part within `budget`.
+pub fn run(days: &[(&dyn Solver, Input)], parts: &[Part], budget: &Budget, threads: usize) -> Calendar {
+    let jobs: Vec<(usize, Part)> = (0..days.len())
+        .flat_map(|d| parts.iter().map(move |&part| (d, part)))
+        .collect();
+    let threads = threads.clamp(1, jobs.len().max(1));
+    let next = AtomicUsize::new(0);
+    let (send, receive) = mpsc::channel();
+    let start = Instant::now();
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let send = send.clone();
+            let (jobs, next) = (&jobs, &next);
+            scope.spawn(move || {
+                while let Some(&(d, part)) = jobs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let (solver, input) = &days[d];
+                    let done = run_part(*solver, input, part, budget);
+                    // Only fails once the receiver is gone, and it outlives the scope
+                    let _ = send.send(((d, part), done));
+                }
+            });
+        }
+    });
+    drop(send);
+    let wall_time = start.elapsed();
+
+    let mut done: Vec<_> = receive.into_iter().collect();
+    done.sort_by_key(|&((d, part), _)| (d, part == Part::Two));
+    let mut done = done.into_iter().peekable();
+    let days = days
+        .iter()
+        .enumerate()
+        .map(|(d, (solver, _))| {
+            let mut day = DayRun {
+                day: solver.day(),
+                parse_time: Duration::ZERO,
+                parts: Vec::new(),
+                cpu_time: Duration::ZERO,
+            };
+            let mut failure = None;
+            while let Some((_, result)) = done.next_if(|((of, _), _)| *of == d) {
+                match result {
+                    Ok((parse, part, cpu)) => {
+                        if day.parts.is_empty() {
+                            day.parse_time = parse;
+                        }
+                        day.cpu_time += cpu;
+                        day.parts.push(part);
+                    }
+                    Err(e) => failure = failure.or(Some(e)),
+                }
+            }
+            match failure {
+                Some(e) => Err(e),
+                None => Ok(day),
+            }
+        })
+        .collect();
+    Calendar { days, wall_time, threads }
+}
+
+/// Parse `input` and solve `part` of it: how long the parse took, the answer,
+/// and the CPU time it all took.
+fn run_part(
+    solver: &dyn Solver,
+    input: &Input,
+    part: Part,
+    budget: &Budget,
+) -> Result<(Duration, PartRun, Duration), Failure> {
+    let before = thread_cpu_time();
+    let Timed { value, elapsed } = run::parse(solver, input)?;
+    let answer = run::solve(solver, &value, part, budget)?;
+    let cpu = match (before, thread_cpu_time()) {
+        (Some(before), Some(after)) => after.saturating_sub(before),
+        _ => elapsed + answer.elapsed,
+    };
+    Ok((elapsed, PartRun { part, answer }, cpu))
+}
+
+/// CPU time the current thread has had so far, if the OS tells.
+fn thread_cpu_time() -> Option<Duration> {
+    let stat = fs::read_to_string("/proc/thread-self/schedstat").ok()?;
+    Some(Duration::from_nanos(stat.split_whitespace().next()?.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc_core::{Answer, Registry, Solution};
+
+    /// Sleeps for 20 less its day milliseconds, so later days finish first
+    /// when they all start together
+    struct Sleeps<const N: u8>;
+
+    impl<const N: u8> Solution for Sleeps<N> {
+        const DAY: u8 = N;
+        type Input = u64;
+
+        fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+            match input.as_str() {
+                "bad" => Err(input.parse_error(1, "bad", "bad input")),
+                _ => Ok(20 - N as u64),
+            }
+        }
+
+        fn part1(input: &Self::Input) -> Answer {
+            thread::sleep(Duration::from_millis(*input));
+            (N as u64 * 10 + 1).into()
+        }
+
+        fn part2(input: &Self::Input) -> Answer {
+            thread::sleep(Duration::from_millis(*input));
+            (N as u64 * 10 + 2).into()
+        }
+    }
+
+    /// Each part waits for the other to start, so it only gets both answers
+    /// if they run at the same time
+    struct Meet;
+
+    static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+
+    fn meet() -> Answer {
+        ARRIVED.fetch_add(1, Ordering::SeqCst);
+        let start = Instant::now();
+        while ARRIVED.load(Ordering::SeqCst) < 2 && start.elapsed() < Duration::from_secs(5) {
+            thread::yield_now();
+        }
+        (ARRIVED.load(Ordering::SeqCst) as u64).into()
+    }
+
+    impl Solution for Meet {
+        const DAY: u8 = 1;
+        type Input = ();
+
+        fn parse(_input: &Input) -> aoc_core::Result<Self::Input> {
+            Ok(())
+        }
+
+        fn part1(_input: &Self::Input) -> Answer {
+            meet()
+        }
+
+        fn part2(_input: &Self::Input) -> Answer {
+            meet()
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register::<Sleeps<2>>();
+        registry.register::<Sleeps<3>>();
+        registry.register::<Sleeps<4>>();
+        registry
+    }
+
+    fn answers(calendar: &Calendar) -> Vec<Result<Vec<String>, String>> {
+        let answers = |d: &DayRun| d.parts.iter().map(|p| p.answer.value.to_string()).collect();
+        calendar.days.iter().map(|d| d.as_ref().map(answers).map_err(|e| e.to_string())).collect()
+    }
+
+    #[test]
+    fn stable_order() {
+        let registry = registry();
+        let days: Vec<(&dyn Solver, Input)> = [4, 2, 3]
+            .into_iter()
+            .map(|day| (registry.get(day).unwrap(), Input::from(if day == 3 { "bad" } else { "" })))
+            .collect();
+        for threads in [1, 2, 6] {
+            let calendar = run(&days, &Part::ALL, &Budget::unlimited(), threads);
+            assert_eq!(calendar.threads, threads);
+            assert_eq!(
+                answers(&calendar),
+                vec![
+                    Ok(vec!["41".to_string(), "42".to_string()]),
+                    Ok(vec!["21".to_string(), "22".to_string()]),
+                    Err("<memory>:1:1: bad input: \"bad\"".to_string()),
+                ]
+            );
+            let (first, second) = (calendar.days[0].as_ref().unwrap(), calendar.days[1].as_ref().unwrap());
+            assert_eq!(calendar.cpu_time(), first.cpu_time + second.cpu_time);
+            assert!(calendar.wall_time >= Duration::from_millis(18));
+        }
+        let one = run(&days[..1], &[Part::Two], &Budget::unlimited(), 8);
+        assert_eq!(one.threads, 1);
+        assert_eq!(answers(&one), vec![Ok(vec!["42".to_string()])]);
+    }
+
+    #[test]
+    fn parts_run_together() {
+        let mut registry = Registry::new();
+        registry.register::<Meet>();
+        let days = [(registry.get(1).unwrap(), Input::from(""))];
+        let calendar = run(&days, &Part::ALL, &Budget::unlimited(), 2);
+        assert_eq!(answers(&calendar), vec![Ok(vec!["2".to_string(), "2".to_string()])]);
+    }
+}
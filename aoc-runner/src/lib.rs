@@ -0,0 +1,113 @@
+//! Every day's [`Solution`](aoc_core::Solution) collected into one
+//! [`Registry`], for tools that work across the whole calendar.
+
+pub mod batch;
+pub mod bench;
+pub mod calendar;
+pub mod run;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod watch;
+
+use aoc_core::Registry;
+
+/// Registry holding every solved day.
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry.register::<aoc1::Day1>();
+    registry.register::<aoc2::Day2>();
+    registry.register::<aoc3::Day3>();
+    registry.register::<aoc4::Day4>();
+    registry.register::<aoc5::Day5>();
+    registry.register::<aoc6::Day6>();
+    registry.register::<aoc7::Day7>();
+    registry.register::<aoc8::Day8>();
+    registry.register::<aoc9::Day9>();
+    registry.register::<aoc10::Day10>();
+    registry.register::<aoc11::Day11>();
+    registry.register::<aoc12::Day12>();
+    registry.register::<aoc13::Day13>();
+    registry.register::<aoc14::Day14>();
+    registry.register::<aoc15::Day15>();
+    registry.register::<aoc16::Day16>();
+    registry.register::<aoc17::Day17>();
+    registry.register::<aoc18::Day18>();
+    registry.register::<aoc20::Day20>();
+    registry.register::<aoc21::Day21>();
+    registry.register::<aoc22::Day22>();
+    registry.register::<aoc23::Day23>();
+    registry.register::<aoc24::Day24>();
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_registered() {
+        let registry = registry();
+        let days: Vec<u8> = registry.days().collect();
+        assert_eq!(days.len(), 23);
+        assert_eq!(days.first(), Some(&1));
+        assert_eq!(days.last(), Some(&24));
+        assert!(registry.get(19).is_none());
+        for day in days {
+            assert_eq!(registry.get(day).unwrap().day(), day);
+        }
+    }
+
+    /// The day crates' model types, built from outside the crates
+    #[test]
+    fn day_types_are_public() {
+        use std::collections::HashSet;
+
+        use aoc_core::geom::{Dir4, Point2};
+        use aoc_core::simulation::Simulation;
+        use aoc_core::Input;
+
+        let ship = aoc5::Ship::new(["ZN", "MCD", "P"], vec![aoc5::Instr::new(1, 2, 1)]);
+        assert_eq!(ship.simulate_part1(), "DCP");
+
+        let session = aoc7::Session::new();
+        session.construct_graph(&Input::from("$ cd /\n$ ls\ndir a\n100 b\n$ cd a\n$ ls\n50 c\n")).unwrap();
+        let device = aoc7::Device::new(session, 200, 100);
+        assert_eq!(device.session().root().size(), 150);
+        assert_eq!(device.smallest_to_delete(), 50);
+
+        let packet: aoc13::ListNode = "[1,[2,3]]".parse().unwrap();
+        assert_eq!(packet.len(), 2);
+
+        let report = aoc15::Report::new(vec![aoc15::Pair::new((8, 7), (2, 10))], 10, 20);
+        assert_eq!(report.session().count_impossibles(report.row()), 12);
+
+        let elves: HashSet<_> = [(2, 1), (3, 1), (2, 2), (2, 4), (3, 4)].into_iter().map(Point2::from).collect();
+        let mut spreading = aoc23::Session::new(aoc23::Elves::new(elves));
+        assert_eq!(spreading.run_to_end(), 3);
+        assert_eq!(spreading.count_empty(), 25);
+
+        let mut blizzard = aoc24::Blizzard::new((1, 3), Dir4::E);
+        blizzard.update_loc(4, 4);
+        assert_eq!(blizzard.pos, (1, 1));
+    }
+
+    #[test]
+    fn generated_inputs_parse() {
+        use aoc_core::config::Config;
+        use aoc_core::gen::Rng;
+        use aoc_core::Input;
+
+        let config = Config::workspace().unwrap();
+
+        for solver in registry().iter() {
+            for seed in 0..4 {
+                let generate = || solver.generate(&mut Rng::new(seed), 1);
+                let text = generate().unwrap_or_else(|| panic!("day {} has no generator", solver.day()));
+                assert_eq!(generate().as_ref(), Some(&text), "day {} seed {}", solver.day(), seed);
+                if let Err(e) = solver.parse(&Input::from(text).with_params(config.params(solver.day(), None))) {
+                    panic!("day {} seed {}: {}", solver.day(), seed, e);
+                }
+            }
+        }
+    }
+}
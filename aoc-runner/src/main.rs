@@ -0,0 +1,877 @@
+//! `aoc`: run and benchmark days from the command line.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Duration;
+
+use aoc_core::budget::Budget;
+use aoc_core::config::Config;
+use aoc_core::gen::Rng;
+use aoc_core::render::{self, Frames, Palette};
+use aoc_core::trace::{self, Tracer};
+use aoc_core::{Grid, Input, Part, Registry, Solver};
+use aoc_runner::batch;
+use aoc_runner::bench::{self, Baseline, Record};
+use aoc_runner::calendar;
+use aoc_runner::run::{self, DayRun, Failure};
+use aoc_runner::watch;
+
+const USAGE: &str = "\
+usage: aoc run <day> [--part 1|2] [--input PATH|-] [--timeout SECS] [--jobs N]
+       aoc run --all [--part 1|2] [--timeout SECS] [--jobs N]
+       aoc bench <day>|--all [--part 1|2] [--input PATH|-] [--runs N] [--warmup N]
+                 [--max-time SECS] [--save PATH] [--baseline PATH] [--threshold PCT]
+       aoc render <day> [--part 1|2] [--input PATH|-] [--format ascii|ppm|gif]
+                  [--every N] [--scale N] [--delay CS] [--out PATH]
+       aoc gen <day> [--seed N] [--size N] [--out PATH]
+       aoc batch <day> <FILE|DIR|GLOB>... [--part 1|2] [--timeout SECS] [--jobs N]
+                 [--format table|csv|json] [--out PATH]
+       aoc watch <day> [FILE|DIR|GLOB]... [--part 1|2] [--timeout SECS] [--interval SECS]
+
+Every command also takes [--trace FILTER] [--trace-format text|json].
+
+Inputs default to aocN/in.1; `--input -` reads stdin.
+`run --timeout` (or AOC_TIMEOUT) gives each part that many seconds; a part that
+runs out prints the best answer it had, if any, and the run fails.
+`run` solves every part of every day at once on `--jobs` threads (default: one
+per core) and prints the days in order once all are done, then the wall time
+and the time spent on the days added up.
+`bench` times parse and each part separately. `--save` writes the results as
+tab-separated lines; `--baseline` compares against such a file and fails if a
+median got more than `--threshold` percent (default 10) slower.
+`render` solves one part (default 1) of a simulation day and keeps every Nth
+frame. ASCII goes to stdout unless `--out` is given; `ppm` writes one image per
+frame into the `--out` directory and `gif` one animation to the `--out` file,
+with each cell `--scale` pixels wide (default 4) and `--delay` hundredths of a
+second between frames (default 10).
+`gen` writes a random input for a day to stdout or `--out`, about `--size`
+times as big as a real one (default 1). The same `--seed` (default 0) gives
+the same input.
+`batch` runs one day over many inputs: files, every file in a directory, or a
+glob such as 'inputs/in.*' (wildcards in the file name only). It prints a
+table of answers and timings, or `--format csv` or `json`, to stdout or `--out`,
+and fails if any input failed or timed out.
+`watch` checks the day's src/, its inputs (default aocN/in.*) and aoc.toml every
+`--interval` seconds (default 0.5). On a change it rebuilds the runner with
+cargo if a source file changed, runs the day over every input, and prints the
+answers and timings against the run before. It runs until interrupted.
+`--trace` writes solver events to stderr, overriding AOC_TRACE: a level
+(error, warn, info, debug, trace) optionally followed by per-day levels, as in
+`info,14=trace`.";
+
+/// Which days a command covers.
+#[derive(Debug, PartialEq)]
+enum Days {
+    One(u8),
+    All,
+}
+
+#[derive(Debug, PartialEq)]
+struct Target {
+    days: Days,
+    part: Option<Part>,
+    input: Option<String>,
+}
+
+impl Target {
+    fn parts(&self) -> Vec<Part> {
+        match self.part {
+            Some(part) => vec![part],
+            None => Part::ALL.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct RunArgs {
+    target: Target,
+    /// Time each part may take; AOC_TIMEOUT when not given.
+    timeout: Option<Duration>,
+    /// Threads to run on; as many as the machine has when not given.
+    jobs: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+struct BenchArgs {
+    target: Target,
+    options: bench::Options,
+    save: Option<String>,
+    baseline: Option<String>,
+    /// Percent slowdown against the baseline that counts as a regression.
+    threshold: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    Ppm,
+    Gif,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Format::Ascii),
+            "ppm" => Ok(Format::Ppm),
+            "gif" => Ok(Format::Gif),
+            _ => Err(format!("unknown format {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct RenderArgs {
+    day: u8,
+    part: Part,
+    input: Option<String>,
+    format: Format,
+    every: usize,
+    scale: usize,
+    /// Hundredths of a second between GIF frames.
+    delay: u16,
+    out: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct GenArgs {
+    day: u8,
+    seed: u64,
+    size: usize,
+    out: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Run(RunArgs),
+    Bench(BenchArgs),
+    Render(RenderArgs),
+    Gen(GenArgs),
+    Batch(batch::Args),
+    Watch(watch::Args),
+    Help,
+}
+
+fn value<'a>(args: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a str, String> {
+    args.next().map(|s| s.as_str()).ok_or_else(|| format!("{} needs a value", flag))
+}
+
+fn number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid {} {:?}", flag, value))
+}
+
+/// Pull `--trace` and `--trace-format` out of `args`, wherever they are, and
+/// return the remaining arguments with the tracer they ask for.
+fn trace_args(args: &[String]) -> Result<(Vec<String>, Option<Tracer>), String> {
+    let mut rest = Vec::new();
+    let mut filter = None;
+    let mut format = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => filter = Some(value(&mut args, arg)?.parse::<trace::Filter>()?),
+            "--trace-format" => format = Some(value(&mut args, arg)?.parse::<trace::Format>()?),
+            _ => rest.push(arg.clone()),
+        }
+    }
+    let tracer = match (filter, format) {
+        (Some(filter), format) => Some(Tracer::new(filter, format.unwrap_or_default())),
+        (None, Some(_)) => return Err("--trace-format needs --trace".to_string()),
+        (None, None) => None,
+    };
+    Ok((rest, tracer))
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+    let command = match args.next().map(|s| s.as_str()) {
+        Some(command @ ("run" | "bench" | "render" | "gen" | "batch" | "watch")) => command,
+        Some("-h") | Some("--help") | Some("help") => return Ok(Command::Help),
+        Some(other) => return Err(format!("unknown command {:?}", other)),
+        None => return Err("missing command".to_string()),
+    };
+
+    let mut days = None;
+    let mut part = None;
+    let mut input = None;
+    let mut options = bench::Options::default();
+    let mut save = None;
+    let mut baseline = None;
+    let mut threshold = 10.0;
+    let mut format = Format::Ascii;
+    let mut every = 1;
+    let mut scale = 4;
+    let mut delay = 10;
+    let mut out = None;
+    let mut seed = 0;
+    let mut size = 1;
+    let mut timeout = None;
+    let mut jobs = None;
+    let mut batch_format = batch::Format::Table;
+    let mut inputs = Vec::new();
+    let mut interval = Duration::from_millis(500);
+    while let Some(arg) = args.next() {
+        match (command, arg.as_str()) {
+            (_, "--all") => days = Some(Days::All),
+            (_, "--part") => part = Some(value(&mut args, arg)?.parse::<Part>()?),
+            (_, "--input") => input = Some(value(&mut args, arg)?.to_string()),
+            ("bench", "--runs") => options.runs = number(value(&mut args, arg)?, arg)?,
+            ("bench", "--warmup") => options.warmup = number(value(&mut args, arg)?, arg)?,
+            ("bench", "--max-time") => {
+                let secs: f64 = number(value(&mut args, arg)?, arg)?;
+                options.max_time = Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())?;
+            }
+            ("run" | "batch" | "watch", "--timeout") => {
+                let secs: f64 = number(value(&mut args, arg)?, arg)?;
+                timeout = Some(Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())?);
+            }
+            ("run" | "batch", "--jobs") => match number(value(&mut args, arg)?, arg)? {
+                0 => return Err("--jobs must be at least 1".to_string()),
+                n => jobs = Some(n),
+            },
+            ("bench", "--save") => save = Some(value(&mut args, arg)?.to_string()),
+            ("bench", "--baseline") => baseline = Some(value(&mut args, arg)?.to_string()),
+            ("bench", "--threshold") => threshold = number(value(&mut args, arg)?, arg)?,
+            ("render", "--format") => format = value(&mut args, arg)?.parse()?,
+            ("render", "--every") => every = number(value(&mut args, arg)?, arg)?,
+            ("render", "--scale") => scale = number(value(&mut args, arg)?, arg)?,
+            ("render", "--delay") => delay = number(value(&mut args, arg)?, arg)?,
+            ("watch", "--interval") => {
+                let secs: f64 = number(value(&mut args, arg)?, arg)?;
+                interval = Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())?;
+            }
+            ("batch", "--format") => batch_format = value(&mut args, arg)?.parse()?,
+            ("render" | "gen" | "batch", "--out") => out = Some(value(&mut args, arg)?.to_string()),
+            ("gen", "--seed") => seed = number(value(&mut args, arg)?, arg)?,
+            ("gen", "--size") => size = number(value(&mut args, arg)?, arg)?,
+            (_, "-h" | "--help") => return Ok(Command::Help),
+            (_, day) if days.is_none() && !day.starts_with("--") => {
+                days = Some(Days::One(number(day, "day")?));
+            }
+            ("batch" | "watch", path) if !path.starts_with("--") => inputs.push(path.to_string()),
+            (_, other) => return Err(format!("unexpected argument {:?}", other)),
+        }
+    }
+
+    let days = days.ok_or("missing day (or --all)")?;
+    if days == Days::All && input.is_some() {
+        return Err("--input cannot be combined with --all".to_string());
+    }
+    if command == "render" {
+        let Days::One(day) = days else {
+            return Err("render takes a single day".to_string());
+        };
+        if format != Format::Ascii && out.is_none() {
+            return Err("--out is needed for ppm and gif".to_string());
+        }
+        return Ok(Command::Render(RenderArgs {
+            day,
+            part: part.unwrap_or(Part::One),
+            input,
+            format,
+            every,
+            scale,
+            delay,
+            out,
+        }));
+    }
+    if command == "gen" {
+        let Days::One(day) = days else {
+            return Err("gen takes a single day".to_string());
+        };
+        if part.is_some() || input.is_some() {
+            return Err("gen takes no --part or --input".to_string());
+        }
+        if size == 0 {
+            return Err("--size must be at least 1".to_string());
+        }
+        return Ok(Command::Gen(GenArgs { day, seed, size, out }));
+    }
+    if command == "batch" {
+        let Days::One(day) = days else {
+            return Err("batch takes a single day".to_string());
+        };
+        if input.is_some() {
+            return Err("batch takes its inputs without --input".to_string());
+        }
+        if inputs.is_empty() {
+            return Err("batch needs at least one file, directory or glob".to_string());
+        }
+        return Ok(Command::Batch(batch::Args {
+            day,
+            part,
+            inputs,
+            timeout,
+            jobs,
+            format: batch_format,
+            out,
+        }));
+    }
+    if command == "watch" {
+        let Days::One(day) = days else {
+            return Err("watch takes a single day".to_string());
+        };
+        if input.is_some() {
+            return Err("watch takes its inputs without --input".to_string());
+        }
+        return Ok(Command::Watch(watch::Args {
+            day,
+            part,
+            inputs,
+            timeout,
+            interval,
+        }));
+    }
+    let target = Target { days, part, input };
+    Ok(match command {
+        "bench" => Command::Bench(BenchArgs {
+            target,
+            options,
+            save,
+            baseline,
+            threshold,
+        }),
+        _ => Command::Run(RunArgs { target, timeout, jobs }),
+    })
+}
+
+/// The input for `day`, with its parameters from the configuration file.
+fn load(day: u8, input: Option<&str>) -> Result<Input, Failure> {
+    let input = match input {
+        Some("-") => Input::from_stdin()?,
+        Some(path) => Input::from_path(path)?,
+        None => Input::from_path(run::default_input(day))?,
+    };
+    let params = Config::workspace()?.params(day, input.name());
+    Ok(input.with_params(params))
+}
+
+fn print_day(run: &DayRun) {
+    println!("day {} (parse {:.2?}, cpu {:.2?})", run.day, run.parse_time, run.cpu_time);
+    for part in &run.parts {
+        let answer = part.answer.value.to_string();
+        if answer.contains('\n') {
+            println!("  part {}: [{:.2?}]", part.part, part.answer.elapsed);
+            for line in answer.lines() {
+                println!("    {}", line);
+            }
+        } else {
+            println!("  part {}: {} [{:.2?}]", part.part, answer, part.answer.elapsed);
+        }
+    }
+}
+
+/// Days `target` covers, in order.
+fn days(registry: &Registry, target: &Target) -> Vec<u8> {
+    match target.days {
+        Days::One(day) => vec![day],
+        Days::All => registry.days().collect(),
+    }
+}
+
+/// The solver and input for `day`.
+fn prepare<'a>(registry: &'a Registry, day: u8, target: &Target) -> Result<(&'a dyn Solver, Input), String> {
+    let solver = registry.get(day).ok_or(Failure::Unregistered(day).to_string())?;
+    let input = load(day, target.input.as_deref()).map_err(|e| e.to_string())?;
+    Ok((solver, input))
+}
+
+/// Print what `run` found, or why it failed.
+fn report(run: Result<DayRun, Failure>, input: &Input, budget: &Budget) -> Result<(), String> {
+    let run = run.map_err(|e| e.describe(input))?;
+    print_day(&run);
+    if let Some(part) = run.timed_out().next() {
+        let limit = budget.timeout().map_or(String::new(), |t| format!(" after {:.2?}", t));
+        return Err(format!("part {} timed out{}", part, limit));
+    }
+    Ok(())
+}
+
+/// A budget of `timeout` per part, or AOC_TIMEOUT when not given.
+fn budget(timeout: Option<Duration>) -> Result<Budget, String> {
+    match timeout {
+        Some(timeout) => Ok(Budget::unlimited().with_timeout(timeout)),
+        None => Budget::from_env(None),
+    }
+}
+
+fn run(args: RunArgs) -> ExitCode {
+    let budget = match budget(args.timeout) {
+        Ok(budget) => budget,
+        Err(e) => {
+            eprintln!("aoc: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let target = args.target;
+    let registry = aoc_runner::registry();
+    let days = days(&registry, &target);
+    let prepared: Vec<_> = days.iter().map(|&day| prepare(&registry, day, &target)).collect();
+    let ready: Vec<_> = prepared.iter().flatten().cloned().collect();
+    let threads = args.jobs.unwrap_or_else(calendar::default_threads);
+    let calendar = calendar::run(&ready, &target.parts(), &budget, threads);
+
+    let (wall_time, cpu_time, threads) = (calendar.wall_time, calendar.cpu_time(), calendar.threads);
+    let mut runs = calendar.days.into_iter().zip(&ready);
+    let mut failed = 0;
+    for (day, prepared) in days.iter().zip(prepared) {
+        let result = prepared.and_then(|_| {
+            let (run, (_, input)) = runs.next().expect("a run for every prepared day");
+            report(run, input, &budget)
+        });
+        if let Err(e) = result {
+            eprintln!("day {}: {}", day, e);
+            failed += 1;
+        }
+    }
+
+    if target.days == Days::All {
+        println!(
+            "{} days, {} failed, wall {:.2?}, cpu {:.2?} on {} threads",
+            days.len(),
+            failed,
+            wall_time,
+            cpu_time,
+            threads
+        );
+    }
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn bench_one(registry: &Registry, day: u8, args: &BenchArgs) -> Result<Vec<Record>, String> {
+    let solver = registry.get(day).ok_or(Failure::Unregistered(day).to_string())?;
+    let input = load(day, args.target.input.as_deref()).map_err(|e| e.to_string())?;
+    bench::bench_day(solver, &input, &args.target.parts(), &args.options).map_err(|e| e.describe(&input))
+}
+
+fn load_baseline(path: &str) -> aoc_core::Result<Baseline> {
+    let records = bench::read_records(&Input::from_path(path)?)?;
+    Ok(Baseline::new(&records))
+}
+
+/// Print one row of the bench table; true if it regressed against `baseline`.
+fn print_record(record: &Record, baseline: Option<&Baseline>, threshold: f64) -> bool {
+    let s = &record.stats;
+    let mut row = format!(
+        "{:>3}  {:<5}  {:>10}  {:>10}  {:>10}  {:>10}  {:>4}",
+        record.day,
+        record.stage.to_string(),
+        format!("{:.2?}", s.median),
+        format!("{:.2?}", s.mad),
+        format!("{:.2?}", s.min),
+        format!("{:.2?}", s.max),
+        s.runs
+    );
+    let mut regressed = false;
+    if let Some(baseline) = baseline {
+        match baseline.change(record) {
+            Some(change) => {
+                regressed = change * 100.0 > threshold;
+                row.push_str(&format!("  {:>+8.1}%", change * 100.0));
+                if regressed {
+                    row.push_str("  REGRESSED");
+                }
+            }
+            None => row.push_str(&format!("  {:>9}", "(new)")),
+        }
+    }
+    println!("{}", row);
+    regressed
+}
+
+fn bench(args: BenchArgs) -> ExitCode {
+    let registry = aoc_runner::registry();
+    let baseline = match args.baseline.as_deref().map(load_baseline).transpose() {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            eprintln!("aoc: baseline {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut header = format!(
+        "{:>3}  {:<5}  {:>10}  {:>10}  {:>10}  {:>10}  {:>4}",
+        "day", "stage", "median", "mad", "min", "max", "runs"
+    );
+    if baseline.is_some() {
+        header.push_str("  vs baseline");
+    }
+    println!("{}", header);
+
+    let mut records = Vec::new();
+    let mut failed = 0;
+    let mut regressed = 0;
+    for day in days(&registry, &args.target) {
+        match bench_one(&registry, day, &args) {
+            Ok(day_records) => {
+                for record in &day_records {
+                    if print_record(record, baseline.as_ref(), args.threshold) {
+                        regressed += 1;
+                    }
+                }
+                records.extend(day_records);
+            }
+            Err(e) => {
+                eprintln!("day {}: {}", day, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if let Some(path) = &args.save {
+        let saved = File::create(path).and_then(|f| bench::write_records(BufWriter::new(f), &records));
+        if let Err(e) = saved {
+            eprintln!("aoc: {}: {}", path, e);
+            failed += 1;
+        }
+    }
+    if regressed > 0 {
+        println!("{} regressed by more than {}%", regressed, args.threshold);
+    }
+    if failed == 0 && regressed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// The frames kept while solving, or `None` if the day draws nothing.
+fn record(registry: &Registry, args: &RenderArgs) -> Result<Option<Vec<Grid<char>>>, String> {
+    let solver = registry.get(args.day).ok_or(Failure::Unregistered(args.day).to_string())?;
+    let input = load(args.day, args.input.as_deref()).map_err(|e| e.to_string())?;
+    let parsed = run::parse(solver, &input).map_err(|e| e.describe(&input))?.value;
+    let mut frames = Frames::new(args.every);
+    let answer = run::record(solver, &parsed, args.part, &mut frames).map_err(|e| e.to_string())?;
+    let Some(value) = answer.value else {
+        return Ok(None);
+    };
+    eprintln!(
+        "day {} part {}: {} [{:.2?}], {} frames",
+        args.day,
+        args.part,
+        value,
+        answer.elapsed,
+        frames.frames().len()
+    );
+    Ok(Some(frames.into_frames()))
+}
+
+fn write_frames(args: &RenderArgs, frames: &[Grid<char>]) -> io::Result<()> {
+    let palette = Palette::default();
+    let create = |path: &Path| File::create(path).map(BufWriter::new);
+    match (args.format, args.out.as_deref()) {
+        (Format::Ascii, None) => render::write_ascii(io::stdout().lock(), frames),
+        (Format::Ascii, Some(path)) => render::write_ascii(create(Path::new(path))?, frames),
+        (Format::Gif, Some(path)) => {
+            let mut w = create(Path::new(path))?;
+            render::write_gif(&mut w, frames, &palette, args.scale, args.delay)?;
+            w.flush()
+        }
+        (Format::Ppm, Some(dir)) => {
+            fs::create_dir_all(dir)?;
+            for (i, frame) in frames.iter().enumerate() {
+                let mut w = create(&Path::new(dir).join(format!("frame-{:05}.ppm", i)))?;
+                render::write_ppm(&mut w, frame, &palette, args.scale)?;
+                w.flush()?;
+            }
+            Ok(())
+        }
+        (_, None) => unreachable!("parse_args requires --out"),
+    }
+}
+
+fn render(args: RenderArgs) -> ExitCode {
+    let registry = aoc_runner::registry();
+    let frames = match record(&registry, &args) {
+        Ok(Some(frames)) => frames,
+        Ok(None) => {
+            eprintln!("day {} has nothing to render", args.day);
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("day {}: {}", args.day, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match write_frames(&args, &frames) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("aoc: {}: {}", args.out.as_deref().unwrap_or("stdout"), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn gen(args: GenArgs) -> ExitCode {
+    let registry = aoc_runner::registry();
+    let Some(solver) = registry.get(args.day) else {
+        eprintln!("day {}: {}", args.day, Failure::Unregistered(args.day));
+        return ExitCode::FAILURE;
+    };
+    let Some(input) = solver.generate(&mut Rng::new(args.seed), args.size) else {
+        eprintln!("day {} has no input generator", args.day);
+        return ExitCode::FAILURE;
+    };
+    let written = match args.out.as_deref() {
+        Some(path) => fs::write(path, &input),
+        None => io::stdout().lock().write_all(input.as_bytes()),
+    };
+    match written {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("aoc: {}: {}", args.out.as_deref().unwrap_or("stdout"), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn batch(args: batch::Args) -> ExitCode {
+    let registry = aoc_runner::registry();
+    let Some(solver) = registry.get(args.day) else {
+        eprintln!("day {}: {}", args.day, Failure::Unregistered(args.day));
+        return ExitCode::FAILURE;
+    };
+    let prepared = budget(args.timeout).and_then(|budget| {
+        let paths = batch::inputs(&args.inputs)?;
+        let config = Config::workspace().map_err(|e| e.to_string())?;
+        Ok((budget, paths, config))
+    });
+    let (budget, paths, config) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            eprintln!("aoc: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let threads = args.jobs.unwrap_or_else(calendar::default_threads);
+    let batch = batch::run(solver, &paths, &config, &args.parts(), &budget, threads);
+    if let Err(e) = batch.save(args.format, args.out.as_deref()) {
+        eprintln!("aoc: {}", e);
+        return ExitCode::FAILURE;
+    }
+    if batch.succeeded() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn watch(args: watch::Args) -> ExitCode {
+    let Err(e) = watch::run(&args);
+    eprintln!("day {}: {}", args.day, e);
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let parsed = trace_args(&args).and_then(|(args, tracer)| {
+        let command = parse_args(&args)?;
+        if let Some(tracer) = tracer {
+            trace::install(tracer);
+        }
+        Ok(command)
+    });
+    match parsed {
+        Ok(Command::Run(args)) => run(args),
+        Ok(Command::Bench(args)) => bench(args),
+        Ok(Command::Render(args)) => render(args),
+        Ok(Command::Gen(args)) => gen(args),
+        Ok(Command::Batch(args)) => batch(args),
+        Ok(Command::Watch(args)) => watch(args),
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("aoc: {}\n\n{}", e, USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_run() {
+        assert_eq!(
+            parse_args(&args("run 5 --part 2 --input -")),
+            Ok(Command::Run(RunArgs {
+                target: Target {
+                    days: Days::One(5),
+                    part: Some(Part::Two),
+                    input: Some("-".to_string()),
+                },
+                timeout: None,
+                jobs: None,
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("run --all --timeout 2.5 --jobs 3")),
+            Ok(Command::Run(RunArgs {
+                target: Target {
+                    days: Days::All,
+                    part: None,
+                    input: None,
+                },
+                timeout: Some(Duration::from_millis(2500)),
+                jobs: Some(3),
+            }))
+        );
+        assert!(parse_args(&args("run 5 --timeout -1")).is_err());
+        assert!(parse_args(&args("bench 5 --timeout 1")).is_err());
+        assert!(parse_args(&args("run --all --jobs 0")).is_err());
+        assert!(parse_args(&args("bench --all --jobs 2")).is_err());
+        assert!(parse_args(&args("run")).is_err());
+        assert!(parse_args(&args("run 5 --part 3")).is_err());
+        assert!(parse_args(&args("run --all --input in.1")).is_err());
+        assert!(parse_args(&args("run 5 --runs 3")).is_err());
+        assert!(parse_args(&args("walk 5")).is_err());
+    }
+
+    #[test]
+    fn parses_bench() {
+        let parsed = parse_args(&args("bench --all --runs 5 --warmup 0 --max-time 0.5 --baseline b.tsv --threshold 25"));
+        assert_eq!(
+            parsed,
+            Ok(Command::Bench(BenchArgs {
+                target: Target {
+                    days: Days::All,
+                    part: None,
+                    input: None,
+                },
+                options: bench::Options {
+                    warmup: 0,
+                    runs: 5,
+                    max_time: Duration::from_millis(500),
+                },
+                save: None,
+                baseline: Some("b.tsv".to_string()),
+                threshold: 25.0,
+            }))
+        );
+        assert!(parse_args(&args("bench 3 --runs many")).is_err());
+    }
+
+    #[test]
+    fn parses_trace() {
+        let (rest, tracer) = trace_args(&args("run --trace 14=trace 14 --part 2")).unwrap();
+        assert_eq!(rest, args("run 14 --part 2"));
+        assert!(tracer.is_some());
+        let (rest, tracer) = trace_args(&args("bench --all")).unwrap();
+        assert_eq!(rest, args("bench --all"));
+        assert!(tracer.is_none());
+        assert!(trace_args(&args("run 1 --trace-format json")).is_err());
+        assert!(trace_args(&args("run 1 --trace loud")).is_err());
+        assert!(trace_args(&args("run 1 --trace")).is_err());
+    }
+
+    #[test]
+    fn parses_render() {
+        assert_eq!(
+            parse_args(&args("render 14 --part 2 --format gif --every 5 --out sand.gif")),
+            Ok(Command::Render(RenderArgs {
+                day: 14,
+                part: Part::Two,
+                input: None,
+                format: Format::Gif,
+                every: 5,
+                scale: 4,
+                delay: 10,
+                out: Some("sand.gif".to_string()),
+            }))
+        );
+        assert!(parse_args(&args("render 14 --format ppm")).is_err());
+        assert!(parse_args(&args("render --all")).is_err());
+        assert!(parse_args(&args("render 14 --format png")).is_err());
+        assert!(parse_args(&args("run 14 --every 2")).is_err());
+    }
+
+    #[test]
+    fn parses_gen() {
+        assert_eq!(
+            parse_args(&args("gen 16 --seed 42 --size 10")),
+            Ok(Command::Gen(GenArgs {
+                day: 16,
+                seed: 42,
+                size: 10,
+                out: None,
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("gen 3 --out big.txt")),
+            Ok(Command::Gen(GenArgs {
+                day: 3,
+                seed: 0,
+                size: 1,
+                out: Some("big.txt".to_string()),
+            }))
+        );
+        assert!(parse_args(&args("gen --all")).is_err());
+        assert!(parse_args(&args("gen 3 --size 0")).is_err());
+        assert!(parse_args(&args("gen 3 --input in.1")).is_err());
+        assert!(parse_args(&args("run 3 --seed 1")).is_err());
+    }
+
+    #[test]
+    fn parses_batch() {
+        assert_eq!(
+            parse_args(&args("batch 16 inputs/ extra/in.* --part 1 --timeout 2 --format csv --out t.csv")),
+            Ok(Command::Batch(batch::Args {
+                day: 16,
+                part: Some(Part::One),
+                inputs: vec!["inputs/".to_string(), "extra/in.*".to_string()],
+                timeout: Some(Duration::from_secs(2)),
+                jobs: None,
+                format: batch::Format::Csv,
+                out: Some("t.csv".to_string()),
+            }))
+        );
+        assert!(parse_args(&args("batch 16")).is_err());
+        assert!(parse_args(&args("batch --all inputs/")).is_err());
+        assert!(parse_args(&args("batch 16 inputs/ --input in.1")).is_err());
+        assert!(parse_args(&args("batch 16 inputs/ --format xml")).is_err());
+        assert!(parse_args(&args("run 16 inputs/")).is_err());
+    }
+
+    #[test]
+    fn parses_watch() {
+        assert_eq!(
+            parse_args(&args("watch 6 --interval 2 --part 2")),
+            Ok(Command::Watch(watch::Args {
+                day: 6,
+                part: Some(Part::Two),
+                inputs: Vec::new(),
+                timeout: None,
+                interval: Duration::from_secs(2),
+            }))
+        );
+        assert_eq!(
+            parse_args(&args("watch 16 mine.txt --timeout 1")),
+            Ok(Command::Watch(watch::Args {
+                day: 16,
+                part: None,
+                inputs: vec!["mine.txt".to_string()],
+                timeout: Some(Duration::from_secs(1)),
+                interval: Duration::from_millis(500),
+            }))
+        );
+        assert!(parse_args(&args("watch --all")).is_err());
+        assert!(parse_args(&args("watch 6 --interval soon")).is_err());
+        assert!(parse_args(&args("batch 6 in.1 --interval 1")).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Running one day with timings, turning panics into errors.
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use aoc_core::budget::Budget;
+use aoc_core::render::Recorder;
+use aoc_core::solution::Parsed;
+use aoc_core::{Answer, Input, Part, Solver};
+
+/// Why a day could not be answered.
+#[derive(Debug)]
+pub enum Failure {
+    /// Nothing is registered for this day.
+    Unregistered(u8),
+    /// Loading or parsing the input failed.
+    Input(aoc_core::Error),
+    /// The solver panicked with this message.
+    Panic(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Unregistered(day) => write!(f, "no solution registered for day {}", day),
+            Failure::Input(e) => write!(f, "{}", e),
+            Failure::Panic(msg) => write!(f, "panicked: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+impl Failure {
+    /// The failure, followed for bad input by the offending line of `input`
+    /// with the bad text underlined.
+    pub fn describe(&self, input: &Input) -> String {
+        match self {
+            Failure::Input(aoc_core::Error::Parse { error, .. }) => match input.excerpt(error) {
+                Some(excerpt) => format!("{}\n{}", self, excerpt),
+                None => self.to_string(),
+            },
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl From<aoc_core::Error> for Failure {
+    fn from(e: aoc_core::Error) -> Self {
+        Failure::Input(e)
+    }
+}
+
+/// A value and how long it took to compute.
+#[derive(Debug)]
+pub struct Timed<T> {
+    pub value: T,
+    pub elapsed: Duration,
+}
+
+/// Answer to one part.
+#[derive(Debug)]
+pub struct PartRun {
+    pub part: Part,
+    pub answer: Timed<Answer>,
+}
+
+/// Everything `run_day` found out about one day.
+#[derive(Debug)]
+pub struct DayRun {
+    pub day: u8,
+    pub parse_time: Duration,
+    pub parts: Vec<PartRun>,
+    /// Time spent on the day in all: here the parse and the parts, while
+    /// [`calendar`](crate::calendar) also counts each part parsing the input
+    /// for itself.
+    pub cpu_time: Duration,
+}
+
+impl DayRun {
+    /// Parts that ran out of budget.
+    pub fn timed_out(&self) -> impl Iterator<Item = Part> + '_ {
+        self.parts.iter().filter(|p| matches!(p.answer.value, Answer::TimedOut(_))).map(|p| p.part)
+    }
+}
+
+/// `answer` as text for other tools to read, if there is one: for a part that
+/// timed out, the best answer it had.
+pub fn answer_text(answer: &Answer) -> Option<String> {
+    match answer {
+        Answer::Unsolved => None,
+        Answer::TimedOut(best) => best.as_deref().map(Answer::to_string),
+        other => Some(other.to_string()),
+    }
+}
+
+/// The `aocN` crate of the workspace this binary was built from.
+pub fn day_dir(day: u8) -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    root.parent().unwrap_or(&root).join(format!("aoc{}", day))
+}
+
+/// `aocN/in.1` of the workspace this binary was built from.
+pub fn default_input(day: u8) -> PathBuf {
+    day_dir(day).join("in.1")
+}
+
+/// Run `f`, turning a panic into [`Failure::Panic`].
+pub fn catch<T>(f: impl FnOnce() -> T) -> Result<T, Failure> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let msg = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic".to_string()
+        };
+        Failure::Panic(msg)
+    })
+}
+
+pub fn parse(solver: &dyn Solver, input: &Input) -> Result<Timed<Parsed>, Failure> {
+    let start = Instant::now();
+    let value = catch(|| solver.parse(input))??;
+    Ok(Timed {
+        value,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Solve `part` with `budget` in force, which starts afresh for each part.
+pub fn solve(solver: &dyn Solver, parsed: &Parsed, part: Part, budget: &Budget) -> Result<Timed<Answer>, Failure> {
+    let start = Instant::now();
+    let value = catch(|| budget.run(|| solver.solve(parsed, part)))?;
+    Ok(Timed {
+        value,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Solve `part` while `recorder` collects frames; `None` if the day has
+/// nothing to draw.
+pub fn record(
+    solver: &dyn Solver,
+    parsed: &Parsed,
+    part: Part,
+    recorder: &mut dyn Recorder,
+) -> Result<Timed<Option<Answer>>, Failure> {
+    let start = Instant::now();
+    let value = catch(|| solver.record(parsed, part, recorder))?;
+    Ok(Timed {
+        value,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Parse `input` once and answer each of `parts` from it, each within `budget`.
+pub fn run_day(solver: &dyn Solver, input: &Input, parts: &[Part], budget: &Budget) -> Result<DayRun, Failure> {
+    let parsed = parse(solver, input)?;
+    let parts: Vec<PartRun> = parts
+        .iter()
+        .map(|&part| {
+            Ok(PartRun {
+                part,
+                answer: solve(solver, &parsed.value, part, budget)?,
+            })
+        })
+        .collect::<Result<_, Failure>>()?;
+    let cpu_time = parsed.elapsed + parts.iter().map(|p: &PartRun| p.answer.elapsed).sum::<Duration>();
+    Ok(DayRun {
+        day: solver.day(),
+        parse_time: parsed.elapsed,
+        parts,
+        cpu_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc_core::{Registry, Solution};
+
+    struct Panics;
+
+    impl Solution for Panics {
+        const DAY: u8 = 1;
+        type Input = ();
+
+        fn parse(_input: &Input) -> aoc_core::Result<Self::Input> {
+            Ok(())
+        }
+
+        fn part1(_input: &Self::Input) -> Answer {
+            panic!("no answer for {}", 1)
+        }
+    }
+
+    struct Numbers;
+
+    impl Solution for Numbers {
+        const DAY: u8 = 2;
+        type Input = Vec<u32>;
+
+        fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+            input.parse_lines()
+        }
+
+        fn part1(input: &Self::Input) -> Answer {
+            input.iter().sum::<u32>().into()
+        }
+    }
+
+    /// Counts for as long as it is allowed to
+    struct Forever;
+
+    impl Solution for Forever {
+        const DAY: u8 = 3;
+        type Input = ();
+
+        fn parse(_input: &Input) -> aoc_core::Result<Self::Input> {
+            Ok(())
+        }
+
+        fn part1(_input: &Self::Input) -> Answer {
+            let mut count = 0;
+            while aoc_core::budget::tick() {
+                count += 1;
+            }
+            aoc_core::budget::answer(count)
+        }
+    }
+
+    #[test]
+    fn budget_per_part() {
+        let mut registry = Registry::new();
+        registry.register::<Forever>();
+        let solver = registry.get(3).unwrap();
+        let budget = Budget::unlimited().with_steps(1000);
+        let run = run_day(solver, &Input::from(""), &Part::ALL, &budget).unwrap();
+        assert_eq!(run.parts[0].answer.value.to_string(), "timed out, best so far 1000");
+        assert_eq!(run.timed_out().collect::<Vec<_>>(), vec![Part::One]);
+
+        let budget = Budget::unlimited().with_timeout(Duration::from_millis(10));
+        let run = run_day(solver, &Input::from(""), &[Part::One], &budget).unwrap();
+        assert!(run.parts[0].answer.elapsed >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn bad_input_is_described() {
+        let mut registry = Registry::new();
+        registry.register::<Numbers>();
+        let solver = registry.get(2).unwrap();
+        let input = Input::from("1\n2x\n");
+        let failure = run_day(solver, &input, &[Part::One], &Budget::unlimited()).unwrap_err();
+        assert_eq!(
+            failure.describe(&input),
+            "<memory>:2:1: invalid digit found in string: \"2x\"\n2 | 2x\n  | ^^"
+        );
+    }
+
+    #[test]
+    fn panics_become_failures() {
+        let mut registry = Registry::new();
+        registry.register::<Panics>();
+        let solver = registry.get(1).unwrap();
+        let input = Input::from("");
+        match run_day(solver, &input, &[Part::One], &Budget::unlimited()) {
+            Err(Failure::Panic(msg)) => assert_eq!(msg, "no answer for 1"),
+            other => panic!("expected a panic failure, got {:?}", other),
+        }
+        let run = run_day(solver, &input, &[Part::Two], &Budget::unlimited()).unwrap();
+        assert_eq!(run.parts[0].answer.value, Answer::Unsolved);
+    }
+}
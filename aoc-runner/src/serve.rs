@@ -0,0 +1,469 @@
+//! Answering puzzles over HTTP, for tools that cannot link Rust.
+//!
+//! A [`Server`] speaks just enough HTTP/1.1 for `curl` and the like: one
+//! request per connection, bodies sized by `Content-Length`. A fixed number of
+//! threads take connections in turn, so a flood of clients waits in the
+//! listen queue instead of starting a thread each, and a client that stalls
+//! holds its thread for no longer than the read timeout.
+//!
+//! - `POST /day/{n}/part/{p}` with the puzzle input as the body answers that
+//!   part. `?name=in.test` picks the input's table in `aoc.toml`, as the file
+//!   name does for `aoc run`; without it the day's own table applies.
+//! - `GET /days` lists the days that can be answered.
+//!
+//! Every response is a JSON object. An answer looks like
+//! `{"day":1,"part":1,"answer":"24000","timed_out":false,"parse_ms":0.02,"solve_ms":0.01}`,
+//! with `answer` null for an unsolved part, or one that timed out before it had
+//! a best answer. Input that does not parse gets a 422 with the `error`, its
+//! `line` and `column`, and an `excerpt` underlining the bad text; anything else
+//! that goes wrong gets a 4xx or 5xx with an `error`.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use aoc_core::budget::Budget;
+use aoc_core::config::Config;
+use aoc_core::input::Source;
+use aoc_core::json::{self, Value};
+use aoc_core::{Answer, Input, Part, Registry};
+
+use crate::run::{self, Failure};
+
+/// Largest request body taken, in bytes.
+pub const MAX_BODY: usize = 16 << 20;
+/// Longest request line or header taken, in bytes.
+const MAX_LINE: usize = 8 << 10;
+const MAX_HEADERS: usize = 100;
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// Connections handled at once, unless [`Server::with_threads`] says otherwise.
+pub const DEFAULT_THREADS: usize = 4;
+
+/// Serves the days of a [`Registry`] on a socket.
+pub struct Server {
+    listener: TcpListener,
+    registry: Registry,
+    config: Config,
+    budget: Budget,
+    threads: usize,
+}
+
+impl Server {
+    /// Answer for the days in `registry` on `listener`, with puzzle parameters
+    /// from `config` and each part within `budget`.
+    pub fn new(listener: TcpListener, registry: Registry, config: Config, budget: Budget) -> Self {
+        Server {
+            listener,
+            registry,
+            config,
+            budget,
+            threads: DEFAULT_THREADS,
+        }
+    }
+
+    /// Handle up to `threads` connections at once, at least one.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answer requests until accepting a connection fails, on every thread,
+    /// for some reason other than the client giving up on it.
+    pub fn serve(&self) -> io::Result<()> {
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads).map(|_| scope.spawn(|| self.work())).collect();
+            workers.into_iter().try_for_each(|w| w.join().expect("handle does not panic"))
+        })
+    }
+
+    /// Take connections one at a time and answer them.
+    fn work(&self) -> io::Result<()> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => self.handle(stream),
+                Err(e) if is_transient(&e) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn handle(&self, stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+        let response = match read_request(&mut BufReader::new(&stream), &mut &stream) {
+            Ok(request) => self.respond(&request),
+            Err(response) => response,
+        };
+        // A client that has gone away cannot be told anything
+        let _ = response.write_to(&mut &stream);
+    }
+
+    fn respond(&self, request: &Request) -> Response {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["days"]) => {
+                let days: Vec<u8> = self.registry.days().collect();
+                Response::json(200, json::object([("days", Value::from(days))]))
+            }
+            (_, ["days"]) => Response::not_allowed("GET"),
+            ("POST", ["day", day, "part", part]) => self.answer(day, part, request),
+            (_, ["day", _, "part", _]) => Response::not_allowed("POST"),
+            _ => Response::error(404, format!("no such endpoint {}", request.path)),
+        }
+    }
+
+    /// Parse the request body as the input of `day` and solve `part` of it.
+    fn answer(&self, day: &str, part: &str, request: &Request) -> Response {
+        let Ok(day) = day.parse::<u8>() else {
+            return Response::error(404, format!("no such day {:?}", day));
+        };
+        let part = match part.parse::<Part>() {
+            Ok(part) => part,
+            Err(e) => return Response::error(404, e),
+        };
+        let Some(solver) = self.registry.get(day) else {
+            return Response::error(404, Failure::Unregistered(day));
+        };
+        let input = match Input::from_bytes(Source::Memory, request.body.clone()) {
+            Ok(input) => input.with_params(self.config.params(day, request.param("name"))),
+            Err(e) => return Response::error(400, e),
+        };
+
+        let mut fields = vec![
+            ("day", Value::from(day)),
+            ("part", Value::from(part.number())),
+        ];
+        let solved = run::parse(solver, &input)
+            .and_then(|parsed| Ok((parsed.elapsed, run::solve(solver, &parsed.value, part, &self.budget)?)));
+        let status = match solved {
+            Ok((parse_time, answer)) => {
+                fields.extend([
+                    ("answer", Value::from(run::answer_text(&answer.value))),
+                    ("timed_out", Value::from(matches!(answer.value, Answer::TimedOut(_)))),
+                    ("parse_ms", millis(parse_time)),
+                    ("solve_ms", millis(answer.elapsed)),
+                ]);
+                200
+            }
+            Err(Failure::Input(aoc_core::Error::Parse { error, .. })) => {
+                fields.extend([
+                    ("error", Value::from(error.to_string())),
+                    ("line", Value::from(error.line)),
+                    ("column", Value::from(error.column)),
+                    ("excerpt", Value::from(input.excerpt(&error))),
+                ]);
+                422
+            }
+            Err(failure) => {
+                fields.push(("error", Value::from(failure.to_string())));
+                match failure {
+                    Failure::Unregistered(_) => 404,
+                    Failure::Input(_) => 422,
+                    Failure::Panic(_) => 500,
+                }
+            }
+        };
+        Response::json(status, json::object(fields))
+    }
+}
+
+/// Accept errors that only lose the one connection.
+fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted
+    )
+}
+
+fn millis(elapsed: Duration) -> Value {
+    Value::from(elapsed.as_secs_f64() * 1000.0)
+}
+
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    query: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// The value of `key` in the query string, taken as it is: no
+    /// percent-decoding.
+    fn param(&self, key: &str) -> Option<&str> {
+        self.query
+            .as_deref()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Value,
+    /// Methods the path takes, for a 405.
+    allow: Option<&'static str>,
+}
+
+impl Response {
+    fn json(status: u16, body: Value) -> Self {
+        Response {
+            status,
+            body,
+            allow: None,
+        }
+    }
+
+    fn error(status: u16, message: impl fmt::Display) -> Self {
+        Response::json(status, json::object([("error", Value::from(message.to_string()))]))
+    }
+
+    fn not_allowed(allow: &'static str) -> Self {
+        Response {
+            allow: Some(allow),
+            ..Response::error(405, format!("only {} is allowed here", allow))
+        }
+    }
+
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        let body = format!("{}\n", self.body);
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            reason(self.status),
+            body.len()
+        )?;
+        if let Some(allow) = self.allow {
+            write!(out, "Allow: {}\r\n", allow)?;
+        }
+        write!(out, "\r\n{}", body)?;
+        out.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        422 => "Unprocessable Content",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        505 => "HTTP Version Not Supported",
+        _ => "",
+    }
+}
+
+/// Read one request from `reader`, telling a client that waits for it through
+/// `writer` to go on and send the body; or the response saying what is wrong
+/// with the request.
+fn read_request(reader: &mut impl BufRead, writer: &mut impl Write) -> Result<Request, Response> {
+    let line = read_line(reader)?;
+    let (method, target, version) = match line.split(' ').collect::<Vec<_>>()[..] {
+        [method, target, version] => (method, target, version),
+        _ => return Err(Response::error(400, format!("malformed request line {:?}", line))),
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(Response::error(505, format!("{} is not supported", version)));
+    }
+
+    let mut length = None;
+    let mut expect_continue = false;
+    for count in 0.. {
+        let header = read_line(reader)?;
+        if header.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(Response::error(431, "too many headers"));
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(Response::error(400, format!("malformed header {:?}", header)));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            let n = value
+                .parse::<usize>()
+                .map_err(|_| Response::error(400, format!("invalid Content-Length {:?}", value)))?;
+            length = Some(n);
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(Response::error(501, "only bodies with a Content-Length are supported"));
+        } else if name.eq_ignore_ascii_case("expect") && value.eq_ignore_ascii_case("100-continue") {
+            expect_continue = true;
+        }
+    }
+
+    let length = match (method, length) {
+        (_, Some(n)) if n > MAX_BODY => {
+            return Err(Response::error(413, format!("bodies are limited to {} bytes", MAX_BODY)))
+        }
+        (_, Some(n)) => n,
+        ("POST", None) => return Err(Response::error(411, "POST needs a Content-Length")),
+        (_, None) => 0,
+    };
+    if expect_continue && length > 0 {
+        write!(writer, "HTTP/1.1 100 {}\r\n\r\n", reason(100))
+            .and_then(|_| writer.flush())
+            .map_err(|e| Response::error(400, e))?;
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| Response::error(400, "body shorter than its Content-Length"))?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (target, None),
+    };
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        body,
+    })
+}
+
+/// One line of the request head, without its line ending.
+fn read_line(reader: &mut impl BufRead) -> Result<String, Response> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_LINE as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .map_err(|e| Response::error(400, e))?;
+    if line.len() > MAX_LINE {
+        return Err(Response::error(431, "request line or header too long"));
+    }
+    if !line.ends_with(b"\n") {
+        return Err(Response::error(400, "request ended early"));
+    }
+    let line = String::from_utf8(line).map_err(|_| Response::error(400, "request head is not UTF-8"))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Shutdown;
+
+    /// A server for every day on a free port, answering on `threads` threads
+    /// of its own
+    fn start(threads: usize) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = Config::workspace().unwrap();
+        let server = Server::new(listener, crate::registry(), config, Budget::unlimited()).with_threads(threads);
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.serve());
+        addr
+    }
+
+    /// Send `request` as it is and split the response into status and body
+    fn send(addr: SocketAddr, request: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, body.trim_end().to_string())
+    }
+
+    fn post(addr: SocketAddr, path: &str, body: &str) -> (u16, String) {
+        let request = format!("POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}", path, body.len(), body);
+        send(addr, &request)
+    }
+
+    /// `body` up to the timings, which differ from run to run
+    fn untimed(body: &str) -> &str {
+        body.split(",\"parse_ms\"").next().unwrap()
+    }
+
+    #[test]
+    fn answers() {
+        let addr = start(DEFAULT_THREADS);
+        let example = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+        let (status, body) = post(addr, "/day/1/part/1", example);
+        assert_eq!(status, 200);
+        assert_eq!(untimed(&body), r#"{"day":1,"part":1,"answer":"24000","timed_out":false"#);
+        assert!(body.contains("\"solve_ms\":"), "{}", body);
+        let (_, body) = post(addr, "/day/1/part/2", example);
+        assert!(body.contains(r#""answer":"45000""#), "{}", body);
+
+        // The example's row comes from its own table in aoc.toml
+        let example = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../aoc15/in.test")).unwrap();
+        let (status, body) = post(addr, "/day/15/part/1?name=in.test", &example);
+        assert_eq!((status, untimed(&body)), (200, r#"{"day":15,"part":1,"answer":"26","timed_out":false"#));
+
+        let (status, body) = send(addr, "GET /days HTTP/1.1\r\n\r\n");
+        assert_eq!(status, 200);
+        assert!(body.starts_with(r#"{"days":[1,2,3,"#) && body.ends_with("23,24]}"), "{}", body);
+    }
+
+    #[test]
+    fn stalled_client() {
+        let addr = start(2);
+        // Connected but sending nothing, which ties up one of the threads
+        let _stalled = TcpStream::connect(addr).unwrap();
+        let (status, _) = send(addr, "GET /days HTTP/1.1\r\n\r\n");
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn errors() {
+        let addr = start(DEFAULT_THREADS);
+        let (status, body) = post(addr, "/day/1/part/1", "1000\n2x00\n");
+        assert_eq!(status, 422);
+        assert_eq!(
+            body,
+            r#"{"day":1,"part":1,"error":"2:1: invalid digit found in string: \"2x00\"","line":2,"column":1,"excerpt":"2 | 2x00\n  | ^^^^"}"#
+        );
+        let (status, body) = post(addr, "/day/19/part/1", "");
+        assert_eq!((status, body.as_str()), (404, r#"{"error":"no solution registered for day 19"}"#));
+        assert_eq!(post(addr, "/day/1/part/3", "").0, 404);
+        assert_eq!(post(addr, "/nowhere", "").0, 404);
+        assert_eq!(send(addr, "GET /day/1/part/1 HTTP/1.1\r\n\r\n").0, 405);
+        assert_eq!(send(addr, "POST /day/1/part/1 HTTP/1.1\r\n\r\n").0, 411);
+        assert_eq!(send(addr, "POST /day/1/part/1 HTTP/1.1\r\nContent-Length: 10\r\n\r\n1").0, 400);
+        assert_eq!(send(addr, "nonsense\r\n\r\n").0, 400);
+    }
+
+    #[test]
+    fn reads_requests() {
+        let mut sent = Vec::new();
+        let raw = b"POST /day/5/part/2?name=in.test&x=1 HTTP/1.1\r\nexpect: 100-continue\r\nContent-Length: 3\r\n\r\nabcdef";
+        let request = read_request(&mut &raw[..], &mut sent).unwrap();
+        assert_eq!(
+            request,
+            Request {
+                method: "POST".to_string(),
+                path: "/day/5/part/2".to_string(),
+                query: Some("name=in.test&x=1".to_string()),
+                body: b"abc".to_vec(),
+            }
+        );
+        assert_eq!(request.param("name"), Some("in.test"));
+        assert_eq!(request.param("nam"), None);
+        assert_eq!(sent, b"HTTP/1.1 100 Continue\r\n\r\n");
+
+        let status = |raw: &str| read_request(&mut raw.as_bytes(), &mut Vec::new()).unwrap_err().status;
+        assert_eq!(status("GET / HTTP/2\r\n\r\n"), 505);
+        assert_eq!(status("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n"), 501);
+        assert_eq!(status(&format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1)), 413);
+        assert_eq!(status(&format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE))), 431);
+        assert_eq!(status("GET / HTTP/1.1\r\nno colon\r\n\r\n"), 400);
+        assert_eq!(status("GET / HTTP/1.1\r\n"), 400);
+    }
+}
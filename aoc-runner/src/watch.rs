@@ -0,0 +1,424 @@
+//! Running a day again whenever its source or its inputs change.
+//!
+//! Only std is at hand, so [`Stamps`] polls: it notes each watched file's
+//! modification time and length, and a later scan tells which files differ.
+//! The solvers are compiled into the runner, so a change to a day's source
+//! means building the runner again; [`run()`] does that with cargo, has the
+//! new binary run `batch --format csv` over the inputs, reads the table back
+//! with [`read_results`], and prints the [`diff`] against the run before.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use aoc_core::config::{self, CONFIG_VAR};
+use aoc_core::Part;
+
+use crate::batch;
+
+/// What `aoc watch` was asked to do.
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub day: u8,
+    /// Both parts when not given.
+    pub part: Option<Part>,
+    /// Files, directories and globs, as given; aocN/in.* when empty.
+    pub inputs: Vec<String>,
+    pub timeout: Option<Duration>,
+    /// How long to wait between looking for changes.
+    pub interval: Duration,
+}
+
+/// When each of a set of files was last modified, and how long it was then.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stamps {
+    files: BTreeMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl Stamps {
+    /// Stamps of those of `paths` that exist.
+    pub fn scan(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let files = paths
+            .into_iter()
+            .filter_map(|path| {
+                let meta = fs::metadata(&path).ok()?;
+                Some((path, (meta.modified().ok()?, meta.len())))
+            })
+            .collect();
+        Stamps { files }
+    }
+
+    /// Files that were added, removed or changed in `newer`, in order.
+    pub fn changed(&self, newer: &Stamps) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = newer
+            .files
+            .iter()
+            .filter(|(path, stamp)| self.files.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .chain(self.files.keys().filter(|path| !newer.files.contains_key(*path)).cloned())
+            .collect();
+        changed.sort();
+        changed
+    }
+}
+
+/// Every file under `dir`, in order; none if it cannot be read.
+pub fn sources(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// One thing a run found out about one input: how long its parse took, the
+/// answer to a part, or why it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub input: String,
+    /// `parse`, `part 1`, `part 2` or `failed`.
+    pub stage: String,
+    /// The answer as `aoc run` prints it, or the error; empty for the parse.
+    pub value: String,
+    pub elapsed: Option<Duration>,
+}
+
+/// What a table from `batch --format csv` says, input by input.
+pub fn read_results(csv: &str) -> Result<Vec<Outcome>, String> {
+    let mut rows = parse_csv(csv)?.into_iter();
+    let header = rows.next().ok_or("no results")?;
+    if header.len() < 3 || header.len() % 3 != 0 || header[..3] != ["input", "error", "parse_ms"] {
+        return Err(format!("unexpected columns {:?}", header));
+    }
+    let parts: Vec<String> = header[3..].chunks(3).map(|c| c[0].replacen("part", "part ", 1)).collect();
+    let mut outcomes = Vec::new();
+    for row in rows {
+        if row.len() != header.len() {
+            return Err(format!("expected {} columns, got {:?}", header.len(), row));
+        }
+        let outcome = |stage: &str, value: String, elapsed| Outcome {
+            input: row[0].clone(),
+            stage: stage.to_string(),
+            value,
+            elapsed,
+        };
+        if !row[1].is_empty() {
+            outcomes.push(outcome("failed", row[1].clone(), None));
+            continue;
+        }
+        outcomes.push(outcome("parse", String::new(), Some(millis(&row[2])?)));
+        for (stage, cells) in parts.iter().zip(row[3..].chunks(3)) {
+            let (answer, status) = (&cells[0], cells[1].as_str());
+            let value = match status {
+                "timed_out" if answer.is_empty() => "timed out".to_string(),
+                "timed_out" => format!("timed out, best so far {}", answer),
+                "unsolved" => "unsolved".to_string(),
+                _ => answer.clone(),
+            };
+            outcomes.push(outcome(stage, value, Some(millis(&cells[2])?)));
+        }
+    }
+    Ok(outcomes)
+}
+
+fn millis(cell: &str) -> Result<Duration, String> {
+    cell.parse::<f64>()
+        .ok()
+        .and_then(|ms| Duration::try_from_secs_f64(ms / 1000.0).ok())
+        .ok_or_else(|| format!("invalid time {:?}", cell))
+}
+
+/// Rows of comma-separated fields, which may be quoted with `"` and then hold
+/// commas, line breaks and doubled quotes.
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut chars = text.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => (),
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// A line for each of `current`, noting what changed since `previous`: a
+/// different answer or error, the time taken against the time before, and
+/// what is gone.
+pub fn diff(previous: Option<&[Outcome]>, current: &[Outcome]) -> String {
+    let width = current.iter().map(|o| o.input.chars().count()).max().unwrap_or(0);
+    let before = |o: &Outcome| previous?.iter().find(|p| p.input == o.input && p.stage == o.stage);
+    let mut out = String::new();
+    for outcome in current {
+        let old = before(outcome);
+        out.push_str(&format!("{:<width$}  {}", outcome.input, outcome.stage, width = width));
+        if outcome.stage != "parse" {
+            out.push(':');
+            match outcome.value.contains('\n') {
+                true => out.push_str(&format!("\n    {}", outcome.value.replace('\n', "\n    "))),
+                false => out.push_str(&format!(" {}", outcome.value)),
+            }
+            match old {
+                Some(old) if old.value == outcome.value => (),
+                Some(old) if old.value.contains('\n') || outcome.value.contains('\n') => out.push_str(" (changed)"),
+                Some(old) => out.push_str(&format!(" (was {})", old.value)),
+                None if previous.is_some() => out.push_str(" (new)"),
+                None => (),
+            }
+        }
+        if let Some(elapsed) = outcome.elapsed {
+            out.push_str(&format!(" [{:.2?}", elapsed));
+            if let Some(was) = old.and_then(|o| o.elapsed).filter(|was| !was.is_zero()) {
+                let change = (elapsed.as_secs_f64() / was.as_secs_f64() - 1.0) * 100.0;
+                out.push_str(&format!(", {:+.0}%", change));
+            }
+            out.push(']');
+        }
+        out.push('\n');
+    }
+    for gone in previous.into_iter().flatten() {
+        if !current.iter().any(|o| o.input == gone.input && o.stage == gone.stage) {
+            out.push_str(&format!("{:<width$}  {}: gone\n", gone.input, gone.stage, width = width));
+        }
+    }
+    out
+}
+
+/// Build the `aoc` binary at `exe` again, in the profile it was built in.
+fn rebuild(exe: &Path) -> Result<(), String> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().ok_or("no workspace")?;
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.args(["build", "--bin", "aoc", "--manifest-path"]).arg(root.join("Cargo.toml"));
+    if exe.parent().and_then(Path::file_name) == Some("release".as_ref()) {
+        command.arg("--release");
+    }
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(_) => Err("build failed".to_string()),
+        Err(e) => Err(format!("cannot run cargo: {}", e)),
+    }
+}
+
+/// Have `exe` answer the day for every one of `inputs`.
+fn run_batch(exe: &Path, args: &Args, inputs: &[String]) -> Result<Vec<Outcome>, String> {
+    let mut command = Command::new(exe);
+    command.arg("batch").arg(args.day.to_string()).args(inputs).args(["--format", "csv"]);
+    if let Some(part) = args.part {
+        command.args(["--part", &part.to_string()]);
+    }
+    if let Some(timeout) = args.timeout {
+        command.args(["--timeout", &timeout.as_secs_f64().to_string()]);
+    }
+    let output = command.output().map_err(|e| format!("cannot run {}: {}", exe.display(), e))?;
+    io::stderr().write_all(&output.stderr).ok();
+    read_results(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// `path`, relative to the current directory if it is under it.
+fn relative(path: PathBuf) -> PathBuf {
+    let here = env::current_dir().unwrap_or_default();
+    path.strip_prefix(&here).map(Path::to_path_buf).unwrap_or(path)
+}
+
+/// Answer the day over its inputs every time its source, one of the inputs
+/// or the configuration file changes, printing what changed since the run
+/// before. Runs until interrupted, so it only returns if it cannot start.
+pub fn run(args: &Args) -> Result<Infallible, String> {
+    // Taken before a rebuild replaces the file, after which Linux reports it deleted
+    let exe = env::current_exe().map_err(|e| format!("cannot find this binary: {}", e))?;
+    let dir = crate::run::day_dir(args.day);
+    if !dir.is_dir() {
+        return Err(format!("no {}", dir.display()));
+    }
+    let src = relative(dir.join("src"));
+    let patterns = match args.inputs.is_empty() {
+        true => vec![relative(dir.join("in.*")).to_string_lossy().into_owned()],
+        false => args.inputs.clone(),
+    };
+    let config = relative(env::var_os(CONFIG_VAR).map_or_else(config::workspace_file, PathBuf::from));
+
+    let mut stamps = Stamps::default();
+    let mut previous: Option<Vec<Outcome>> = None;
+    let mut built = false;
+    let mut run = 0;
+    loop {
+        let (changed, inputs) = loop {
+            let inputs = batch::inputs(&patterns).unwrap_or_default();
+            let watched = sources(&src).into_iter().chain(inputs.iter().cloned()).chain([config.clone()]);
+            let now = Stamps::scan(watched);
+            let changed = stamps.changed(&now);
+            stamps = now;
+            if !changed.is_empty() {
+                break (changed, inputs);
+            }
+            thread::sleep(args.interval);
+        };
+
+        run += 1;
+        let names: Vec<String> = changed.iter().take(3).map(|p| p.display().to_string()).collect();
+        let more = match changed.len() {
+            n if n > 3 => format!(" and {} more", n - 3),
+            _ => String::new(),
+        };
+        println!("--- run {}: {}{} changed", run, names.join(", "), more);
+        if !built || changed.iter().any(|p| p.starts_with(&src)) {
+            built = match rebuild(&exe) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("day {}: {}", args.day, e);
+                    continue;
+                }
+            };
+        }
+        if inputs.is_empty() {
+            eprintln!("day {}: no inputs match {}", args.day, patterns.join(" "));
+            continue;
+        }
+        let inputs: Vec<String> = inputs.iter().map(|p| p.display().to_string()).collect();
+        match run_batch(&exe, args, &inputs) {
+            Ok(outcomes) => {
+                print!("{}", diff(previous.as_deref(), &outcomes));
+                previous = Some(outcomes);
+            }
+            Err(e) => eprintln!("day {}: {}", args.day, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "\
+input,error,parse_ms,part1,part1_status,part1_ms,part2,part2_status,part2_ms
+in.test,,0.010,7,ok,0.020,,unsolved,0.001
+in.1,\"2:1: bad \"\"x, y\"\"\",,,,,,,
+";
+
+    const SECOND: &str = "\
+input,error,parse_ms,part1,part1_status,part1_ms,part2,part2_status,part2_ms
+in.test,,0.010,5,ok,0.010,19,timed_out,0.002
+in.1,,0.100,\"#.
+.#\",ok,1.000,,timed_out,1.000
+";
+
+    #[test]
+    fn parses_csv() {
+        assert_eq!(
+            parse_csv("a,\"b,\"\"c\"\"\n\",\r\n,x").unwrap(),
+            [vec!["a", "b,\"c\"\n", ""], vec!["", "x"]]
+        );
+        assert!(parse_csv("\"open").is_err());
+    }
+
+    #[test]
+    fn reads_results() {
+        let outcomes = read_results(FIRST).unwrap();
+        let summary: Vec<(&str, &str, &str)> = outcomes
+            .iter()
+            .map(|o| (o.input.as_str(), o.stage.as_str(), o.value.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("in.test", "parse", ""),
+                ("in.test", "part 1", "7"),
+                ("in.test", "part 2", "unsolved"),
+                ("in.1", "failed", "2:1: bad \"x, y\""),
+            ]
+        );
+        assert_eq!(outcomes[1].elapsed, Some(Duration::from_micros(20)));
+        assert!(read_results("input,error\n").is_err());
+        assert!(read_results("input,error,parse_ms\nin.1,,soon\n").is_err());
+    }
+
+    #[test]
+    fn diffs() {
+        let first = read_results(FIRST).unwrap();
+        assert_eq!(
+            diff(None, &first),
+            "\
+in.test  parse [10.00µs]
+in.test  part 1: 7 [20.00µs]
+in.test  part 2: unsolved [1.00µs]
+in.1     failed: 2:1: bad \"x, y\"
+"
+        );
+        let second = read_results(SECOND).unwrap();
+        assert_eq!(
+            diff(Some(&first), &second),
+            "\
+in.test  parse [10.00µs, +0%]
+in.test  part 1: 5 (was 7) [10.00µs, -50%]
+in.test  part 2: timed out, best so far 19 (was unsolved) [2.00µs, +100%]
+in.1     parse [100.00µs]
+in.1     part 1:
+    #.
+    .# (new) [1.00ms]
+in.1     part 2: timed out (new) [1.00ms]
+in.1     failed: gone
+"
+        );
+    }
+
+    #[test]
+    fn stamps() {
+        let dir = std::env::temp_dir().join(format!("aoc-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src/bin")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "a").unwrap();
+        fs::write(dir.join("src/bin/x.rs"), "b").unwrap();
+        let files = sources(&dir.join("src"));
+        assert_eq!(files, [dir.join("src/bin/x.rs"), dir.join("src/lib.rs")]);
+
+        let watched = || files.iter().cloned().chain([dir.join("in.1")]);
+        let before = Stamps::scan(watched());
+        assert_eq!(Stamps::default().changed(&before), files);
+        assert!(before.changed(&Stamps::scan(watched())).is_empty());
+        fs::write(dir.join("src/lib.rs"), "longer").unwrap();
+        fs::write(dir.join("in.1"), "").unwrap();
+        fs::remove_file(dir.join("src/bin/x.rs")).unwrap();
+        let after = Stamps::scan(watched());
+        assert_eq!(before.changed(&after), [dir.join("in.1"), dir.join("src/bin/x.rs"), dir.join("src/lib.rs")]);
+        assert!(sources(&dir.join("nowhere")).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
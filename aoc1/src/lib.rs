@@ -1,75 +1,104 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 1: Calorie Counting.
+//!
+//! There is nothing to model beyond a list of numbers per elf, so the public
+//! surface is [`Day1`] alone; its parsed input is a `Vec<Vec<u64>>`.
+
 use std::collections::BinaryHeap;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, ParseError, Result, Solution};
+use aoc_core::gen::Rng;
 
-fn parse_input(input: Vec<String>) -> Vec<Vec<u64>> {
-    let mut cur_vec: Option<Vec<u64>> = None;
-    let mut ret_vec: Vec<Vec<u64>> = vec![];
-    for (i, v) in input.into_iter().enumerate() {
-        match v.is_empty() {
-            false => cur_vec.get_or_insert(Vec::new()).push(v.parse::<u64>().unwrap()),
-            true => ret_vec.push(cur_vec.take().unwrap())
-        }
+fn parse_input(input: &Input) -> std::result::Result<Vec<Vec<u64>>, ParseError> {
+    let elves: Vec<Vec<u64>> = input.blocks().into_iter().map(|block| {
+        block.lines.iter().enumerate().map(|(i, v)| {
+            parse_at(block.first_line + i, v, v.trim())
+        }).collect()
+    }).collect::<std::result::Result<_, _>>()?;
+    if elves.is_empty() {
+        return Err(ParseError::new(1, "", "expected at least one elf"));
     }
-    ret_vec.push(cur_vec.take().unwrap());
-    ret_vec
+    Ok(elves)
 }
 
-fn get_total(input: &Vec<Vec<u64>>) -> Vec<u64> {
+fn get_total(input: &[Vec<u64>]) -> Vec<u64> {
     input.iter().map(|v| v.iter().sum()).collect()
 }
 
-fn get_max_3(input: &Vec<u64>) -> u64 {
+fn get_max_3(input: &[u64]) -> u64 {
     let mut heap: BinaryHeap<i64> = BinaryHeap::new();
     for val in input.iter() {
         let nval = *val as i64;
         if heap.len() < 3 {
-            heap.push(-1 * nval);
+            heap.push(-nval);
         }
         else {
-            if -1 * nval < *heap.peek().unwrap() {
+            if -nval < *heap.peek().unwrap() {
                 heap.pop();
-                heap.push(-1 * nval);
+                heap.push(-nval);
             }
         }
     }
     let mut sum = 0;
     while !heap.is_empty() {
-        sum += (heap.pop().unwrap() * -1) as u64;
+        sum += -heap.pop().unwrap() as u64;
     }
     sum
 }
 
+/// About 250 elves per `size`, each carrying a handful of snacks
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let elves: Vec<String> = (0..250 * size).map(|_| {
+        let snacks: Vec<String> = (0..rng.range(1..=15)).map(|_| rng.range(1000..=60000).to_string()).collect();
+        snacks.join("\n")
+    }).collect();
+    elves.join("\n\n") + "\n"
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u8 = 1;
+    type Input = Vec<Vec<u64>>;
+
+    fn parse(input: &Input) -> Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        (*get_total(input).iter().max().unwrap()).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        get_max_3(&get_total(input)).into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test() {
-        use super::*;
-        let input: Vec<Vec<u64>> = parse_input(read_input("in.test").unwrap());
-        let sums = get_total(&input);
-        println!("test: {}", sums.iter().max().unwrap());
-        println!("test 2: {}", get_max_3(&sums));
+        aoc_core::expected::check::<Day1>("in.test");
     }
 
     #[test]
     fn part() {
-        use super::*;
-        use std::time::Instant;
-        let now = Instant::now();
-        let input: Vec<Vec<u64>> = parse_input(read_input("in.1").unwrap());
-        let sums = get_total(&input);
-        let part1_res = sums.iter().max().unwrap();
-        let part2_res = get_max_3(&sums);
-        let elapsed = now.elapsed();
-        println!("part 1: {}", part1_res);
-        println!("test 2: {}", part2_res);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day1>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day1>("1000
+
+20x0
+");
+        assert_eq!((e.line, e.column, e.text.as_str()), (3, 1, "20x0"));
+        assert_eq!(aoc_core::expected::parse_error::<Day1>("").line, 1);
     }
 }
@@ -1,16 +1,14 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::io;
-use std::io::Write;
-
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+//! Day 10: Cathode-Ray Tube.
+//!
+//! A program is a list of [`Op`]s. A [`Session`] runs it, keeping the value
+//! of X after every instruction, and reads the signal or the screen off that.
+
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Image, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-struct Data {
+/// How many cycles an instruction takes and its argument
+pub struct Data {
     num_cycles: usize,
     val: i32
 }
@@ -18,8 +16,8 @@ struct Data {
 impl Data {
     pub fn new(num_cycles: usize, val: i32) -> Self {
         Data {
-            num_cycles: num_cycles,
-            val: val
+            num_cycles,
+            val
         }
     }
 
@@ -28,24 +26,43 @@ impl Data {
     }
 }
 
-enum Op {
+/// An instruction
+pub enum Op {
     Noop(Data),
     Addx(Data)
 }
 
 impl Op {
-    pub fn new(val: String) -> Self {
+    /// Read `noop` or `addx V` from line `n`
+    pub fn parse(n: usize, val: &str) -> Result<Self, ParseError> {
         let v: Vec<&str> = val.split(" ").collect();
-        if v.len() == 1 {
-            return Op::Noop(Data::new(1, 0));
+        match v[..] {
+            ["noop"] => Ok(Op::Noop(Data::new(1, 0))),
+            ["addx", x] => Ok(Op::Addx(Data::new(2, parse_at(n, val, x)?))),
+            _ => Err(ParseError::new(n, val, "expected `noop` or `addx V`")),
         }
-        Op::Addx(Data::new(2, v[1].parse::<i32>().unwrap()))
     }
 }
 
 
-fn parse_input(inp: Vec<String>) -> Vec<Op> {
-    inp.into_iter().map(|val| Op::new(val)).collect()
+/// The program has to fit on the 240 cycles of the screen
+fn parse_input(inp: &Input) -> Result<Vec<Op>, ParseError> {
+    let (mut cycles, mut x) = (0, 1i32);
+    let mut ops = Vec::new();
+    for (n, val) in inp.numbered_lines() {
+        let op = Op::parse(n, val)?;
+        let (Op::Noop(d) | Op::Addx(d)) = &op;
+        cycles += d.num_cycles;
+        if cycles > 240 {
+            return Err(ParseError::new(n, val, "the program runs past the 240th cycle"));
+        }
+        x = x.checked_add(d.get_val()).ok_or_else(|| ParseError::new(n, val, "X overflows"))?;
+        ops.push(op);
+    }
+    if ops.is_empty() {
+        return Err(ParseError::new(1, "", "expected at least one instruction"));
+    }
+    Ok(ops)
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -57,8 +74,8 @@ struct State {
 impl State {
     pub fn new(cycle_number: usize, x: i32) -> Self {
         State {
-            cycle_number: cycle_number,
-            x: x
+            cycle_number,
+            x
         }
     }
 
@@ -69,15 +86,11 @@ impl State {
     pub fn get_x(&self) -> i32 {
         self.x
     }
-
-    pub fn get_signal_strength(&self) -> i32 {
-        self.cycle_number as i32 * self.x
-    }
 }
 
-struct Session {
+/// The CPU and screen, before the first cycle
+pub struct Session {
     states: Vec<State>,
-    x: i32,
     crt: Vec<Vec<bool>>,
 }
 
@@ -86,26 +99,23 @@ impl Session {
 
         Session {
             states: vec![State::new(0, 1)],
-            x: 1,
             crt: vec![vec![false; 40]; 6],
         }
     }
 
+    /// Run `instructions` to the end
     pub fn run_through(&mut self, instructions: &Vec<Op>) {
         for instr in instructions {
             let State {cycle_number: cur_cycle, x: cur_x} = self.states[self.states.len() - 1];
             match instr {
-                Op::Noop(_) => self.states.push(State::new(cur_cycle + 1, cur_x)),
-                Op::Addx(v) => self.states.push(State::new(cur_cycle + 2, cur_x + v.get_val()))
+                Op::Noop(d) => self.states.push(State::new(cur_cycle + d.num_cycles, cur_x)),
+                Op::Addx(d) => self.states.push(State::new(cur_cycle + d.num_cycles, cur_x + d.get_val()))
             }
         }
     }
 
-    pub fn print_states(&self) {
-        println!("States: {:?}", self.states);
-    }
-
-    pub fn get_part1(&self) -> i32 {
+    /// Sum of the signal strengths during the 20th, 60th, ... 220th cycles
+    pub fn get_part1(&self) -> i64 {
         let mut res = vec![0];
         for val in self.states.iter() {
             if val.get_cycle() /  20 == res.len() {
@@ -116,7 +126,7 @@ impl Session {
         }
         let mut product = 0;
         let mut coeff = 0;
-        for (i, val) in res.into_iter().enumerate() {
+        for val in res.into_iter() {
             coeff += 20;
             if coeff > 220 {
                 break;
@@ -124,14 +134,14 @@ impl Session {
             if coeff % 40 == 0 {
                 continue;
             }
-            product += coeff * val;
+            product += coeff * val as i64;
         }
         product
     }
 
     fn get_part2(&mut self) {
         let get_coords = |cycle_num: usize| -> (usize, usize) {
-            ((cycle_num - 1) / 40 as usize, ((cycle_num - 1) % 40) as usize)
+            ((cycle_num - 1) / 40, (cycle_num - 1) % 40)
         };
         let get_x_coords = |x: i32| -> i32 {
             match x <= 0 {
@@ -143,13 +153,12 @@ impl Session {
         self.crt[0][1] = true;
         let mut cur_cycle = self.states[1].get_cycle();
         let mut cur_x = self.states[1].get_x();
-        let mut i = 0;
         for state in self.states[1..].iter() {
             let State {cycle_number: nex_cycle, x: nex_x} = state;
             for cyc in cur_cycle+1..*nex_cycle+1 {
                 let (crt_row_num, crt_col_num) = get_coords(cyc);
                 let x_col_num = get_x_coords(cur_x);
-                for v in [x_col_num - 1, x_col_num, x_col_num + 1].into_iter() {
+                for v in [x_col_num.saturating_sub(1), x_col_num, x_col_num.saturating_add(1)].into_iter() {
                     if v < 0 {
                         continue;
                     }
@@ -164,47 +173,87 @@ impl Session {
         }
     }
 
-    pub fn print_crt(&self) {
-        for i in 0..6 {
-            for j in 0..40 {
-                match self.crt[i][j] {
-                    false => print!("."),
-                    true => print!("#"),
-                };
-                io::stdout().flush().unwrap();
-            }
-            println!("");
+    /// What the screen shows once the program has run
+    pub fn draw(&mut self) -> Image {
+        self.get_part2();
+        Image::from_rows(&self.crt)
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+/// A program that runs for exactly the 240 cycles the screen has, with the
+/// sprite wandering after the beam so that some pixels light up. The screen
+/// does not grow, so `size` is ignored
+fn random_input(rng: &mut Rng, _size: usize) -> String {
+    let (mut cycles, mut x) = (0, 1);
+    let mut out = String::new();
+    while cycles < 240 {
+        let beam = (cycles % 40) as i64 + rng.range(-4..=6);
+        let v = (beam - x).clamp(-x, 38 - x).clamp(-10, 10);
+        if cycles == 239 || v == 0 || rng.chance(0.35) {
+            out += "noop\n";
+            cycles += 1;
+        } else {
+            out += &format!("addx {}\n", v);
+            x += v;
+            cycles += 2;
         }
     }
+    out
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u8 = 10;
+    type Input = Vec<Op>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        let mut session = Session::new();
+        session.run_through(input);
+        session.get_part1().into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let mut session = Session::new();
+        session.run_through(input);
+        session.draw().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    //#[test]
+    #[test]
     fn it_works() {
-        let inp = parse_input(read_input("in.test").unwrap());
-        let mut session = Session::new();
-        session.run_through(&inp);
-        //session.print_states();
-        println!("test 1: {}", session.get_part1());
-        session.get_part2();
-        session.print_crt();
+        aoc_core::expected::check::<Day10>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let inp = parse_input(read_input("in.1").unwrap());
-        let mut session = Session::new();
-        session.run_through(&inp);
-        //session.print_states();
-        session.get_part2();
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", session.get_part1());
-        session.print_crt();
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day10>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day10>("noop\naddx\n");
+        assert_eq!((e.line, e.message.as_str()), (2, "expected `noop` or `addx V`"));
+        assert_eq!(aoc_core::expected::parse_error::<Day10>("").message, "expected at least one instruction");
+        assert_eq!(aoc_core::expected::parse_error::<Day10>(&"noop\n".repeat(241)).line, 241);
+        assert_eq!(aoc_core::expected::parse_error::<Day10>("addx 2147483647\n").message, "X overflows");
     }
 }
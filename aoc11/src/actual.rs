@@ -10,7 +10,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([54, 89, 94]), 
             Box::new(|old: Item| -> Item { old * 7 }),
             Box::new(|val: Item| -> usize { 
-                match val % 17 == 0 {
+                match val.is_multiple_of(17) {
                     true => 5,
                     false => 3
                 }
@@ -20,7 +20,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([66, 71]), 
             Box::new(|old: Item| -> Item { old + 4 }),
             Box::new(|val: Item| -> usize { 
-                match val % 3 == 0 {
+                match val.is_multiple_of(3) {
                     true => 0,
                     false => 3
                 }
@@ -30,7 +30,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([76, 55, 80, 55, 55, 96, 78]), 
             Box::new(|old: Item| -> Item { old + 2 }),
             Box::new(|val: Item| -> usize { 
-                match val % 5 == 0 {
+                match val.is_multiple_of(5) {
                     true => 7,
                     false => 4
                 }
@@ -40,7 +40,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([93, 69, 76, 66, 89, 54, 59, 94]), 
             Box::new(|old: Item| -> Item { old + 7 }),
             Box::new(|val: Item| -> usize { 
-                match val % 7 == 0 {
+                match val.is_multiple_of(7) {
                     true => 5,
                     false => 2
                 }
@@ -50,7 +50,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([80, 54, 58, 75, 99]), 
             Box::new(|old: Item| -> Item { old * 17 }),
             Box::new(|val: Item| -> usize { 
-                match val % 11 == 0 {
+                match val.is_multiple_of(11) {
                     true => 1,
                     false => 6
                 }
@@ -60,7 +60,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([69, 70, 85, 83]), 
             Box::new(|old: Item| -> Item { old + 8 }),
             Box::new(|val: Item| -> usize { 
-                match val % 19 == 0 {
+                match val.is_multiple_of(19) {
                     true => 2,
                     false => 7
                 }
@@ -70,7 +70,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([89]), 
             Box::new(|old: Item| -> Item { old + 6 }),
             Box::new(|val: Item| -> usize { 
-                match val % 2 == 0 {
+                match val.is_multiple_of(2) {
                     true => 0,
                     false => 1
                 }
@@ -80,7 +80,7 @@ pub fn get_actual_monkeys() -> Vec<Monkey> {
             VecDeque::from([62, 80, 58, 57, 93, 56]), 
             Box::new(|old: Item| -> Item { old * old }),
             Box::new(|val: Item| -> usize { 
-                match val % 13 == 0 {
+                match val.is_multiple_of(13) {
                     true => 6,
                     false => 4
                 }
@@ -10,7 +10,7 @@ pub fn get_example_monkeys() -> Vec<Monkey> {
             VecDeque::from([79, 98]), 
             Box::new(|old: Item| -> Item { old * 19 }),
             Box::new(|val: Item| -> usize { 
-                match val % 23 == 0 {
+                match val.is_multiple_of(23) {
                     true => 2,
                     false => 3
                 }
@@ -20,7 +20,7 @@ pub fn get_example_monkeys() -> Vec<Monkey> {
             VecDeque::from([54, 65, 75, 74]), 
             Box::new(|old: Item| -> Item { old + 6 }),
             Box::new(|val: Item| -> usize { 
-                match val % 19 == 0 {
+                match val.is_multiple_of(19) {
                     true => 2,
                     false => 0
                 }
@@ -30,7 +30,7 @@ pub fn get_example_monkeys() -> Vec<Monkey> {
             VecDeque::from([79, 60, 97]), 
             Box::new(|old: Item| -> Item { old * old }),
             Box::new(|val: Item| -> usize { 
-                match val % 13 == 0 {
+                match val.is_multiple_of(13) {
                     true => 1,
                     false => 3
                 }
@@ -40,7 +40,7 @@ pub fn get_example_monkeys() -> Vec<Monkey> {
             VecDeque::from([74]), 
             Box::new(|old: Item| -> Item { old + 3 }),
             Box::new(|val: Item| -> usize { 
-                match val % 17 == 0 {
+                match val.is_multiple_of(17) {
                     true => 0,
                     false => 1
                 }
@@ -1,8 +1,18 @@
+//! Day 11: Monkey in the Middle.
+//!
+//! [`parse_monkeys`] reads the notes into [`Monkey`]s, and a [`Session`]
+//! plays rounds of keep away with them one [`step`](Simulation::step) at a
+//! time.
+
 pub mod example;
 pub mod monkey;
 pub mod actual;
 
-use monkey::{Monkey, Item};
+pub use monkey::{parse_monkeys, Item, Monkey};
+
+use aoc_core::simulation::Simulation;
+use aoc_core::{Answer, Input, Solution};
+use aoc_core::gen::Rng;
 
 /// Part 1 is straightforward implement as in the question.
 /// Part 2 is the Yearly Chinese Remainder Theorem question.
@@ -10,19 +20,26 @@ use monkey::{Monkey, Item};
 /// This check is to see if divisible by a number. So, if we take the LCM of these numbers
 /// and take modulo of worry after applying the new  = fn(old) worry function. The result is unchanged.
 /// Not doing so would result in integer overflow 
-struct Session {
+/// Each step is one round.
+pub struct Session {
     monkeys: Vec<Monkey>,
     counts: Vec<usize>,
-    lcm: Item
+    lcm: Item,
+    should_worry: bool,
+    rounds: usize
 }
 
 impl Session {
-    pub fn new(monkeys: Vec<Monkey>, lcm: Item) -> Self {
+    /// With `should_worry`, worry levels are kept modulo `lcm` instead of
+    /// being divided by 3 after every inspection
+    pub fn new(monkeys: Vec<Monkey>, lcm: Item, should_worry: bool) -> Self {
         let size = monkeys.len();
         Session {
-            monkeys: monkeys,
+            monkeys,
             counts: vec![0; size],
-            lcm: lcm
+            lcm,
+            should_worry,
+            rounds: 0
         }
     }
 
@@ -32,7 +49,7 @@ impl Session {
         unsafe {
             let monkey_ref = monkey_ptr.as_mut().unwrap();
             match monkey_ref.is_empty() {
-                true => return,
+                true => (),
                 false => {
                     self.counts[index] += monkey_ref.items.len();
                     while !monkey_ref.items.is_empty() {
@@ -43,7 +60,7 @@ impl Session {
                             false => worry / 3
                         };
                         let destination = (monkey_ref.test_fn)(worry);
-                        if destination < 0 || destination > size - 1 {
+                        if destination >= size {
                             panic!("destination exceeds size: {}", destination);
                         }
                         let dest_ptr: *mut Monkey = &mut self.monkeys[destination];
@@ -54,62 +71,179 @@ impl Session {
         }
     }
 
-    pub fn run_round(&mut self, should_worry: bool) {
+    /// Every monkey takes its turn once
+    pub fn run_round(&mut self) {
         let size = self.monkeys.len();
         for i in 0..size {
-            self.run_turn(i, should_worry);
-        }
-    }
-
-    pub fn test(&mut self) {
-        self.run_round(false);
-        for i in 0..self.monkeys.len() {
-            self.monkeys[i].print();
+            self.run_turn(i, self.should_worry);
         }
-        println!("Counts: {:?}", self.counts);
+        self.rounds += 1;
     }
 
-    pub fn run_rounds(&mut self, num_rounds: usize, should_worry: bool) -> usize {
-        for i in 0..num_rounds {
-            self.run_round(should_worry);
-        }
+    /// Items inspected by the two busiest monkeys, multiplied
+    pub fn monkey_business(&self) -> usize {
         let mut counts = self.counts.clone();
         counts.sort();
         counts[counts.len() -1] * counts[counts.len() - 2]
     }
 }
 
+impl Simulation for Session {
+    /// Worry levels of the items each monkey holds
+    type Snapshot = Vec<Vec<Item>>;
+
+    fn step(&mut self) -> bool {
+        self.run_round();
+        true
+    }
+
+    fn steps(&self) -> usize {
+        self.rounds
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.monkeys.iter().map(|m| m.items.iter().copied().collect()).collect()
+    }
+}
+
+/// Eight monkeys per `size`. Divisors are primes up to 23 so that their LCM,
+/// squared, still fits in an `Item`; monkeys never throw to themselves. Part 1
+/// does not reduce worry levels modulo anything, so drafts are played through
+/// its 20 rounds and dropped until one does not overflow
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    const PRIMES: [Item; 9] = [2, 3, 5, 7, 11, 13, 17, 19, 23];
+    struct Draft {
+        items: Vec<Item>,
+        /// `*` or `+`, and the operand; `None` for `old`
+        op: (char, Option<Item>),
+        divisor: Item,
+        targets: (usize, usize),
+    }
+    let count = 8 * size;
+    loop {
+        let mut primes = PRIMES;
+        rng.shuffle(&mut primes);
+        let squarer = rng.below(count);
+        let monkeys: Vec<Draft> = (0..count).map(|i| {
+            let num_items = rng.range(1..=8);
+            let op = match rng.below(3) {
+                _ if i == squarer => ('*', None),
+                0 => ('*', Some(rng.range(2..=19) as Item)),
+                _ => ('+', Some(rng.range(1..=8) as Item)),
+            };
+            let mut target = || (i + 1 + rng.below(count - 1)) % count;
+            let if_true = target();
+            let if_false = loop {
+                let t = target();
+                if t != if_true {
+                    break t;
+                }
+            };
+            Draft {
+                items: (0..num_items).map(|_| rng.range(50..=99) as Item).collect(),
+                op,
+                divisor: primes.get(i).copied().unwrap_or_else(|| *rng.pick(&PRIMES)),
+                targets: (if_true, if_false),
+            }
+        }).collect();
+
+        let mut held: Vec<Vec<Item>> = monkeys.iter().map(|m| m.items.clone()).collect();
+        let overflows = (0..20 * count).any(|turn| {
+            let m = &monkeys[turn % count];
+            std::mem::take(&mut held[turn % count]).into_iter().any(|old| {
+                let operand = m.op.1.unwrap_or(old);
+                let new = match m.op.0 {
+                    '*' => old.checked_mul(operand),
+                    _ => old.checked_add(operand),
+                };
+                let Some(new) = new.map(|v| v / 3) else {
+                    return true;
+                };
+                let to = if new % m.divisor == 0 { m.targets.0 } else { m.targets.1 };
+                held[to].push(new);
+                false
+            })
+        });
+        if overflows {
+            continue;
+        }
+
+        let notes: Vec<String> = monkeys.iter().enumerate().map(|(i, m)| {
+            let items: Vec<String> = m.items.iter().map(|v| v.to_string()).collect();
+            let operand = m.op.1.map_or("old".to_string(), |v| v.to_string());
+            format!(
+                "Monkey {}:\n  Starting items: {}\n  Operation: new = old {} {}\n  Test: divisible by {}\n    If true: throw to monkey {}\n    If false: throw to monkey {}\n",
+                i, items.join(", "), m.op.0, operand, m.divisor, m.targets.0, m.targets.1
+            )
+        }).collect();
+        return notes.join("\n");
+    }
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    const DAY: u8 = 11;
+    /// Monkeys and the LCM of their divisors
+    type Input = (Vec<Monkey>, Item);
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_monkeys(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        let mut session = Session::new(input.0.clone(), 0, false);
+        session.run_to(20);
+        session.monkey_business().into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let mut session = Session::new(input.0.clone(), input.1, true);
+        session.run_to(10000);
+        session.monkey_business().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::example::LCM_OF_MODS;
-
     use super::*;
 
     #[test]
     fn it_works() {
-        use example::*;
-        let inp = get_example_monkeys();
-        let mut session = Session::new(inp, 0);
-        let part1 = session.run_rounds(20, false);
-        let mut session2 = Session::new(get_example_monkeys(), LCM_OF_MODS);
-        let part2 = session2.run_rounds(10000, true);
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day11>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        use actual::*;
-        let inp = get_actual_monkeys();
-        let mut session = Session::new(inp, 0);
-        let part1 = session.run_rounds(20, false);
-        let mut session2 = Session::new(get_actual_monkeys(), LCM_OF_MODS);
-        let part2 = session2.run_rounds(10000, true);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day11>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let monkey = |to: usize| format!(
+            "Monkey 0:\n  Starting items: 79\n  Operation: new = old * 19\n  Test: divisible by 23\n    If true: throw to monkey {}\n    If false: throw to monkey 1\n",
+            to
+        );
+        let e = aoc_core::expected::parse_error::<Day11>(&monkey(1));
+        assert_eq!((e.line, e.message.as_str()), (1, "expected at least two monkeys"));
+        assert_eq!(aoc_core::expected::parse_error::<Day11>("").line, 1);
+        let e = aoc_core::expected::parse_error::<Day11>(&format!("{}\n{}", monkey(1), monkey(1)));
+        assert_eq!((e.line, e.column, e.message.as_str()), (12, 30, "a monkey cannot throw to itself"));
+    }
+
+    #[test]
+    fn first_round() {
+        let (monkeys, lcm) = Day11::parse(&Input::from_path("in.test").unwrap()).unwrap();
+        let mut session = Session::new(monkeys, lcm, false);
+        assert!(session.step());
+        assert_eq!(session.steps(), 1);
+        assert_eq!(
+            session.snapshot(),
+            vec![vec![20, 23, 27, 26], vec![2080, 25, 167, 207, 401, 1046], vec![], vec![]]
+        );
     }
 }
@@ -1,13 +1,18 @@
 use std::collections::VecDeque;
+use std::rc::Rc;
 
-pub type Item = u64;
+use aoc_core::error::parse_at;
+use aoc_core::{Input, ParseError};
 
-const LCM_OF_MODS: u64 = 9699690;
+pub type Item = u64;
 
+/// The items a monkey holds, how inspecting one changes its worry level, and
+/// which monkey it throws an item to
+#[derive(Clone)]
 pub struct Monkey {
     pub items: VecDeque<Item>,
-    pub worry_fn: Box<dyn Fn(Item) -> Item>,
-    pub test_fn: Box<dyn Fn(Item) -> usize>
+    pub worry_fn: Rc<dyn Fn(Item) -> Item>,
+    pub test_fn: Rc<dyn Fn(Item) -> usize>
 }
 
 impl Monkey {
@@ -16,9 +21,9 @@ impl Monkey {
         worry: Box<dyn Fn(Item) -> Item>,
         test: Box<dyn Fn(Item) -> usize>) -> Self {
         Monkey {
-            items: items,
-            worry_fn: worry,
-            test_fn: test
+            items,
+            worry_fn: Rc::from(worry),
+            test_fn: Rc::from(test)
         }
     }
 
@@ -26,11 +31,84 @@ impl Monkey {
         self.items.is_empty()
     }
 
-    pub fn print(&self) {
-        println!("items {:?}", self.items);
-    }
-
     pub fn size(&self) -> usize {
         self.items.len()
     }
-}
\ No newline at end of file
+}
+
+fn gcd(a: Item, b: Item) -> Item {
+    match b == 0 {
+        true => a,
+        false => gcd(b, a % b)
+    }
+}
+
+fn last_word(line: &str) -> &str {
+    line.split_whitespace().last().unwrap_or(line)
+}
+
+/// Parse the monkey notes, one block per monkey; there must be two monkeys
+/// for there to be monkey business, and none may throw to itself.
+/// Also returns the LCM of all the divisibility checks (see Session in lib.rs)
+pub fn parse_monkeys(input: &Input) -> Result<(Vec<Monkey>, Item), ParseError> {
+    let mut lcm = 1;
+    let blocks = input.blocks();
+    if blocks.len() < 2 {
+        let (n, line) = blocks.first().map_or((1, ""), |b| (b.first_line, b.lines[0]));
+        return Err(ParseError::new(n, line, "expected at least two monkeys"));
+    }
+    let monkeys = blocks.iter().enumerate().map(|(index, block)| {
+        let lines = &block.lines;
+        let n = |i: usize| block.first_line + i;
+        if lines.len() != 6 {
+            return Err(ParseError::new(n(0), lines[0], "expected six lines per monkey"));
+        }
+        let Some((_, items)) = lines[1].split_once(':') else {
+            return Err(ParseError::new(n(1), lines[1], "expected `Starting items: ...`"));
+        };
+        let items = items
+            .split(',')
+            .map(|v| parse_at::<Item>(n(1), lines[1], v.trim()))
+            .collect::<Result<_, _>>()?;
+        let op: Vec<&str> = lines[2].split_whitespace().rev().take(2).collect();
+        let worry: Box<dyn Fn(Item) -> Item> = match (op.get(1).copied(), op.first().copied()) {
+            (Some("*"), Some("old")) => Box::new(|old: Item| -> Item { old * old }),
+            (Some("+"), Some("old")) => Box::new(|old: Item| -> Item { old + old }),
+            (Some("*"), Some(v)) => {
+                let v = parse_at::<Item>(n(2), lines[2], v)?;
+                Box::new(move |old: Item| -> Item { old * v })
+            },
+            (Some("+"), Some(v)) => {
+                let v = parse_at::<Item>(n(2), lines[2], v)?;
+                Box::new(move |old: Item| -> Item { old + v })
+            },
+            _ => return Err(ParseError::new(n(2), lines[2], "unknown operation")),
+        };
+        let divisor = parse_at::<Item>(n(3), lines[3], last_word(lines[3]))?;
+        if divisor == 0 {
+            return Err(ParseError::at(n(3), lines[3], last_word(lines[3]), "cannot test divisibility by 0"));
+        }
+        let target = |i: usize| -> Result<usize, ParseError> {
+            let word = last_word(lines[i]);
+            match parse_at::<usize>(n(i), lines[i], word)? {
+                t if t == index => Err(ParseError::at(n(i), lines[i], word, "a monkey cannot throw to itself")),
+                t if t < blocks.len() => Ok(t),
+                _ => Err(ParseError::at(n(i), lines[i], word, "no such monkey")),
+            }
+        };
+        let if_true = target(4)?;
+        let if_false = target(5)?;
+        lcm = lcm / gcd(lcm, divisor) * divisor;
+        Ok(Monkey::new(
+            items,
+            worry,
+            Box::new(move |val: Item| -> usize {
+                match val.is_multiple_of(divisor) {
+                    true => if_true,
+                    false => if_false
+                }
+            })
+        ))
+    }).collect::<Result<_, _>>()?;
+    Ok((monkeys, lcm))
+}
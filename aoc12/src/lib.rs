@@ -1,155 +1,154 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::VecDeque;
+//! Day 12: Hill Climbing Algorithm.
+//!
+//! A [`Graph`] borrows the height map and the summit, and finds the fewest
+//! steps up from a square or from anywhere at the bottom.
+
 use std::cmp::min;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::grid::Pos;
+use aoc_core::search;
+use aoc_core::{Answer, Grid, Input, Solution};
+use aoc_core::gen::Rng;
 
-type Pos = (usize, usize);
-
-fn parse_input(inp: Vec<String>) -> (Pos, Pos, Vec<Vec<u8>>) {
-    let mut res = vec![vec![0; inp[0].len()]; inp.len()];
-    let mut start = (0, 0);
-    let mut end = (0, 0);
-    for (i, line) in inp.into_iter().enumerate() {
-        for (j, v) in line.chars().enumerate() {
-            if v == 'S' {
-                start = (i, j);
-                continue;
-            }
-            if v == 'E' {
-                end = (i, j);
-                res[i][j] = 25;
-                continue;
-            }
-            res[i][j] = v as u8 - 97;
-        }
-    }
-    (start, end, res)
+/// Start, end and the height map, with `S` at height 0 and `E` at 25
+fn parse_input(input: &Input) -> aoc_core::Result<(Pos, Pos, Grid<u8>)> {
+    let map = Grid::parse(input, |c| match c {
+        'S' | 'E' | 'a'..='z' => Some(c),
+        _ => None,
+    })?;
+    let start = map.find(|&c| c == 'S').ok_or_else(|| input.parse_error(1, "", "no start"))?;
+    let end = map.find(|&c| c == 'E').ok_or_else(|| input.parse_error(1, "", "no end"))?;
+    let topo = map.map(|&c| match c {
+        'S' => 0,
+        'E' => 25,
+        _ => c as u8 - b'a',
+    });
+    Ok((start, end, topo))
 }
 
-struct Graph<'a> {
+/// The height map, 0 to 25, with the summit at `end`
+pub struct Graph<'a> {
     end: &'a Pos,
-    topo: &'a Vec<Vec<u8>>
+    topo: &'a Grid<u8>
 }
 
 impl<'a> Graph<'a> {
-    pub fn new(end: &'a Pos, topo: &'a Vec<Vec<u8>>) -> Self {
+    pub fn new(end: &'a Pos, topo: &'a Grid<u8>) -> Self {
         Graph {
-            end: end,
-            topo: topo
+            end,
+            topo
         }
     }
 
+    /// Squares reachable in one step going up, climbing at most one level
+    fn climb(&self, cur: Pos) -> impl Iterator<Item = Pos> + '_ {
+        let cur_height = self.topo[cur];
+        self.topo.neighbours4(cur).filter(move |&v| cur_height + 1 >= self.topo[v])
+    }
+
+    /// Squares reachable in one step going down, undoing `climb`
+    fn descend(&self, cur: Pos) -> impl Iterator<Item = Pos> + '_ {
+        let cur_height = self.topo[cur];
+        self.topo.neighbours4(cur).filter(move |&v| cur_height <= self.topo[v] + 1)
+    }
+
+    /// Fewest steps up from `start` to `end`, `u16::MAX` if it cannot be climbed
     pub fn get_shortest_path(&self, start: Pos, end: Pos) -> u16 {
-        let (m, n) = (self.topo.len(), self.topo[0].len());
-        let mut shortest = vec![vec![u16::MAX; n]; m];
-        let mut queue = VecDeque::from([(start, 0)]);
-        let get_next = |cur: Pos| -> Vec<Pos> {
-            let mut res = Vec::new();
-            if cur.0 > 0 {
-                res.push((cur.0 - 1, cur.1));
-            }
-            if cur.0 < m - 1 {
-                res.push((cur.0 + 1, cur.1));
-            }
-            if cur.1 > 0 {
-                res.push((cur.0, cur.1 - 1));
-            }
-            if cur.1 < n - 1 {
-                res.push((cur.0, cur.1 + 1));
-            }
-            res
-        };
-        while !queue.is_empty() {
-            let ((cur_x, cur_y), cost) = queue.pop_front().unwrap();
-            if cur_x < 0 || cur_x >= m || cur_y < 0 || cur_y >= n {
-                continue;
-            }
-            let cur_height = self.topo[cur_x][cur_y];
-            if cost >= shortest[cur_x][cur_y] {
-                continue;
-            }
-            shortest[cur_x][cur_y] = cost;
-            for v in get_next((cur_x, cur_y)) {
-                let nex_height = self.topo[v.0][v.1];
-                if cur_height + 1 >= nex_height {
-                    queue.push_back((v, cost + 1));
-                }
-            }
-        }
-        shortest[end.0][end.1]
+        let found = search::bfs([start], |&cur| self.climb(cur), |&cur| cur == end);
+        found.cost().map_or(u16::MAX, |cost| cost as u16)
     }
 
+    /// Fewest steps up to the summit from any square at height 0, climbing
+    /// from each of them in turn
     pub fn brute_force_part_2(&self) -> u16 {
         let mut min_dist = u16::MAX;
-        let mut start_points = vec![];
-        for (i, line) in self.topo.iter().enumerate() {
-            for (j, v) in line.iter().enumerate() {
-                match *v == 0 {
-                    true => start_points.push((i, j)),
-                    false => (),
-                }
-            }
-        }
+        let start_points = self.topo.find_all(|&v| v == 0);
         for start in start_points {
             min_dist = min(min_dist, self.get_shortest_path(start, *self.end));
         }
         min_dist
     }
 
+    /// Fewest steps down from `start` to any square at height 0; the same as
+    /// [`brute_force_part_2`](Graph::brute_force_part_2) from the summit.
     // Invert the BFS -> Start from end and flip the condition to move to next step
     pub fn optimized_part_2(&self, start: Pos) -> u16 {
-        let (m, n) = (self.topo.len(), self.topo[0].len());
-        let mut shortest = vec![vec![u16::MAX; n]; m];
-        let mut queue = VecDeque::from([(start, 0)]);
-        let get_next = |cur: Pos| -> Vec<Pos> {
-            let mut res = Vec::new();
-            if cur.0 > 0 {
-                res.push((cur.0 - 1, cur.1));
-            }
-            if cur.0 < m - 1 {
-                res.push((cur.0 + 1, cur.1));
-            }
-            if cur.1 > 0 {
-                res.push((cur.0, cur.1 - 1));
-            }
-            if cur.1 < n - 1 {
-                res.push((cur.0, cur.1 + 1));
-            }
-            res
-        };
-        while !queue.is_empty() {
-            let ((cur_x, cur_y), cost) = queue.pop_front().unwrap();
-            if cur_x < 0 || cur_x >= m || cur_y < 0 || cur_y >= n {
-                continue;
-            }
-            let cur_height = self.topo[cur_x][cur_y];
-            if cost >= shortest[cur_x][cur_y] {
-                continue;
-            }
-            shortest[cur_x][cur_y] = cost;
-            for v in get_next((cur_x, cur_y)) {
-                let nex_height = self.topo[v.0][v.1];
-                if cur_height - 1 <= nex_height {
-                    queue.push_back((v, cost + 1));
-                }
-            }
+        let found = search::bfs([start], |&cur| self.descend(cur), |&cur| self.topo[cur] == 0);
+        found.cost().map_or(u16::MAX, |cost| cost as u16)
+    }
+}
+
+/// A 41 by 143 map at `size` 1, both sides growing with the square root of
+/// `size`. Heights rise towards the summit with some noise, and a staircase
+/// that climbs one level at a time is cut from `S` to `E` so that there is
+/// always a way up
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let (height, width) = (aoc_core::gen::scaled(41, size, 2), aoc_core::gen::scaled(143, size, 2));
+    let start = (rng.below(height), 0);
+    let end = (rng.below(height), width / 2 + rng.below(width / 2 - 10));
+    let reach = (height + width) as f64;
+    let mut map: Vec<Vec<u8>> = (0..height).map(|r| (0..width).map(|c| {
+        let dist = r.abs_diff(end.0) + c.abs_diff(end.1);
+        let level = 25.0 - 40.0 * dist as f64 / reach + rng.range(-3..=3) as f64;
+        level.clamp(0.0, 25.0) as u8
+    }).collect()).collect();
+
+    // One step at a time towards the summit, down or across in a random order
+    let mut steps: Vec<(usize, usize)> = vec![];
+    let (mut r, mut c) = start;
+    let mut moves: Vec<bool> = vec![true; start.0.abs_diff(end.0)];
+    moves.extend(vec![false; end.1 - start.1]);
+    rng.shuffle(&mut moves);
+    steps.push((r, c));
+    for vertical in moves {
+        match vertical {
+            true if end.0 > r => r += 1,
+            true => r -= 1,
+            false => c += 1,
         }
-        let mut min_dist = u16::MAX;
-        for (i, line) in self.topo.iter().enumerate() {
-            for (j, v) in line.iter().enumerate() {
-                if *v == 0 {
-                    min_dist = min(min_dist, shortest[i][j]);
-                }
-            }
+        steps.push((r, c));
+    }
+    for (i, &(r, c)) in steps.iter().enumerate() {
+        map[r][c] = (25 * i / (steps.len() - 1)) as u8;
+    }
+
+    let mut out = String::with_capacity(height * (width + 1));
+    for (r, row) in map.iter().enumerate() {
+        for (c, &level) in row.iter().enumerate() {
+            out.push(match (r, c) {
+                p if p == start => 'S',
+                p if p == end => 'E',
+                _ => (b'a' + level) as char,
+            });
         }
-        min_dist
+        out.push('\n');
+    }
+    out
+}
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    const DAY: u8 = 12;
+    /// Start, end and the height map
+    type Input = (Pos, Pos, Grid<u8>);
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        let (start, end, topo) = input;
+        Graph::new(end, topo).get_shortest_path(*start, *end).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let (_, end, topo) = input;
+        Graph::new(end, topo).optimized_part_2(*end).into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
 
@@ -159,26 +158,39 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let (start, end, inp) = parse_input(read_input("in.test").unwrap());
-        let graph = Graph::new(&end, &inp);
-        let part1 = graph.get_shortest_path(start, end);
-        let part2 = graph.brute_force_part_2();
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day12>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let (start, end, inp) = parse_input(read_input("in.1").unwrap());
-        let graph = Graph::new(&end, &inp);
-        let part1 = graph.get_shortest_path(start, end);
-        //let part2 = graph.brute_force_part_2();
-        let part2 = graph.optimized_part_2(end);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:?}", elapsed);
+        aoc_core::expected::check::<Day12>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day12>("Sab\nacE\nab?\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (3, 3, "?"));
+        assert_eq!(aoc_core::expected::parse_error::<Day12>("Sab\n").message, "no end");
+    }
+
+    /// A few rows of low, bumpy ground, so that some squares are out of reach
+    fn small_map(rng: &mut Rng, scale: usize) -> String {
+        let (height, width) = (2 + scale / 3, 2 + scale);
+        let mut map: Vec<Vec<char>> = (0..height).map(|_| (0..width).map(|_| *rng.pick(&['a', 'b', 'c', 'd'])).collect()).collect();
+        map[rng.below(height)][rng.below(width / 2)] = 'S';
+        map[rng.below(height)][width / 2 + rng.below(width - width / 2)] = 'E';
+        map.iter().map(|row| row.iter().collect::<String>() + "\n").collect()
+    }
+
+    #[test]
+    fn brute_force_agrees() {
+        aoc_core::differential::check(
+            0..40,
+            1..=12,
+            small_map,
+            |input| parse_input(input).ok(),
+            |(_, end, topo)| Graph::new(end, topo).brute_force_part_2(),
+            |(_, end, topo)| Graph::new(end, topo).optimized_part_2(*end),
+        );
     }
 }
@@ -1,21 +1,34 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 13: Distress Signal.
+//!
+//! A packet is a [`ListNode`] of [`Node`]s, which parses from its text and
+//! compares in the puzzle's order. A [`Session`] holds the [`Pair`]s the
+//! signal came in.
+//!
+//! ```
+//! use std::cmp::Ordering;
+//! use aoc13::ListNode;
+//!
+//! let left: ListNode = "[[1],[2,3,4]]".parse().unwrap();
+//! let right: ListNode = "[[1],4]".parse().unwrap();
+//! assert_eq!(left.compare(&right), Ordering::Less);
+//! ```
+
 use std::mem::take;
 use std::fmt::Debug;
 use std::cmp::Ordering;
+use std::str::FromStr;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-type Pair = (Node, Node);
+/// Two packets, left and right
+pub type Pair = (Node, Node);
 
-#[derive(Debug, Default)]
-struct ListNode {
-    items: Vec<Box<Node>>
+/// A list in a packet, and so a packet itself
+#[derive(Debug, Default, Clone)]
+pub struct ListNode {
+    items: Vec<Node>
 }
 
 impl ListNode {
@@ -25,24 +38,29 @@ impl ListNode {
         }
     }
 
-    pub fn single(single_item: Box<Node>) -> Self {
+    pub fn single(single_item: Node) -> Self {
         ListNode {
             items: vec![single_item]
         }
     }
 
-    pub fn push(&mut self, node: Box<Node>) {
+    pub fn push(&mut self, node: Node) {
         self.items.push(node);
     }
 
     pub fn get(&self, index: usize) -> &Node {
-        self.items[index].as_ref()
+        &self.items[index]
     }
 
     pub fn len(&self) -> usize {
         self.items.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Less if `self` comes first in the right order
     pub fn compare(&self, node: &ListNode) -> Ordering {
         let (mut i, mut j) = (0, 0);
         loop {
@@ -66,8 +84,20 @@ impl ListNode {
     }
 }
 
-#[derive(Debug)]
-enum Node {
+impl FromStr for ListNode {
+    type Err = ParseError;
+
+    /// A packet on its own, as on one line of the input
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut node = ListNode::new();
+        parse_packets(&mut node, s, 1, s)?;
+        Ok(node)
+    }
+}
+
+/// A value in a packet
+#[derive(Debug, Clone)]
+pub enum Node {
     Number(u8),
     List(ListNode),
 }
@@ -81,23 +111,23 @@ impl Node {
         match (self, other) {
             (Node::Number(u), Node::Number(v)) => {
                 if v == u {
-                    return Ordering::Equal
+                    Ordering::Equal
                 } else if u < v {
-                    return Ordering::Less
+                    Ordering::Less
                 } else {
-                    return Ordering::Greater
+                    Ordering::Greater
                 }
             },
             (Node::List(u), Node::List(v)) => {
-                return u.compare(v);
+                u.compare(v)
             },
             (Node::List(u), Node::Number(v)) => {
-                let new_list = ListNode::single(Box::new(Node::Number(*v)));
-                return u.compare(&new_list);
+                let new_list = ListNode::single(Node::Number(*v));
+                u.compare(&new_list)
             },
             (Node::Number(u), Node::List(v)) => {
-                let new_list = ListNode::single(Box::new(Node::Number(*u)));
-                return new_list.compare(v);
+                let new_list = ListNode::single(Node::Number(*u));
+                new_list.compare(v)
             }
         }
     }
@@ -108,7 +138,7 @@ impl Node {
 /// So this will effectively figure out the next node in the list of nodes
 fn find_index(slice: &str) -> usize {
     let mut bracket_count = 0;
-    for (i, c) in slice.chars().enumerate() {
+    for (i, c) in slice.char_indices() {
         if c == ',' && bracket_count == 0 {
             return i;
         }
@@ -126,10 +156,14 @@ fn find_index(slice: &str) -> usize {
 /// So, the first value of this list is the slice upto first comma
 /// where the bracket_count is <= 0. 
 /// If it is a ListNode -> create a new ListNode, and recurse
-fn parse_packets(cur_node: &mut ListNode, inp: &str) {
+/// `inp` is part of `line`, line number `n`, for error reporting
+fn parse_packets(cur_node: &mut ListNode, inp: &str, n: usize, line: &str) -> Result<(), ParseError> {
+    if inp.len() < 2 || !inp.starts_with('[') || !inp.ends_with(']') {
+        return Err(ParseError::at(n, line, inp, "expected a list"));
+    }
     // If empty list, return early
-    if inp.len() <= 2 {
-        return;
+    if inp.len() == 2 {
+        return Ok(());
     }
     let mut start_index = 1;
     let end_index = inp.len() - 1;
@@ -137,60 +171,65 @@ fn parse_packets(cur_node: &mut ListNode, inp: &str) {
         let cur_slice = &inp[start_index..end_index];
         let ind = find_index(cur_slice);
         let node: &str = &cur_slice[0..ind];
-        match node.parse::<u8>() {
-            Ok(val) => cur_node.push(Box::new(Node::Number(val))),
-            Err(_) => {
+        match node.starts_with('[') {
+            false => cur_node.push(Node::Number(parse_at(n, line, node)?)),
+            true => {
                 let mut new_node = ListNode::new();
-                parse_packets(&mut new_node, &node);
-                cur_node.push(Box::new(Node::List(new_node)));
+                parse_packets(&mut new_node, node, n, line)?;
+                cur_node.push(Node::List(new_node));
             }
         }
         start_index += ind + 1;
     }
+    Ok(())
 }
 
-fn parse_input(input: Vec<String>) -> Vec<Pair> {
+fn parse_input(input: &Input) -> Result<Vec<Pair>, ParseError> {
     let mut res = vec![];
-    for inp in input {
+    let mut last = (0, "");
+    for (n, inp) in input.numbered_lines() {
         if inp.is_empty() {
             continue;
         } else {
             let mut new_node = ListNode::new();
-            parse_packets(&mut new_node, &inp);
+            parse_packets(&mut new_node, inp, n, inp)?;
             res.push(new_node);
+            last = (n, inp);
         }
     }
+    if res.len() % 2 == 1 {
+        return Err(ParseError::new(last.0, last.1, "packet without a pair"));
+    }
     let mut new_res = vec![];
     let mut i = 0;
     while i < res.len() {
         new_res.push((Node::List(take(&mut res[i])), Node::List(take(&mut res[i + 1]))));
         i += 2;
     }
-    new_res
+    Ok(new_res)
 }
 
-struct Session {
+/// The pairs of packets, in the order they arrived
+pub struct Session {
     pairs: Vec<Pair>
 }
 
 impl Session {
     pub fn new(pairs: Vec<Pair>) -> Self {
         Session {
-            pairs: pairs
+            pairs
         }
     }
 
+    /// Sum of the 1-based indices of the pairs already in the right order
     pub fn sum_right_ordered_indices(&self) -> usize {
         let mut res = 0;
         for i in 0..self.pairs.len() {
-            //println!("Pair: {}, LHS: {:?}", i, self.pairs[i].0);
-            //println!("Pair: {}, RHS: {:?}", i, self.pairs[i].1);
-            match self.pairs[i].0.compare(&self.pairs[i].1) {
-                Ordering::Less => res += i + 1,
-                _ => (),
-            } 
-            //println!("Pair: {}, Comparison: {:?}", i + 1, self.pairs[i].0.compare(&self.pairs[i].1));
-            //println!("");
+            let order = self.pairs[i].0.compare(&self.pairs[i].1);
+            aoc_core::trace!("pair compared", pair = i + 1, lhs = ?self.pairs[i].0, rhs = ?self.pairs[i].1, order = ?order);
+            if order == Ordering::Less {
+                res += i + 1;
+            }
         }
         res
     }
@@ -198,13 +237,13 @@ impl Session {
     fn get_dividers() -> Vec<Node> {
         vec![
             Node::List(ListNode::single(
-                Box::new(Node::List(ListNode::single(
-                    Box::new(Node::Number(2))))))), 
+                Node::List(ListNode::single(Node::Number(2))))),
             Node::List(ListNode::single(
-                Box::new(Node::List(ListNode::single(
-                    Box::new(Node::Number(6)))))))]
+                Node::List(ListNode::single(Node::Number(6)))))]
     }
 
+    /// Sort every packet along with the two divider packets and multiply
+    /// where the dividers end up. This takes the packets out of the pairs
     pub fn get_sorted_dividers_pos(&mut self) -> usize {
         let mut flat_items: Vec<Node> = vec![];
         for val in self.pairs.iter_mut() {
@@ -217,9 +256,8 @@ impl Session {
         let mut res = 1;
         for (i, val) in flat_items.iter().enumerate() {
             for div in dividers_copy.iter() {
-                match div.compare(val) {
-                    Ordering::Equal => res *= i + 1,
-                    _ => (),
+                if div.compare(val) == Ordering::Equal {
+                    res *= i + 1;
                 }
             }
         }
@@ -227,31 +265,76 @@ impl Session {
     }
 }
 
+/// 150 pairs of packets per `size`. Half the pairs start out the same, so
+/// that the comparison has to look past the first few values. Packets that
+/// would sort level with a divider packet are drawn again
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let divider = |p: &str| matches!(p.replace(['[', ']'], "").as_str(), "2" | "6");
+    fn packet(rng: &mut Rng, depth: usize, prefix: &[String]) -> String {
+        let len = rng.range(prefix.len() as i64..=prefix.len() as i64 + 4) as usize;
+        let values: Vec<String> = prefix.iter().cloned().chain((prefix.len()..len).map(|_| {
+            match depth < 4 && rng.chance(0.3) {
+                true => packet(rng, depth + 1, &[]),
+                false => rng.range(0..=10).to_string(),
+            }
+        })).collect();
+        format!("[{}]", values.join(","))
+    }
+    let mut pairs = vec![];
+    while pairs.len() < 150 * size {
+        let first: Vec<String> = (0..rng.range(0..=5)).map(|_| packet(rng, 1, &[])).collect();
+        let shared = match rng.chance(0.5) {
+            true => rng.range(0..=first.len() as i64) as usize,
+            false => 0,
+        };
+        let (first, second) = (format!("[{}]", first.join(",")), packet(rng, 0, &first[..shared]));
+        if !divider(&first) && !divider(&second) {
+            pairs.push(format!("{}\n{}\n", first, second));
+        }
+    }
+    pairs.join("\n")
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u8 = 13;
+    type Input = Vec<Pair>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        Session::new(input.clone()).sum_right_ordered_indices().into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        Session::new(input.clone()).get_sorted_dividers_pos().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let pairs = parse_input(read_input("in.test").unwrap());
-        let mut session = Session::new(pairs);
-        let part1 = session.sum_right_ordered_indices();
-        let part2 = session.get_sorted_dividers_pos();
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day13>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let pairs = parse_input(read_input("in.1").unwrap());
-        let mut session = Session::new(pairs);
-        let part1 = session.sum_right_ordered_indices();
-        let part2 = session.get_sorted_dividers_pos();
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:?}", elapsed);
+        aoc_core::expected::check::<Day13>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day13>("[1,2]\n[1,\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "[1,"));
     }
 }
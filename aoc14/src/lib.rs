@@ -1,53 +1,62 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 14: Regolith Reservoir.
+//!
+//! A [`Session`] is the cave with sand pouring into it, a grain per
+//! [`step`](Simulation::step); its snapshots draw the cave.
+
 use std::collections::HashSet;
 use std::cmp::{min, max};
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::render::{Discard, Recorder};
+use aoc_core::simulation::{Observed, Simulation};
+use aoc_core::error::parse_at;
+use aoc_core::geom::{Dir8, Point2};
+use aoc_core::{Answer, Grid, Input, ParseError, Part, Solution};
+use aoc_core::gen::Rng;
 
-type Point = (i32, i32);
+/// A spot in the cave, with x increasing to the right and y going down
+pub type Point = Point2<i32>;
 
 /// Return all rock positions and the maximum y-coordinate  
 /// (x increaes to the right and y increases as we go down)
-fn parse_input(inp: Vec<String>) -> (HashSet<Point>, i32) {
-    let get_coords = |s: &str| -> Point {
-        let tmp: Vec<&str> = s.trim().split(",").collect();
-        (tmp[0].parse::<i32>().unwrap(), tmp[1].parse::<i32>().unwrap())
-    };
+fn parse_input(inp: &Input) -> Result<(HashSet<Point>, i32), ParseError> {
     let mut global_max_y = i32::MIN;
     let mut set = HashSet::new();
-    for line in inp.into_iter() {
+    for (n, line) in inp.numbered_lines() {
+        let get_coords = |s: &str| -> Result<Point, ParseError> {
+            let Some((x, y)) = s.trim().split_once(",") else {
+                return Err(ParseError::at(n, line, s.trim(), "expected `x,y`"));
+            };
+            Ok(Point2::new(parse_at(n, line, x)?, parse_at(n, line, y)?))
+        };
         let segments: Vec<&str> = line.split("->").collect();
-        let (mut xp, mut yp) = get_coords(segments[0]);
-        for i in 1..segments.len() {
-            let (xn, yn) = get_coords(segments[i]);
+        let Point2 { x: mut xp, y: mut yp } = get_coords(segments[0])?;
+        for segment in &segments[1..] {
+            let Point2 { x: xn, y: yn } = get_coords(segment)?;
             if xn != xp && yn != yp {
-                panic!("Unknown case");
+                return Err(ParseError::at(n, line, segment.trim(), "paths only go straight across or down"));
             }
             if xn == xp {
                 let (miny, maxy) = (min(yn, yp), max(yn, yp));
                 for y in miny..maxy+1 {
                     global_max_y = max(y, global_max_y);
-                    set.insert((xn, y));
+                    set.insert(Point2::new(xn, y));
                 }
             }
             if yn == yp {
                 global_max_y = max(yn, global_max_y);
                 let (minx, maxx) = (min(xn, xp), max(xn, xp));
                 for x in minx..maxx+1 {
-                    set.insert((x, yn));
+                    set.insert(Point2::new(x, yn));
                 }
             }
             xp = xn;
             yp = yn;
         }
     }
-    (set, global_max_y)
+    if set.is_empty() {
+        return Err(ParseError::new(1, inp.lines().next().unwrap_or(""), "no rock paths"));
+    }
+    Ok((set, global_max_y))
 }
 
 enum State {
@@ -55,76 +64,92 @@ enum State {
     Stop
 }
 
-struct Session {
+/// Sand pouring into the cave, one grain a step. With `floor` (part 2) there
+/// is a floor two below the lowest rock and the sand stops once it blocks the
+/// source, otherwise once a grain falls past the lowest rock
+pub struct Session {
     rocks: HashSet<Point>,
     sand: HashSet<Point>,
     max_y: i32,
-    start: Point
+    start: Point,
+    floor: bool,
+    done: bool
 }
 
 impl Session {
-    pub fn new(rocks: HashSet<Point>, max_y: i32, start_coord: Point) -> Self {
+    /// `max_y` is the y of the lowest rock, and the sand comes in at `start_coord`
+    pub fn new(rocks: HashSet<Point>, max_y: i32, start_coord: Point, floor: bool) -> Self {
         Session {
-            rocks: rocks,
+            rocks,
             sand: HashSet::new(),
-            max_y: max_y,
-            start: start_coord
+            max_y,
+            start: start_coord,
+            floor,
+            done: false
         }
     }
 
+    /// Straight down, then down and to the left, then down and to the right
     fn get_next_pos(&self, sand_pos: &Point) -> Option<Point> {
-        let (x, y) = *sand_pos;
-        let possibles = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)];
-        for pos in possibles {
-            if !self.sand.contains(&pos) && !self.rocks.contains(&pos) {
-                return Some(pos);
-            }
-        }
-        None
+        let possibles = Dir8::S.fan().map(|d| sand_pos.step(d));
+        possibles.into_iter().find(|&pos| !self.sand.contains(&pos) && !self.rocks.contains(&pos))
     }
 
     fn simulate_drop(&mut self) -> State {
+        // A cave that fills up to the source never spills into the abyss
+        if self.sand.contains(&self.start) {
+            return State::Stop;
+        }
         let mut cur_pos = self.start;
         let mut i = 0;
         loop {
             i += 1;
-            //println!("Iter {}, pos: {:?}", i, cur_pos);
+            aoc_core::trace!("grain falling", step = i, x = cur_pos.x, y = cur_pos.y);
             match self.get_next_pos(&cur_pos) {
                 Some(v) => {
                     cur_pos = v;
-                    if cur_pos.1 > self.max_y {
+                    if cur_pos.y > self.max_y {
+                        aoc_core::debug!("grain fell into the abyss", steps = i, x = cur_pos.x);
                         return State::Stop;
                     }
                 },
                 None => {
                     self.sand.insert(cur_pos);
+                    aoc_core::debug!("grain settled", steps = i, x = cur_pos.x, y = cur_pos.y, grains = self.sand.len());
                     return State::Drop;
                 }
             }
         }
     }
 
+    fn drop_grain(&mut self) -> State {
+        match self.floor {
+            true => self.simulate_drop_2(),
+            false => self.simulate_drop(),
+        }
+    }
+
     fn simulate_drop_2(&mut self) -> State {
         let mut cur_pos = self.start;
         let mut i = 0;
         loop {
             i += 1;
-            //println!("Iter {}, pos: {:?}", i, cur_pos);
+            aoc_core::trace!("grain falling", step = i, x = cur_pos.x, y = cur_pos.y);
             match self.get_next_pos(&cur_pos) {
                 Some(v) => {
-                    if v.1 == self.max_y + 2 {
+                    if v.y == self.max_y + 2 {
                         self.sand.insert(cur_pos);
-                        //println!("Drop_here: {:?}", cur_pos);
+                        aoc_core::debug!("grain settled on the floor", steps = i, x = cur_pos.x, y = cur_pos.y, grains = self.sand.len());
                         return State::Drop;
                     }
                     cur_pos = v;
                 },
                 None => {
                     self.sand.insert(cur_pos);
-                    if cur_pos == (500, 0) {
+                    if cur_pos == self.start {
                         return State::Stop;
                     }
-                    //println!("Drop now: {:?}", cur_pos);
+                    aoc_core::debug!("grain settled", steps = i, x = cur_pos.x, y = cur_pos.y, grains = self.sand.len());
                     return State::Drop;
                 }
             }
@@ -132,27 +157,126 @@ impl Session {
     }
 
 
-    pub fn drop_sand_until_abyss(&mut self, part2: bool) -> usize {
-        let mut iterations = 0;
-        match part2 {
-            true => {
-                loop {
-                    match self.simulate_drop_2() {
-                        State::Drop => iterations += 1,
-                        State::Stop => break,
-                    }
-                }
+    /// The cave from the source down to the floor in part 2, or to just below the lowest rock
+    fn render(&self) -> Grid<char> {
+        let floor = self.max_y + 2;
+        let part2 = self.floor;
+        let (min_x, max_x, bottom) = match part2 {
+            true => (self.start.x - floor, self.start.x + floor, floor),
+            false => (
+                self.rocks.iter().map(|p| p.x).min().unwrap_or(self.start.x) - 1,
+                self.rocks.iter().map(|p| p.x).max().unwrap_or(self.start.x) + 1,
+                self.max_y + 1,
+            ),
+        };
+        let (width, height) = ((max_x - min_x + 1) as usize, (bottom + 1) as usize);
+        Grid::from_fn(width, height, |(r, c)| {
+            let p = Point2::new(min_x + c as i32, r as i32);
+            if self.sand.contains(&p) {
+                'o'
+            } else if p == self.start {
+                '+'
+            } else if self.rocks.contains(&p) || part2 && p.y == floor {
+                '#'
+            } else {
+                '.'
+            }
+        })
+    }
+}
+
+impl Simulation for Session {
+    type Snapshot = Grid<char>;
+
+    /// Drop one grain; false once the sand has stopped
+    fn step(&mut self) -> bool {
+        if self.done {
+            return false;
+        }
+        match (self.floor, self.drop_grain()) {
+            (_, State::Drop) => true,
+            // The grain that finally blocks the source still comes to rest
+            (true, State::Stop) => {
+                self.done = true;
+                true
             },
-            false => {
-                loop {
-                    match self.simulate_drop() {
-                        State::Drop => iterations += 1,
-                        State::Stop => break,
-                    }
-                } 
+            // The grain that falls into the abyss changes nothing
+            (false, State::Stop) => {
+                self.done = true;
+                false
             }
         }
-        iterations
+    }
+
+    /// Grains that came to rest
+    fn steps(&self) -> usize {
+        self.sand.len()
+    }
+
+    fn snapshot(&self) -> Grid<char> {
+        self.render()
+    }
+}
+
+/// 80 rock formations per `size`, buckets and ledges, placed under the source
+/// where the sand will find them; the first one is right below it. The cave
+/// gets deeper with the square root of `size`, like its floor in part 2
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let depth = aoc_core::gen::scaled(160, size, 2) as i64;
+    (0..80 * size).map(|i| {
+        // Spread evenly over the triangle the sand can reach, not crowded at the top
+        let y = 10 + ((depth - 10) as f64 * rng.f64().sqrt()) as i64;
+        let width = rng.range(2..=12);
+        let x = match i {
+            0 => 500 - width / 2,
+            _ => 500 + rng.range(-y..=y),
+        };
+        let points = match rng.below(3) {
+            0 => vec![(x, y), (x + width, y)],
+            1 => {
+                let lip = y - rng.range(0..=2);
+                vec![(x, lip), (x, y), (x + width, y), (x + width, lip)]
+            }
+            _ => {
+                let top = y - rng.range(2..=8.min(y - 1));
+                vec![(x, top), (x, y), (x + width, y), (x + width, top)]
+            }
+        };
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        points.join(" -> ") + "\n"
+    }).collect()
+}
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u8 = 14;
+    /// Rock positions and the lowest rock
+    type Input = (HashSet<Point>, i32);
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        Self::record(input, Part::One, &mut Discard).unwrap()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        Self::record(input, Part::Two, &mut Discard).unwrap()
+    }
+
+    /// Grains that come to rest, with a frame after each one
+    fn record(input: &Self::Input, part: Part, recorder: &mut dyn Recorder) -> Option<Answer> {
+        let (rocks, max_y) = input;
+        let session = Session::new(rocks.clone(), *max_y, Point2::new(500, 0), part == Part::Two);
+        let mut session = Observed::recording(session, recorder);
+        session.run_to_end();
+        Some(session.steps().into())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
 
@@ -162,27 +286,41 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let (inp, max_y) = parse_input(read_input("in.test").unwrap());
-        let mut session = Session::new(inp.clone(), max_y, (500, 0));
-        let part1 = session.drop_sand_until_abyss(false);
-        let mut session2 = Session::new(inp, max_y, (500, 0));
-        let part2 = session2.drop_sand_until_abyss(true);
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2 + 1);
+        aoc_core::expected::check::<Day14>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let (inp, max_y) = parse_input(read_input("in.1").unwrap());
-        let mut session = Session::new(inp.clone(), max_y, (500, 0));
-        let part1 = session.drop_sand_until_abyss(false);
-        let mut session2 = Session::new(inp, max_y, (500, 0));
-        let part2 = session2.drop_sand_until_abyss(true);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2 + 1);
-        println!("Elapsed: {:?}", elapsed);
+        aoc_core::expected::check::<Day14>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day14>("498,4 -> 498,6\n503,4 -> 50x,4\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 10, "50x"));
+        assert_eq!(aoc_core::expected::parse_error::<Day14>("").message, "no rock paths");
+    }
+
+    #[test]
+    fn one_grain_at_a_time() {
+        let (rocks, max_y) = Day14::parse(&Input::from_path("in.test").unwrap()).unwrap();
+        let mut session = Session::new(rocks, max_y, Point2::new(500, 0), false);
+        assert_eq!(session.run_to(5), 5);
+        let drawn = "\
+.......+....
+............
+............
+............
+.....#...##.
+.....#...#..
+...###...#..
+.......o.#..
+.....oooo#..
+.#########..
+............";
+        assert_eq!(session.snapshot().to_string(), drawn);
+        assert_eq!(session.run_to_end(), 19);
+        assert!(!session.step());
+        assert_eq!(session.steps(), 24);
     }
 }
@@ -1,21 +1,29 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 15: Beacon Exclusion Zone.
+//!
+//! Each sensor and the beacon closest to it make a [`Pair`]. A [`Session`]
+//! holds them all and the search box for the distress beacon; a [`Report`]
+//! adds the row part 1 asks about.
+
 use std::collections::HashSet;
-use std::cmp::{min, max};
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::geom::Point2;
+use aoc_core::budget;
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, IntervalSet, ParseError, Solution};
+use aoc_core::gen::Rng;
+
+pub type Point = i32;
 
-type Point = i32;
-type Dist = u32;
-type Coord = (Point, Point);
+/// The row and search box of real inputs, unless `aoc.toml` says otherwise
+const ROW: Point = 2000000;
+const MAX_LIMIT: Point = 4000000;
+pub type Dist = u32;
+/// `(x, y)`
+pub type Coord = (Point, Point);
 
+/// A sensor and the beacon closest to it
 #[derive(Debug, Copy, Clone)]
-struct Pair {
+pub struct Pair {
     sensor: Coord,
     closest_beacon: Coord,
     manhattan_dist: Dist
@@ -31,15 +39,17 @@ impl Pair {
     }
 
     fn manhattan_dist(sen: &Coord, beac: &Coord) -> Dist {
-        ((sen.0 - beac.0).abs() + (sen.1 - beac.1).abs()) as Dist
+        Point2::from(*sen).manhattan(Point2::from(*beac)) as Dist
     }
 
+    /// Manhattan distance from the sensor to its beacon
     pub fn dist(&self) -> Dist {
         self.manhattan_dist
     }
 
+    /// Distance from the sensor to row `y`
     pub fn get_y_dist(&self, y: &Point) -> Dist {
-        (self.sensor.1 - y).abs() as Dist
+        (self.sensor.1 - y).unsigned_abs()
     }
 
     pub fn get_sensor(&self) -> &Coord {
@@ -51,7 +61,9 @@ impl Pair {
     }
 }
 
-struct Session {
+/// The sensors, and the search box from `min_limit` to `max_limit` on both
+/// axes
+pub struct Session {
     pairs: Vec<Pair>,
     min_limit: Point,
     max_limit: Point
@@ -61,139 +73,191 @@ impl Session {
     pub fn new(inp: Vec<Pair>, min_limit: Point, max_limit: Point) -> Self {
         Session {
             pairs: inp,
-            min_limit: min_limit,
-            max_limit: max_limit
+            min_limit,
+            max_limit
         }
     }
 
-    pub fn count_impossibles(&self, y: Point) -> usize {
-        let mut set = HashSet::new();
-        let mut res = 0;
+    /// x-coordinates on row `y` that some sensor is at least as close to as its beacon
+    fn row_coverage(&self, y: &Point) -> IntervalSet<Point> {
+        let mut covered = IntervalSet::new();
         for pair in self.pairs.iter() {
-            let cost = pair.get_y_dist(&y);
-            let (sensor, dist, beacon) = (pair.get_sensor(), pair.dist(), pair.get_closest_beacon());
-            //println!("sensor: {:?}, man_d: {}, cost: {}", sensor, pair.dist(), cost);
-            if cost > pair.dist() {
+            let (sensor, dist) = (pair.get_sensor(), pair.dist());
+            let cost = pair.get_y_dist(y);
+            if cost > dist {
                 continue;
             }
-            let remain = dist - cost;
-            for x in (sensor.0 - remain as Point)..(sensor.0 + remain as Point + 1) {
-                set.insert((x, y));
-            }
-            //let mut add = (2 * remain + 1) as usize;
-            if pair.get_closest_beacon().1  == y {
-                set.remove(beacon);
-            }
-            //println!("Added: {}", add);
+            let remain = (dist - cost) as Point;
+            covered.insert(sensor.0 - remain, sensor.0 + remain);
         }
-        set.len()
+        covered
     }
 
-    /// Given a y-coordinate, return the range of x-coordinates on that row
-    /// reachable from all sensor
-    fn get_distress_helper(&self, y: &Point) -> Vec<Coord> {
-        let mut intervals = vec![];
-        for pair in self.pairs.iter() {
-            let (sensor, dist, beacon) = (pair.get_sensor(), pair.dist(), pair.get_closest_beacon());
-            let cost = pair.get_y_dist(&y);
-            if cost > pair.dist() {
-                continue;
-            }
-            let remain = dist - cost;
-           intervals.push((max(self.min_limit, sensor.0 - remain as Point), min(self.max_limit, sensor.0 + remain as Point)));
-        }
-        let mut merge_intervals = move || -> Vec<Coord> {
-            intervals.sort();
-            let mut stack: Vec<Coord> = vec![];
-            stack.push(intervals[0]);
-            for i in 1..intervals.len() {
-                let mut last = stack.pop().unwrap();
-                let cur = intervals[i];
-                if last.1 >= cur.0 {
-                    if last.1 >= cur.1 {
-                        stack.push(last);
-                        continue;
-                    }
-                    stack.push((last.0, cur.1));
-                    continue
-                }
-                stack.push(last);
-                stack.push(cur);
-            }
-            stack
-        };
-        merge_intervals()
+    /// Positions on row `y` where the beacon cannot be
+    pub fn count_impossibles(&self, y: Point) -> usize {
+        let beacons: HashSet<Point> = self.pairs.iter()
+            .map(|pair| pair.get_closest_beacon())
+            .filter(|beacon| beacon.1 == y)
+            .map(|beacon| beacon.0)
+            .collect();
+        let covered = self.row_coverage(&y);
+        let on_beacon = beacons.into_iter().filter(|&x| covered.contains(x)).count();
+        covered.covered() as usize - on_beacon
+    }
+
+    /// x-coordinates within the search box on row `y` that no sensor covers
+    fn get_distress_helper(&self, y: &Point) -> IntervalSet<Point> {
+        IntervalSet::single(self.min_limit, self.max_limit).difference(&self.row_coverage(y))
     }
 
-    pub fn get_tuning_frequency(&self) -> u64 {
+    /// Tuning frequency of the one position in the search box no sensor
+    /// covers. `None` if no row has a gap, or the budget ran out before one
+    /// was found
+    pub fn get_tuning_frequency(&self) -> Option<u64> {
         let (rangel, rangeu) = (self.min_limit, self.max_limit + 1);
-        let (mut xval, mut yval): (u64, u64) = (0, 0);
+        let mut found = None;
         for y in rangel..rangeu {
-            let intervals = self.get_distress_helper(&y);
-            //if intervals.len() > 1 {
-            //    println!("y: {}, intervals: {:?}", y, intervals);
-            //}
-            if intervals.len() == 1 {
-                continue;
+            if !budget::tick() {
+                break;
+            }
+            let missing = self.get_distress_helper(&y);
+            if !missing.is_empty() {
+                aoc_core::debug!("row not covered", y = y, missing = missing.to_string());
             }
-            for i in 0..intervals.len() - 1 {
-                if intervals[i + 1].0 - intervals[i].1 == 2 {
-                    xval = (intervals[i].1 + 1) as u64;
-                    yval = y as u64;
-                }
+            if missing.covered() == 1 {
+                found = Some((missing.first().unwrap() as u64, y as u64));
             }
         }
-        xval * 4000000 + yval
+        found.map(|(xval, yval)| xval * 4000000 + yval)
     }
 }
 
 /// Return vector of sensor-closest-beacon pairs
-fn parse_input(inp: Vec<String>) -> Vec<Pair> {
-    let parse = |s: &str, remove_last: bool| -> i32 {
-        let new_s = match remove_last {
-            false => s,
-            true => {
-                let mut chars = s.chars();
-                chars.next_back();
-                chars.as_str()
+fn parse_input(inp: &Input) -> Result<Vec<Pair>, ParseError> {
+    inp.numbered_lines().map(|(n, line)| {
+        // `x=2,` or `y=18:` -> 2 or 18
+        let parse = |s: &str, name: &str| -> Result<i32, ParseError> {
+            let s = s.trim_end_matches([',', ':']);
+            match s.strip_prefix(name) {
+                Some(v) => parse_at(n, line, v),
+                None => Err(ParseError::at(n, line, s, format!("expected {}<number>", name))),
             }
         };
-        let arr: Vec<&str> = new_s.split("=").collect();
-        arr[arr.len() - 1].parse::<i32>().unwrap()
-    };
-    inp.into_iter().map(|line| {
         let tmp: Vec<&str> = line.split_whitespace().collect();
-        let sensor = (parse(tmp[2], true), parse(tmp[3], true));
-        let beac = (parse(tmp[8], true), parse(tmp[9], false));
-        Pair::new(sensor, beac)
+        let ["Sensor", "at", sx, sy, "closest", "beacon", "is", "at", bx, by] = tmp[..] else {
+            return Err(ParseError::new(n, line, "expected `Sensor at x=.., y=..: closest beacon is at x=.., y=..`"));
+        };
+        let sensor = (parse(sx, "x=")?, parse(sy, "y=")?);
+        let beac = (parse(bx, "x=")?, parse(by, "y=")?);
+        Ok(Pair::new(sensor, beac))
     }).collect()
 }
 
+/// The sensor report along with the puzzle parameters that go with it: the
+/// row to check for part 1 and the upper bound of the part 2 search box
+pub struct Report {
+    pairs: Vec<Pair>,
+    row: Point,
+    max_limit: Point,
+}
+
+impl Report {
+    pub fn new(pairs: Vec<Pair>, row: Point, max_limit: Point) -> Self {
+        Report { pairs, row, max_limit }
+    }
+
+    /// The sensors, searching from 0 to `max_limit`
+    pub fn session(&self) -> Session {
+        Session::new(self.pairs.clone(), 0, self.max_limit)
+    }
+
+    pub fn row(&self) -> Point {
+        self.row
+    }
+}
+
+/// 35 sensors per `size` around the real search box, which leave exactly one
+/// spot in it uncovered. Four of them are huge and each covers everything on one
+/// side of that spot, along one of the diagonals through it; the rest are drawn
+/// at random and stop just short of it. The box itself does not grow, and is
+/// the one the real inputs use
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    const MAX: i64 = MAX_LIMIT as i64;
+    let (px, py) = (rng.range(1..=MAX - 1), rng.range(1..=MAX - 1));
+    let line = |(sx, sy): (i64, i64), (bx, by): (i64, i64)| {
+        format!("Sensor at x={}, y={}: closest beacon is at x={}, y={}\n", sx, sy, bx, by)
+    };
+    // Along the diagonals, u = x + y and v = x - y, a sensor covers a square.
+    // These reach past the whole box on the other axis, where they are nudged
+    // by one if need be: x and y are whole only if u and v are both odd or both
+    // even.
+    let reach = MAX + 2;
+    let (pu, pv) = (px + py, px - py);
+    let mut lines: Vec<String> = [
+        (pu - 1 - reach, (pu - 1 - reach).rem_euclid(2)),
+        (pu + 1 + reach, (pu + 1 + reach).rem_euclid(2)),
+        (MAX + (pv - 1 - reach - MAX).rem_euclid(2), pv - 1 - reach),
+        (MAX + (pv + 1 + reach - MAX).rem_euclid(2), pv + 1 + reach),
+    ].into_iter().map(|(u, v)| {
+        let sensor = ((u + v) / 2, (u - v) / 2);
+        line(sensor, (sensor.0 + reach, sensor.1))
+    }).collect();
+    while lines.len() < 35 * size {
+        let sensor = (rng.range(-MAX / 4..=MAX * 5 / 4), rng.range(-MAX / 4..=MAX * 5 / 4));
+        let dist = (sensor.0 - px).abs() + (sensor.1 - py).abs();
+        if dist < 2 {
+            continue;
+        }
+        let radius = dist - 1 - rng.range(0..=dist / 3);
+        let dx = rng.range(-radius..=radius);
+        let dy = (radius - dx.abs()) * *rng.pick(&[-1, 1]);
+        lines.push(line(sensor, (sensor.0 + dx, sensor.1 + dy)));
+    }
+    rng.shuffle(&mut lines);
+    lines.concat()
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    const DAY: u8 = 15;
+    type Input = Report;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        let pairs = parse_input(input).map_err(|e| input.locate(e))?;
+        Ok(Report::new(pairs, input.param_or("row", ROW)?, input.param_or("max", MAX_LIMIT)?))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        input.session().count_impossibles(input.row()).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        budget::found(input.session().get_tuning_frequency())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let inp = parse_input(read_input("in.test").unwrap());
-        let session = Session::new(inp, 0, 20);
-        let part1 = session.count_impossibles(10);
-        let part2 = session.get_tuning_frequency();
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day15>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let inp = parse_input(read_input("in.1").unwrap());
-        let session = Session::new(inp, 0, 4000000);
-        let part1 = session.count_impossibles(2000000);
-        let part2 = session.get_tuning_frequency();
-        let elapsed = now.elapsed();
-        println!("part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:?}", elapsed);
+        aoc_core::expected::check::<Day15>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day15>("Sensor at x=2, y=18: closest beacon is at x=-2, y=15\nSensor at x=9, y=1x: closest beacon is at x=10, y=16\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 18, "1x"));
     }
 }
@@ -1,25 +1,36 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 16: Proboscidea Volcanium.
+//!
+//! Valves are numbered in the order the scan mentions them: the [`Graph`]
+//! says where each one's tunnels lead and its [`Mapping`] gives its name and
+//! flow rate. A [`Session`] searches for the most pressure to release.
+
 use std::collections::{HashMap, HashSet};
-use std::cmp::{min, max};
+use std::cmp::max;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::budget;
+use aoc_core::error::parse_at;
+use aoc_core::search;
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-type Graph = HashMap<usize, Vec<usize>>;
+/// The valves each valve's tunnels lead to
+pub type Graph = HashMap<usize, Vec<usize>>;
 
 /// (current_tunnel, cur_time, flow_rate achieved so far)
 type State = (usize, usize, usize);
 
 /// (current_tunnel, current_elephant_tunnel, cur_time, flow_rate achieved so far)
+#[cfg(test)]
 type DoubleState = (usize, usize, usize, usize);
 
+/// (one tunnel, the other, cur_time, valves opened as bits): what is left to
+/// gain from a [`DoubleState`] depends on nothing else
+#[cfg(test)]
+type DoubleKey = (usize, usize, usize, u64);
+
+/// A valve's name and flow rate
 #[derive(Debug, Clone)]
-struct Mapping {
+pub struct Mapping {
     pub tunnel: String,
     pub flow_rate: usize
 }
@@ -33,8 +44,9 @@ impl Mapping {
     }
 }
 
-/// Graphs and map of tunnel to integer id
-fn parse_input(inp: Vec<String>) -> (Graph, Vec<Mapping>) {
+/// Graphs and map of tunnel to integer id. At most 64 valves may have a flow
+/// rate, so that a set of them fits in a `u64`
+fn parse_input(inp: &Input) -> Result<(Graph, Vec<Mapping>), ParseError> {
     let mut i: usize = 0;
     let mut mappings = HashMap::new();
     let mut graph = HashMap::new();
@@ -46,34 +58,51 @@ fn parse_input(inp: Vec<String>) -> (Graph, Vec<Mapping>) {
             tmp
         })
     };
-    for line in inp.into_iter() {
+    // First place each tunnel is led to, in case it is never described
+    let mut referenced = HashMap::new();
+    let mut working = 0;
+    for (n, line) in inp.numbered_lines() {
         let space_sep: Vec<&str> = line.split_whitespace().collect();
+        let rate = match space_sep[..] {
+            ["Valve", _, "has", "flow", rate, _, _, _, _, _, ..] => rate,
+            _ => return Err(ParseError::new(n, line, "expected `Valve XX has flow rate=N; tunnels lead to valves ...`")),
+        };
         let cur = space_sep[1];
         let cur_id = add_mapping(cur.to_string());
-        let flrt_str: Vec<&str> = space_sep[4].split(";").next().unwrap().split("=").collect();
-        let flrt = flrt_str[1].parse::<usize>().unwrap();
+        let Some(flrt_str) = rate.strip_prefix("rate=").and_then(|r| r.strip_suffix(";")) else {
+            return Err(ParseError::at(n, line, rate, "expected `rate=N;`"));
+        };
+        let flrt = parse_at::<usize>(n, line, flrt_str)?;
+        if flrt > 0 {
+            working += 1;
+            if working > 64 {
+                return Err(ParseError::at(n, line, flrt_str, "only 64 valves can have a flow rate"));
+            }
+        }
         flow_rates_map.insert(cur_id, flrt);
         let cons = space_sep[9..].to_vec();
         let mut tunnels = vec![];
         for val in cons {
-            let val_new = match val.len() == 3 {
-                true => val.split(",").next().unwrap(),
-                false => val
-            };
+            let val_new = val.trim_end_matches(",");
             let ind = add_mapping(val_new.to_string());
+            referenced.entry(ind).or_insert((n, line, val_new));
             tunnels.push(ind);
         }
         graph.insert(cur_id, tunnels);
     }
     let mut reverse_mappings = vec![Mapping::new("".to_string(), 0); mappings.len()];
     for (k, v) in mappings.into_iter() {
-        let mapping = Mapping::new(k.to_string(), *flow_rates_map.get(&v).unwrap());
-        reverse_mappings[v] = mapping;
+        let Some(&rate) = flow_rates_map.get(&v) else {
+            let (n, line, val) = referenced[&v];
+            return Err(ParseError::at(n, line, val, "no such valve"));
+        };
+        reverse_mappings[v] = Mapping::new(k.to_string(), rate);
     }
-    (graph, reverse_mappings)
+    Ok((graph, reverse_mappings))
 }
 
-struct Session {
+/// The volcano with `time` minutes to go, starting at valve `AA`
+pub struct Session {
     graph: Graph,
     mappings: Vec<Mapping>,
     time: usize,
@@ -96,7 +125,12 @@ impl Session {
         if visited.len() == self.mappings.len() {
             return 0;
         }
+        // Out of budget: settle for what this path has let out so far
+        if !budget::tick() {
+            return state.2;
+        }
         if memo.contains_key(&state) {
+            aoc_core::trace!("memo hit", state = ?state);
             return *memo.get(&state).unwrap();
         }
         let (cur_node, cur_time, cur_flow) = state;
@@ -117,7 +151,7 @@ impl Session {
         if !visited.contains(&cur_node) {
             visited.insert(cur_node);
             let new_flow_rate = cur_flow + (self.time - (cur_time + 1)) * self.mappings[cur_node].flow_rate;
-            //println!("state: {:?}, flow_rate {}, Added flow rate: {}", state, self.mappings[cur_node].flow_rate, new_flow_rate);
+            aoc_core::trace!("valve opened", state = ?state, flow_rate = self.mappings[cur_node].flow_rate, total = new_flow_rate);
             max_val = max(max_val, self.backtrack((cur_node, cur_time + 1, new_flow_rate), visited, memo));
             visited.remove(&cur_node);
         }
@@ -125,7 +159,6 @@ impl Session {
         for node in next_nodes {
             max_val = max(max_val, self.backtrack((node, cur_time + 1, cur_flow), visited, memo));
         }
-        //visited.remove(&cur_node);
         if max_val > self.cur_max {
             self.cur_max = cur_flow;
             self.visited_nodes = visited.clone();
@@ -134,34 +167,32 @@ impl Session {
         max_val
     }
 
-    // Works but is too slow for part 2. This will exhaustively check all combinations
-    fn backtrack_doublestate(&self, state: DoubleState, visited: &mut HashSet<usize>, memo: &mut HashMap<DoubleState, usize>) -> usize {
-        //println!("State: {:?}, visited: {:?}", state, visited);
-        if visited.len() == self.mappings.len() {
-            return 0;
-        }
-        if memo.contains_key(&state) {
-            return *memo.get(&state).unwrap();
+    // Works but is too slow for part 2. This will exhaustively check all combinations,
+    // unless the budget runs out first
+    #[cfg(test)]
+    fn backtrack_doublestate(&self, state: DoubleState, visited: &mut HashSet<usize>, memo: &mut HashMap<DoubleKey, usize>) -> usize {
+        aoc_core::trace!("double state", state = ?state, visited = visited.len());
+        // Every valve is open, so nothing changes from here on
+        if visited.len() == self.mappings.len() || !budget::tick() {
+            return state.3;
         }
         let (cn1, cn2, cur_time, cur_flow) = state;
+        let key = (cn1.min(cn2), cn1.max(cn2), cur_time, visited.iter().fold(0, |bits, v| bits | 1 << v));
+        if let Some(gain) = memo.get(&key) {
+            aoc_core::trace!("memo hit", state = ?state);
+            return cur_flow + gain;
+        }
         if cur_time >= self.time {
             if cur_time == self.time {
-                memo.insert(state, cur_flow);
+                memo.insert(key, 0);
                 return cur_flow;
             }
-            memo.insert(state, 0);
             return 0;
         }
         let mut max_val = cur_flow;
         let (nn1_nodes, nn2_nodes) = (self.graph.get(&cn1).unwrap(), self.graph.get(&cn2).unwrap());
         let nf1 = (self.time - (cur_time + 1)) * self.mappings[cn1].flow_rate;
         let nf2 = (self.time - (cur_time + 1)) * self.mappings[cn2].flow_rate;
-        // Option-0: If both elephant and man in same room,
-        // room is visited and only one tunnel from here, then illegal state
-        if cn1 == cn2 && visited.contains(&cn1) && nn1_nodes.len() == 1 {
-            memo.insert(state, 0);
-            return 0;
-        }
         // Option-1: elephant and man in different rooms, and both rooms unvisited
         // So, both open
         if !visited.contains(&cn1) && !visited.contains(&cn2) && cn1 != cn2 {
@@ -191,21 +222,20 @@ impl Session {
             }
             visited.remove(&cn2);
         }
-        // Option-3: No one opens
-        for nn1 in nn1_nodes.into_iter() {
-            for nn2 in nn2_nodes.into_iter() {
-                if cn1 == cn2 && nn1 == nn2 {
-                    continue;
-                }
+        // Option-3: No one opens. Both may well take the same tunnel, to open
+        // valves either side of the next room
+        for nn1 in nn1_nodes.iter() {
+            for nn2 in nn2_nodes.iter() {
                 max_val = max(max_val, self.backtrack_doublestate((*nn1, *nn2, cur_time + 1, cur_flow), visited, memo));
             }
         }
-        memo.insert(state, max_val);
+        memo.insert(key, max_val - cur_flow);
         max_val
     }
 
+    /// Most pressure to release alone
     pub fn get_max_pressure(&mut self) -> usize {
-        /// Map of (tun_id, cur_time, cur_flow) -> flow_rate
+        // Map of (tun_id, cur_time, cur_flow) -> flow_rate
         let mut memo: HashMap<(usize, usize, usize), usize> = HashMap::new();
         let mut visited: HashSet<usize> = HashSet::new();
         let mut aa_node = 0;
@@ -218,79 +248,213 @@ impl Session {
         self.backtrack((aa_node, 0, 0), &mut visited, &mut memo)
     }
 
-    /// Maha-Bongu
-    /// So, this works only for the actual input and not the example
-    /// Run the backtracking once with N=26 (set cur_time = 4).
-    /// Then, for al the visited valves, set flow_rate to 0, then rn backtracking again
-    /// Assumes the two processes are independent/linear. 
-    /// backtrack_doublestate is correct and will work, but will take hours
-    /// not to mention I don't think my machine is powerful enough or enough RAM.
-    /// Right way -> do a DFS for man -> compute max_val and visited. 
-    /// Followed immmeditately by DFS for elephant with a complementary set of nodes
-    /// max of sum of these two is your memo table storage.
-    pub fn get_max_pressure_with_elephant(&mut self) -> usize {
-        let mut memo: HashMap<State, usize> = HashMap::new();
-        let mut visited: HashSet<usize> = HashSet::new();
-        let mut aa_node = 0;
-        for (i, m) in self.mappings.iter().enumerate() {
-            if &m.tunnel == "AA" {
-                aa_node = i;
+    /// Most pressure to release with an elephant, after 4 minutes spent
+    /// teaching it. The two of us open different valves, so this finds the
+    /// most one of us can release opening each set of valves, and adds up the
+    /// best two sets that have no valve in common
+    pub fn get_max_pressure_with_elephant(&self) -> usize {
+        let aa_node = self.mappings.iter().position(|m| m.tunnel == "AA").unwrap_or(0);
+        // Only valves with a flow rate are worth walking to
+        let working: Vec<usize> = (0..self.mappings.len()).filter(|&v| self.mappings[v].flow_rate > 0).collect();
+        let distances: HashMap<usize, HashMap<usize, usize>> = working.iter().chain([&aa_node]).map(|&from| {
+            let found = search::bfs([from], |v| self.graph.get(v).cloned().unwrap_or_default(), |_| false);
+            (from, found.distances().clone())
+        }).collect();
+        let mut best = HashMap::new();
+        self.best_per_set((aa_node, self.time - 4, 0, 0), &working, &distances, &mut best);
+        aoc_core::debug!("sets opened", sets = best.len());
+        let mut sets: Vec<(u64, usize)> = best.into_iter().collect();
+        sets.sort_by_key(|&(_, pressure)| std::cmp::Reverse(pressure));
+        let mut max_val = 0;
+        for (i, &(mine, pressure)) in sets.iter().enumerate() {
+            // Sorted, so no pair from here on can do better
+            if 2 * pressure <= max_val {
                 break;
             }
+            if let Some(&(_, elephant)) = sets[i..].iter().find(|(theirs, _)| mine & theirs == 0) {
+                max_val = max(max_val, pressure + elephant);
+            }
         }
-        let val1 = self.backtrack((aa_node, 4, 0), &mut visited, &mut memo);
-        println!("Val1: {}, visited: {:?}", val1, self.visited_nodes);
-        for i in self.visited_nodes.iter() {
-            self.mappings[*i].flow_rate = 0;
+        max_val
+    }
+
+    /// Record in `best` the most pressure released for every set of
+    /// `working` valves (bit `i` for `working[i]`) that can be opened from
+    /// `(valve, time_left, opened, released)`, walking straight from one
+    /// valve to open to the next
+    fn best_per_set(&self, state: (usize, usize, u64, usize), working: &[usize], distances: &HashMap<usize, HashMap<usize, usize>>, best: &mut HashMap<u64, usize>) {
+        let (valve, time_left, opened, released) = state;
+        let entry = best.entry(opened).or_insert(0);
+        *entry = max(*entry, released);
+        // Out of budget: keep the sets found so far
+        if !budget::tick() {
+            return;
+        }
+        for (i, &next) in working.iter().enumerate() {
+            if opened & (1 << i) != 0 {
+                continue;
+            }
+            match distances[&valve].get(&next) {
+                Some(&d) if d < time_left => {
+                    let left = time_left - d - 1;
+                    let flow = left * self.mappings[next].flow_rate;
+                    self.best_per_set((next, left, opened | (1 << i), released + flow), working, distances, best);
+                }
+                _ => (),
+            }
         }
-        //visited = self.visited_nodes.clone();
-        visited = HashSet::new();
-        self.visited_nodes = HashSet::new();
-        self.cur_max = 0;
-        memo = HashMap::new();
-        let val2 = self.backtrack((aa_node, 4, 0), &mut visited, &mut memo);
-        //println!("Mappings: {:?}", self.mappings);
-        println!("Val2: {}, visited: {:?}", val2, self.visited_nodes);
-        val1 + val2
     }
 
+    /// Valves opened on the best path the last search found
     pub fn get_visited(&self) -> HashSet<usize> {
         self.visited_nodes.clone()
     }
+}
 
-    pub fn print_mappings(&self) {
-        for (i, val) in self.mappings.iter().enumerate() {
-            let nodes = self.graph.get(&i).unwrap();
-            let actuals: Vec<String> = nodes.into_iter().map(|v| self.mappings[*v].tunnel.to_owned()).collect();
-            println!("Node id: {}, tunnel: {:?}, leads_to: {:?}", i, val.tunnel, actuals);
+/// 55 valves per `size`, up to the 676 two-letter names there are, joined by
+/// winding tunnels with a few shortcuts. Only 15 valves are worth opening
+/// whatever the size, as the search grows exponentially with them
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let count = (55 * size).min(26 * 26);
+    let mut names: Vec<String> = ('A'..='Z').flat_map(|a| ('A'..='Z').map(move |b| format!("{}{}", a, b))).filter(|n| n != "AA").collect();
+    rng.shuffle(&mut names);
+    names.truncate(count - 1);
+    names.insert(0, "AA".to_string());
+    let mut tunnels: Vec<Vec<usize>> = vec![vec![]; count];
+    let mut connect = |a: usize, b: usize| {
+        if a != b && !tunnels[a].contains(&b) {
+            tunnels[a].push(b);
+            tunnels[b].push(a);
+        }
+    };
+    for i in 1..count {
+        connect(i, i - 1 - rng.below(i.min(3)));
+    }
+    for _ in 0..count / 6 {
+        connect(rng.below(count), rng.below(count));
+    }
+    let mut useful: Vec<usize> = (1..count).collect();
+    rng.shuffle(&mut useful);
+    let mut rates = vec![0; count];
+    for &i in useful.iter().take(15) {
+        rates[i] = rng.range(3..=25);
+    }
+    let mut order: Vec<usize> = (0..count).collect();
+    rng.shuffle(&mut order);
+    order.iter().map(|&i| {
+        let mut leads: Vec<&str> = tunnels[i].iter().map(|&t| names[t].as_str()).collect();
+        rng.shuffle(&mut leads);
+        match leads.len() {
+            1 => format!("Valve {} has flow rate={}; tunnel leads to valve {}\n", names[i], rates[i], leads[0]),
+            _ => format!("Valve {} has flow rate={}; tunnels lead to valves {}\n", names[i], rates[i], leads.join(", ")),
         }
+    }).collect()
+}
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u8 = 16;
+    type Input = (Graph, Vec<Mapping>);
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        let (graph, mappings) = input;
+        budget::answer(Session::new(graph.clone(), mappings.clone(), 30).get_max_pressure())
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let (graph, mappings) = input;
+        budget::answer(Session::new(graph.clone(), mappings.clone(), 30).get_max_pressure_with_elephant())
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_core::budget::Budget;
 
     #[test]
     fn it_works() {
-        let (graph, mappings) = parse_input(read_input("in.test").unwrap());
-        let mut session = Session::new(graph, mappings, 30);
-        let part1 = session.get_max_pressure();
-        // Doesn't work, use the backtrack_doublestate here
-        let part2 = session.get_max_pressure_with_elephant();
-        //session.print_mappings();
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day16>("in.test");
     }
 
     #[test]
     fn actual() {
-        let (graph, mappings) = parse_input(read_input("in.1").unwrap());
-        let mut session = Session::new(graph, mappings, 30);
-        let part1 = session.get_max_pressure();
-        let part2 = session.get_max_pressure_with_elephant();
-        //session.print_mappings();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
+        aoc_core::expected::check::<Day16>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day16>("Valve AA has flow rate=0; tunnels lead to valves BB\nValve BB has flow rate=13; tunnel leads to valve CC\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 50, "CC"));
+    }
+
+    /// `scale` valves from AA on, each joined to one of the three before it
+    /// and a few more tunnels besides
+    fn small_cave(rng: &mut Rng, scale: usize) -> String {
+        let count = scale + 1;
+        let name = |i: usize| format!("{0}{0}", (b'A' + i as u8) as char);
+        let mut tunnels: Vec<Vec<usize>> = vec![vec![]; count];
+        let mut connect = |a: usize, b: usize| {
+            if a != b && !tunnels[a].contains(&b) {
+                tunnels[a].push(b);
+                tunnels[b].push(a);
+            }
+        };
+        for i in 1..count {
+            connect(i, i - 1 - rng.below(i.min(3)));
+        }
+        for _ in 0..count / 3 {
+            connect(rng.below(count), rng.below(count));
+        }
+        (0..count).map(|i| {
+            let rate = if i == 0 || rng.chance(0.3) { 0 } else { rng.range(1..=25) };
+            let leads: Vec<String> = tunnels[i].iter().map(|&t| name(t)).collect();
+            format!("Valve {} has flow rate={}; tunnels lead to valves {}\n", name(i), rate, leads.join(", "))
+        }).collect()
+    }
+
+    /// Both of us moving at once, searched in full
+    fn exhaustive(graph: &Graph, mappings: &[Mapping]) -> usize {
+        let session = Session::new(graph.clone(), mappings.to_vec(), 30);
+        let aa = mappings.iter().position(|m| m.tunnel == "AA").unwrap();
+        session.backtrack_doublestate((aa, aa, 4, 0), &mut HashSet::new(), &mut HashMap::new())
+    }
+
+    #[test]
+    fn elephant_agrees_with_exhaustive() {
+        let found = aoc_core::differential::find(
+            0..40,
+            1..=6,
+            small_cave,
+            |input| parse_input(input).ok(),
+            |(graph, mappings)| exhaustive(graph, mappings),
+            |(graph, mappings)| Session::new(graph.clone(), mappings.clone(), 30).get_max_pressure_with_elephant(),
+        );
+        assert!(found.is_none(), "{}", found.unwrap());
+    }
+
+    #[test]
+    fn exhaustive_example() {
+        let (graph, mappings) = parse_input(&Input::from_path("in.test").unwrap()).unwrap();
+        assert_eq!(exhaustive(&graph, &mappings), 1707);
+    }
+
+    #[test]
+    fn exhaustive_gives_up() {
+        let (graph, mappings) = parse_input(&Input::from_path("in.test").unwrap()).unwrap();
+        let best = Budget::unlimited().with_steps(10_000).run(|| budget::answer(exhaustive(&graph, &mappings)));
+        match best {
+            Answer::TimedOut(Some(best)) => assert!(matches!(*best, Answer::Number(n) if n < 1707), "{}", best),
+            other => panic!("expected to time out, got {}", other),
+        }
     }
 }
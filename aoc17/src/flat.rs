@@ -1,4 +1,6 @@
-use crate::rock::{Point, Rock};
+use aoc_core::geom::Point2;
+
+use crate::rock::{points, Point, Rock};
 
 /// Row and Col here is the coordinate of the left-most point
 #[derive(Debug)]
@@ -20,12 +22,12 @@ impl Rock for Flat {
     }
 
     fn get_right_endpoints(&self) -> Vec<Point> {
-        vec![(self.left.0 + 3, self.left.1)]
+        vec![Point2::new(self.left.x + 3, self.left.y)]
     }
 
     fn get_bottom_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.left;
-        vec![(x, y), (x + 1, y), (x + 2, y), (x + 3, y)]
+        let Point2 { x, y } = self.left;
+        points(&[(x, y), (x + 1, y), (x + 2, y), (x + 3, y)])
     }
 
     fn get_top_endpoints(&self) -> Vec<Point> {
@@ -41,15 +43,15 @@ impl Rock for Flat {
     }
 
     fn move_left(&mut self) {
-        self.left.0 -= 1;
+        self.left.x -= 1;
     }
 
     fn move_right(&mut self) {
-        self.left.0 += 1;
+        self.left.x += 1;
     }
 
     fn move_down(&mut self) {
-        self.left.1 -= 1;
+        self.left.y -= 1;
     }
 
     fn get_pivot(&self) -> Point {
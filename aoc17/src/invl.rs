@@ -1,4 +1,6 @@
-use crate::rock::{Point, Rock};
+use aoc_core::geom::Point2;
+
+use crate::rock::{points, Point, Rock};
 
 /// Row and Col here is the coordinate of the left-most point of inverted L -> _|
 #[derive(Debug)]
@@ -17,45 +19,45 @@ impl InvL {
 impl Rock for InvL {
 
     fn get_left_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.left;
-        vec![(x, y), (x + 2, y + 1), (x + 2, y + 2)]
+        let Point2 { x, y } = self.left;
+        points(&[(x, y), (x + 2, y + 1), (x + 2, y + 2)])
     }
 
     fn get_right_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.left;
-        vec![(x + 2, y), (x + 2, y + 1), (x + 2, y + 2)]
+        let Point2 { x, y } = self.left;
+        points(&[(x + 2, y), (x + 2, y + 1), (x + 2, y + 2)])
     }
 
     fn get_bottom_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.left;
-        vec![(x, y), (x + 1, y), (x + 2, y)]
+        let Point2 { x, y } = self.left;
+        points(&[(x, y), (x + 1, y), (x + 2, y)])
     }
 
     fn get_top_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.left;
-        vec![(x, y), (x + 1, y), (x + 2, y + 2)]
+        let Point2 { x, y } = self.left;
+        points(&[(x, y), (x + 1, y), (x + 2, y + 2)])
     }
 
     fn get_highest_point(&self) -> Point {
-        let (x, y) = self.left;
-        (x + 2, y + 2)
+        let Point2 { x, y } = self.left;
+        Point2::new(x + 2, y + 2)
     }
 
     fn get_all_points(&self) -> Vec<Point> {
-        let (x, y) = self.left;
-        vec![(x, y), (x + 1, y), (x + 2, y), (x + 2, y + 1), (x + 2, y + 2)]
+        let Point2 { x, y } = self.left;
+        points(&[(x, y), (x + 1, y), (x + 2, y), (x + 2, y + 1), (x + 2, y + 2)])
     }
 
     fn move_left(&mut self) {
-        self.left.0 -= 1;
+        self.left.x -= 1;
     }
 
     fn move_right(&mut self) {
-        self.left.0 += 1;
+        self.left.x += 1;
     }
 
     fn move_down(&mut self) {
-        self.left.1 -= 1;
+        self.left.y -= 1;
     }
 
     fn get_pivot(&self) -> Point {
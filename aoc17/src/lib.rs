@@ -1,11 +1,20 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::{HashMap, HashSet};
+//! Day 17: Pyroclastic Flow.
+//!
+//! A [`Session`] drops the five [`Rock`] shapes into the chamber, pushed by
+//! the [`Jet`]s, a rock per [`step`](Simulation::step). [`tower_height`]
+//! finds the height after any number of rocks without dropping them all.
+
+use std::collections::HashSet;
 use std::cmp::{min, max};
-use std::iter::Cycle;
 
-use crate::rock::{Rock, Point};
+use aoc_core::cycle;
+use aoc_core::geom::Point2;
+use aoc_core::render::{Discard, Recorder};
+use aoc_core::simulation::{Observed, Simulation};
+use aoc_core::{Answer, Grid, Input, ParseError, Part, Solution};
+use aoc_core::gen::Rng;
+
+pub use crate::rock::{Rock, Point};
 
 pub mod rock;
 pub mod flat;
@@ -20,32 +29,26 @@ use square::Square;
 use invl::InvL;
 use plus::Plus;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
-
+/// A push of hot gas
 #[derive(Debug, Clone)]
-enum Jet {
+pub enum Jet {
     Left,
     Right
 }
 
-/// x-coordinate and distance travelled along y-coordinate
-/// from initial position of rock to final positon
-/// Measured using the pivot (whatever point is stored in the structure)
-type FinalPos = (usize, usize);
-
-/// Map of rock to set of FinalPos for each 
-/// Will be used to check for cycles
-type History = HashMap<usize, Vec<FinalPos>>;
-
-fn parse_input(inp: Vec<String>) -> Vec<Jet> {
-    inp[0].chars().map(|c| match c {
-        '<' => Jet::Left,
-        '>' => Jet::Right,
-        _ => panic!("Unknown char")
+fn parse_input(inp: &Input) -> Result<Vec<Jet>, ParseError> {
+    let mut lines = inp.as_str().trim_end().lines();
+    let line = lines.next().unwrap_or("");
+    if line.is_empty() {
+        return Err(ParseError::new(1, line, "expected a line of jets"));
+    }
+    if let Some(extra) = lines.next() {
+        return Err(ParseError::new(2, extra, "expected a single line of jets"));
+    }
+    line.char_indices().map(|(i, c)| match c {
+        '<' => Ok(Jet::Left),
+        '>' => Ok(Jet::Right),
+        _ => Err(ParseError::at(1, line, &line[i..i + c.len_utf8()], "expected < or >"))
     }).collect()
 }
 
@@ -58,60 +61,76 @@ pub fn spawn(index: usize, cur_high_point: Option<usize>) -> Box<dyn Rock> {
         Some(v) => v + 4
     };
     match index % 5 {
-        0 => Box::new(Flat::new((2, high_point))),
-        1 => Box::new(Plus::new((3, high_point + 1))),
-        2 => Box::new(InvL::new((2, high_point))),
-        3 => Box::new(Straight::new((2, high_point))),
-        4 => Box::new(Square::new((2, high_point))),
+        0 => Box::new(Flat::new(Point2::new(2, high_point))),
+        1 => Box::new(Plus::new(Point2::new(3, high_point + 1))),
+        2 => Box::new(InvL::new(Point2::new(2, high_point))),
+        3 => Box::new(Straight::new(Point2::new(2, high_point))),
+        4 => Box::new(Square::new(Point2::new(2, high_point))),
         _ => panic!("Not possible"),
     }
 }
 
-struct Session<I: Iterator<Item = Jet>> {
-    jet_stream: I,
+/// Rows below the top of a column that still count towards the fingerprint
+const PROFILE_DEPTH: usize = 64;
+
+/// Next rock, next jet, and how far below the highest rock the top of each column is
+type Fingerprint = (usize, usize, [usize; 7]);
+
+/// Rows of the tower drawn in each frame, counting down from where the next rock spawns
+const VIEW_ROWS: usize = 40;
+
+/// Rocks falling into the chamber, one a step
+pub struct Session {
+    jets: Vec<Jet>,
+    jet_index: usize,
     index: usize,
-    history: History,
-    max_height_history: Vec<usize>,
     rocks: HashSet<Point>,
-    max_height: Option<usize>
+    max_height: Option<usize>,
+    column_tops: [Option<usize>; 7]
 }
 
-impl<I: Iterator<Item = Jet>> Session<I> {
-    pub fn new(stream: I) -> Self {
+impl Session {
+    pub fn new(jets: Vec<Jet>) -> Self {
         Session {
             index: 0,
             rocks: HashSet::new(),
             max_height: None,
-            jet_stream: stream,
-            history: HashMap::new(),
-            max_height_history: Vec::new()
+            jets,
+            jet_index: 0,
+            column_tops: [None; 7]
         }
     }
 
-    fn corner_check(&self, jet: &Jet, rock: &Box<dyn Rock>) -> bool {
-        match jet {
-            &Jet::Left => {
+    fn next_jet(&mut self) -> Jet {
+        let jet = self.jets[self.jet_index].clone();
+        self.jet_index = (self.jet_index + 1) % self.jets.len();
+        jet
+    }
+
+    fn corner_check(&self, jet: &Jet, rock: &dyn Rock) -> bool {
+        match *jet {
+            Jet::Left => {
                 let le = rock.get_left_endpoints();
                 for p in &le {
-                    if p.0 == 0 {
+                    if p.x == 0 {
                         return false;
                     }
                 }
                 for p in &le {
-                    if self.rocks.contains(&(p.0 - 1, p.1)) {
+                    if self.rocks.contains(&Point2::new(p.x - 1, p.y)) {
                         return false;
                     }
                 }
             },
-            &Jet::Right => {
+            Jet::Right => {
                 let re = rock.get_right_endpoints();
                 for p in &re {
-                    if p.0 == 6 {
+                    if p.x == 6 {
                         return false;
                     }
                 }
                 for p in &re {
-                    if self.rocks.contains(&(p.0 + 1, p.1)) {
+                    if self.rocks.contains(&Point2::new(p.x + 1, p.y)) {
                         return false;
                     }
                 }
@@ -120,15 +139,15 @@ impl<I: Iterator<Item = Jet>> Session<I> {
         true
     }
 
-    fn bottom_check(&self, rock: &Box<dyn Rock>) -> bool {
+    fn bottom_check(&self, rock: &dyn Rock) -> bool {
         let be = rock.get_bottom_endpoints();
         for p in &be {
-            if p.1 == 0 {
+            if p.y == 0 {
                 return false;
             }
         }
         for p in &be {
-            if self.rocks.contains(&(p.0, p.1 - 1)) {
+            if self.rocks.contains(&Point2::new(p.x, p.y - 1)) {
                 return false;
             }
         }
@@ -137,32 +156,28 @@ impl<I: Iterator<Item = Jet>> Session<I> {
 
     fn iteration(&mut self) {
         let mut rock = spawn(self.index, self.max_height);
-        let init_pivot = rock.get_pivot();
-        //println!("Index: {}, Spawned rock: {:?}", self.index, rock);
+        aoc_core::trace!("rock spawned", index = self.index, rock = ?rock);
         loop {
-            let jet = self.jet_stream.next().unwrap();
-            if self.corner_check(&jet, &rock) {
+            let jet = self.next_jet();
+            aoc_core::trace!("jet", jet = ?jet, rock = ?rock);
+            if self.corner_check(&jet, rock.as_ref()) {
                 match jet {
                     Jet::Left => {
                         rock.move_left();
-                        //println!("Moved Left: {:?}", rock);
                     },
                     Jet::Right => {
                         rock.move_right();
-                        //println!("Moved right: {:?}", rock);
                     },
                 };
             }
-            match self.bottom_check(&rock) {
+            match self.bottom_check(rock.as_ref()) {
                 true => {
                     rock.move_down();
-                    //println!("Moved down: {:?}", rock);
                 },
                 false => break,
             };
         }
-        let cur_pivot = rock.get_pivot();
-        let v = rock.get_highest_point().1;
+        let v = rock.get_highest_point().y;
         if self.max_height.is_none() {
             self.max_height = Some(v);
         } else {
@@ -172,50 +187,124 @@ impl<I: Iterator<Item = Jet>> Session<I> {
             }
         }
         for p in rock.get_all_points() {
+            self.column_tops[p.x] = max(self.column_tops[p.x], Some(p.y));
             self.rocks.insert(p);
         }
-        self.history.entry(self.index % 5)
-        .and_modify(|val1| {
-            let ins_val = (cur_pivot.0, init_pivot.1 - cur_pivot.1);
-            let pos = val1.iter().position(|&q| q == ins_val);
-            if let Some(x) = pos {
-                if self.index % 5 == 3 {
-                    //println!("Cur Index: {}, Act Index: {} Final Pos: {:?}, Height: {}",self.index, x, ins_val, self.max_height.unwrap());
-                }
+        aoc_core::trace!("rock landed", index = self.index, rock = ?rock, height = self.max_height.unwrap() + 1);
+        self.index += 1;
+    }
+
+    /// The top of the chamber as the puzzle draws it, with the floor once it is in view
+    pub fn render(&self) -> Grid<char> {
+        let top = self.height() + 3;
+        let rows = min(top, VIEW_ROWS);
+        let with_floor = rows == top;
+        Grid::from_fn(9, rows + with_floor as usize, |(r, c)| {
+            if r == rows {
+                return if c == 0 || c == 8 { '+' } else { '-' };
+            }
+            let y = top - 1 - r;
+            match c {
+                0 | 8 => '|',
+                _ if self.rocks.contains(&Point2::new(c - 1, y)) => '#',
+                _ => '.',
             }
-            val1.push(ins_val);
         })
-        .or_insert(Vec::new());
-        self.max_height_history.push(self.max_height.unwrap());
-        //println!("Index: {} Final Rock Pos: {:?}", self.index, rock);
-        self.index += 1;
     }
 
-    pub fn run_simulation(&mut self, upto: usize) -> usize {
-        while self.index < upto {
-            self.iteration();
-        }
-        
-        for k in 1760..1761 {
-            let mut flag = true;
-            let mut height_diff = 0;
-            for j in 1..self.max_height_history.len() - 2 * k {
-                if self.max_height_history[j + k] - self.max_height_history[j] == self.max_height_history[j + 2 * k] - self.max_height_history[j + k] {
-                    height_diff = self.max_height_history[j + k] - self.max_height_history[j];
-                    let hd_part = self.max_height_history[j + 1476] - self.max_height_history[j];
-                    let hd_part2 = self.max_height_history[j + 1738] - self.max_height_history[j];
-                    let hd_part3 = self.max_height_history[j + 34] - self.max_height_history[j];
-                    let hd_part4 = self.max_height_history[j + 35] - self.max_height_history[j];
-                    let hd_part5 = self.max_height_history[j + 36] - self.max_height_history[j];
-                    println!("j: {}, height_diff: {}", j, height_diff);
-                    println!("hd_part: {}, hd_part2: {}", hd_part, hd_part2);
-                    println!("hd_part3: {}, hd_part4: {}, hd_part5: {}", hd_part3, hd_part4, hd_part5);
-                }
+    /// Rows the tower is tall
+    pub fn height(&self) -> usize {
+        self.max_height.map_or(0, |h| h + 1)
+    }
+
+    /// Two states with the same fingerprint drop the same rocks the same way from then on,
+    /// assuming nothing falls deeper than `PROFILE_DEPTH` below the top
+    fn fingerprint(&self) -> Fingerprint {
+        let height = self.height();
+        let profile = self.column_tops.map(|top| match top {
+            Some(t) => min(height - t, PROFILE_DEPTH),
+            None => PROFILE_DEPTH,
+        });
+        (self.index % 5, self.jet_index, profile)
+    }
+}
+
+impl Simulation for Session {
+    type Snapshot = Grid<char>;
+
+    fn step(&mut self) -> bool {
+        self.iteration();
+        true
+    }
+
+    /// Rocks dropped
+    fn steps(&self) -> usize {
+        self.index
+    }
+
+    fn snapshot(&self) -> Grid<char> {
+        self.render()
+    }
+}
+
+/// Height of the tower after `upto` rocks, skipping ahead once the fingerprints repeat
+pub fn tower_height(session: &mut Observed<Session>, upto: usize) -> usize {
+    let height = cycle::extrapolate(
+        session,
+        upto,
+        |s| {
+            s.step();
+        },
+        |s| s.get().fingerprint(),
+        |s| s.get().height() as i64,
+    );
+    height as usize
+}
+
+/// 10091 jets per `size`, in short gusts one way or the other
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let len = 10091 * size;
+    let mut out = String::with_capacity(len + 1);
+    while out.len() < len {
+        let jet = *rng.pick(&['<', '>']);
+        let gust = rng.range(1..=4) as usize;
+        out.extend(std::iter::repeat_n(jet, gust.min(len - out.len())));
+    }
+    out.push('\n');
+    out
+}
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    const DAY: u8 = 17;
+    type Input = Vec<Jet>;
 
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        Self::record(input, Part::One, &mut Discard).unwrap()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        Self::record(input, Part::Two, &mut Discard).unwrap()
+    }
+
+    fn record(input: &Self::Input, part: Part, recorder: &mut dyn Recorder) -> Option<Answer> {
+        let mut session = Observed::recording(Session::new(input.clone()), recorder);
+        Some(match part {
+            Part::One => {
+                session.run_to(2022);
+                session.get().height().into()
             }
-        }
+            Part::Two => tower_height(&mut session, 1_000_000_000_000).into(),
+        })
+    }
 
-        self.max_height.unwrap() + 1
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
 
@@ -225,27 +314,22 @@ mod tests {
 
     /// Period is 35.
     /// Height addition every period is 53
-    /// Therefore -> 2022 % 35 = 27 and 2022 // 35 = 57 -> smulation(27) + 57 * 53 = 3068
-    /// For 10^12 -> 10^12 % 35 = 15 and 10^12 // 35 = 28571428571 -> simulation(15) + 28571428571 * 53 = 1514285714288
-    //#[test]
+    #[test]
     fn it_works() {
-        let inp = parse_input(read_input("in.test").unwrap());
-        println!("Length of jet stream: {}", inp.len());
-        let mut session = Session::new(inp.into_iter().cycle());
-        let part1 = session.run_simulation(15);
-        println!("Test 1: {}", part1);
+        aoc_core::expected::check::<Day17>("in.test");
     }
 
     /// Period is 1760 starting at 285
     /// Height addition every period is 2737
-    /// Height addition in first 284 is 465
-    /// Therefore -> 465 + (10**12 % 1760) * 2737 + Height addition in first 35/36 (which is the remainder at the end) = 1555113636385
     #[test]
     fn actual() {
-        let inp = parse_input(read_input("in.1").unwrap());
-        println!("Length of jet stream: {}", inp.len());
-        let mut session = Session::new(inp.into_iter().cycle());
-        let part1 = session.run_simulation(10000);
-        println!("Part 1: {}", part1);
+        aoc_core::expected::check::<Day17>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day17>("<>x\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 3, "x"));
+        assert_eq!(aoc_core::expected::parse_error::<Day17>("").message, "expected a line of jets");
     }
 }
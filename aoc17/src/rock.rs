@@ -1,10 +1,17 @@
 use std::fmt::Debug;
 
+use aoc_core::geom::Point2;
+
 /// Assume in the tunnel - left-bottom-corner is (0, 0)
-/// Going right -> increase Point.0
-/// Going up -> increase Point.1
-//// So, the room is the first quadrant
-pub type Point = (usize, usize);
+/// Going right -> increase Point.x
+/// Going up -> increase Point.y, unlike the screen convention of `geom`
+/// So, the room is the first quadrant
+pub type Point = Point2<usize>;
+
+/// The `(x, y)` pairs as points
+pub fn points(coords: &[(usize, usize)]) -> Vec<Point> {
+    coords.iter().map(|&c| Point2::from(c)).collect()
+}
 
 pub trait Rock: Debug {
     /// Any point that can be on collision course when moving left
@@ -1,4 +1,6 @@
-use crate::rock::{Point, Rock};
+use aoc_core::geom::Point2;
+
+use crate::rock::{points, Point, Rock};
 
 /// Row and Col here is the coordinate of the left-most point
 #[derive(Debug)]
@@ -16,44 +18,44 @@ impl Square {
 
 impl Rock for Square {
     fn get_left_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.bottom_left;
-        vec![(x, y), (x, y + 1)]
+        let Point2 { x, y } = self.bottom_left;
+        points(&[(x, y), (x, y + 1)])
     }
 
     fn get_right_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.bottom_left;
-        vec![(x + 1, y), (x + 1, y + 1)]
+        let Point2 { x, y } = self.bottom_left;
+        points(&[(x + 1, y), (x + 1, y + 1)])
     }
 
     fn get_bottom_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.bottom_left;
-        vec![(x, y), (x + 1, y)]
+        let Point2 { x, y } = self.bottom_left;
+        points(&[(x, y), (x + 1, y)])
     }
 
     fn get_top_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.bottom_left;
-        vec![(x, y + 1), (x + 1, y + 1)]
+        let Point2 { x, y } = self.bottom_left;
+        points(&[(x, y + 1), (x + 1, y + 1)])
     }
 
     fn get_highest_point(&self) -> Point {
-        (self.bottom_left.0, self.bottom_left.1 + 1)
+        Point2::new(self.bottom_left.x, self.bottom_left.y + 1)
     }
 
     fn get_all_points(&self) -> Vec<Point> {
-        let (x, y) = self.bottom_left;
-        vec![(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+        let Point2 { x, y } = self.bottom_left;
+        points(&[(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)])
     }
 
     fn move_left(&mut self) {
-        self.bottom_left.0 -= 1;
+        self.bottom_left.x -= 1;
     }
 
     fn move_right(&mut self) {
-        self.bottom_left.0 += 1;
+        self.bottom_left.x += 1;
     }
 
     fn move_down(&mut self) {
-        self.bottom_left.1 -= 1;
+        self.bottom_left.y -= 1;
     }
 
     fn get_pivot(&self) -> Point {
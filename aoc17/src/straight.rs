@@ -1,4 +1,6 @@
-use crate::rock::{Point, Rock};
+use aoc_core::geom::Point2;
+
+use crate::rock::{points, Point, Rock};
 
 /// Row and Col here is the coordinate of the bottom-most point
 #[derive(Debug)]
@@ -20,8 +22,8 @@ impl Rock for Straight {
     }
 
     fn get_right_endpoints(&self) -> Vec<Point> {
-        let (x, y) = self.bottom;
-        vec![(x, y), (x, y + 1), (x, y + 2), (x, y + 3)]
+        let Point2 { x, y } = self.bottom;
+        points(&[(x, y), (x, y + 1), (x, y + 2), (x, y + 3)])
     }
 
     fn get_bottom_endpoints(&self) -> Vec<Point> {
@@ -29,11 +31,11 @@ impl Rock for Straight {
     }
 
     fn get_top_endpoints(&self) -> Vec<Point> {
-        vec![(self.bottom.0, self.bottom.1 + 3)]
+        vec![Point2::new(self.bottom.x, self.bottom.y + 3)]
     }
 
     fn get_highest_point(&self) -> Point {
-        (self.bottom.0, self.bottom.1 + 3)
+        Point2::new(self.bottom.x, self.bottom.y + 3)
     }
 
     fn get_all_points(&self) -> Vec<Point> {
@@ -41,15 +43,15 @@ impl Rock for Straight {
     }
     
     fn move_left(&mut self) {
-        self.bottom.0 -= 1;
+        self.bottom.x -= 1;
     }
 
     fn move_right(&mut self) {
-        self.bottom.0 += 1;
+        self.bottom.x += 1;
     }
 
     fn move_down(&mut self) {
-        self.bottom.1 -= 1;
+        self.bottom.y -= 1;
     }
 
     fn get_pivot(&self) -> Point {
@@ -1,36 +1,44 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::{HashMap, HashSet, VecDeque};
+//! Day 18: Boiling Boulders.
+//!
+//! A [`Session`] holds the lava droplet as a set of [`Cube`]s and measures its
+//! surface, with or without the air pockets trapped inside.
+
+use std::collections::HashSet;
 use std::cmp::{min, max};
 
-type Cube = (i32, i32, i32);
+use aoc_core::geom::Point3;
+use aoc_core::search;
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+/// A 1x1x1 cube of lava, by its coordinates
+pub type Cube = Point3<i32>;
+/// Cubes, then the max and min x, y and z
+pub type Scan = (HashSet<Cube>, i32, i32, i32, i32, i32, i32);
 
-fn parse_input(inp: Vec<String>) -> (HashSet<Cube>, i32, i32, i32, i32, i32, i32) {
+fn parse_input(inp: &Input) -> Result<Scan, ParseError> {
     let (mut xmax, mut ymax, mut zmax) = (0, 0, 0);
     let (mut xmin, mut ymin, mut zmin) = (i32::MAX, i32::MAX, i32::MAX);
-    let col = inp.into_iter().map(|l| {
-        let tmp: Vec<i32> = l.trim().split(",").map(|num| num.parse::<i32>().unwrap()).collect();
+    let col = inp.numbered_lines().map(|(n, l)| {
+        let tmp: Vec<i32> = l.trim().split(",").map(|num| parse_at(n, l, num)).collect::<Result<_, _>>()?;
+        if tmp.len() != 3 {
+            return Err(ParseError::new(n, l, "expected `x,y,z`"));
+        }
         xmax = max(xmax, tmp[0]);
         ymax = max(ymax, tmp[1]);
         zmax = max(zmax, tmp[2]);
         xmin = min(xmin, tmp[0]);
         ymin = min(ymin, tmp[1]);
         zmin = min(zmin, tmp[2]);
-        (tmp[0], tmp[1], tmp[2])
-    }).collect();
-    (col, xmax, ymax, zmax, xmin, ymin, zmin)
+        Ok(Point3::new(tmp[0], tmp[1], tmp[2]))
+    }).collect::<Result<_, _>>()?;
+    Ok((col, xmax, ymax, zmax, xmin, ymin, zmin))
 }
 
-struct Session {
+/// The droplet, and the box around it to search for air in
+pub struct Session {
     cubes: HashSet<Cube>,
-    starting_points: HashSet<Cube>,
     air_trapped: HashSet<Cube>,
     xmax: i32,
     ymax: i32,
@@ -44,31 +52,31 @@ impl Session {
     pub fn new(inp: HashSet<Cube>, xmax: i32, ymax: i32, zmax: i32, xmin: i32, ymin: i32, zmin: i32) -> Self {
         Session {
             cubes: inp,
-            starting_points: HashSet::new(),
             air_trapped: HashSet::new(),
-            xmax: xmax,
-            ymax: ymax,
-            zmax: zmax,
-            xmin: xmin,
-            ymin: ymin,
-            zmin: zmin
+            xmax,
+            ymax,
+            zmax,
+            xmin,
+            ymin,
+            zmin
         }
     }
 
-    fn get_neighbours(cube: &Cube) -> Vec<Cube> {
-        let &(x, y, z) = cube;
-        vec![
-            (x + 1, y, z), (x - 1, y, z),
-            (x, y + 1, z), (x, y - 1, z),
-            (x, y, z + 1), (x, y, z - 1)
-        ]
+    /// The droplet made of `cubes`, in a box that just fits them
+    pub fn from_cubes(cubes: HashSet<Cube>) -> Self {
+        let bounds = |axis: fn(&Cube) -> i32| {
+            (cubes.iter().map(axis).min().unwrap_or(0), cubes.iter().map(axis).max().unwrap_or(0))
+        };
+        let ((xmin, xmax), (ymin, ymax), (zmin, zmax)) = (bounds(|c| c.x), bounds(|c| c.y), bounds(|c| c.z));
+        Session::new(cubes, xmax, ymax, zmax, xmin, ymin, zmin)
     }
 
+    /// Faces of cubes not touching another cube
     pub fn count_surfaces(&mut self) -> usize {
         let mut count = 0;
         for v in self.cubes.iter() {
             let mut init_count = 6;
-            for n in Session::get_neighbours(v) {
+            for n in v.neighbours6() {
                 if self.cubes.contains(&n) {
                     init_count -= 1;
                 }
@@ -78,11 +86,13 @@ impl Session {
         count
     }
 
+    /// Faces of cubes on the outside of the droplet, once
+    /// [`get_air_trapped`](Session::get_air_trapped) has found the pockets inside
     pub fn count_surfaces_2(&self) -> usize {
         let mut count = 0;
         for v in self.cubes.iter() {
             let mut init_count = 6;
-            for n in Session::get_neighbours(v) {
+            for n in v.neighbours6() {
                 if self.cubes.contains(&n) || self.air_trapped.contains(&n) {
                     init_count -= 1;
                 }
@@ -93,98 +103,107 @@ impl Session {
     }
 
     fn check_boundary(&self, cube: &Cube) -> bool {
-        cube.0 < self.xmin - 1 || cube.1 < self.ymin - 1|| cube.2 < self.zmin - 1
-        || cube.0 > self.xmax + 1 || cube.1 > self.ymax + 1 || cube.2 > self.zmax + 1
-    }
-
-    fn dfs(&self, cur_air: Cube, visited: &mut HashSet<Cube>, result: &mut HashMap<Cube, bool>, depth: usize) -> bool {
-        //println!("cur_air: {:?}, depth: {}", cur_air, depth);
-        if result.contains_key(&cur_air) {
-            return *result.get(&cur_air).unwrap();
-        }
-        if visited.contains(&cur_air) {
-            return true;
-        }
-        visited.insert(cur_air);
-        let nns = Session::get_neighbours(&cur_air);
-        let tmp: Vec<bool> = nns.into_iter().map(|potential| {
-            if self.cubes.contains(&potential) {
-                return true;
-            }
-            if self.check_boundary(&potential) {
-                return false;
-            }
-            if visited.contains(&potential) {
-                return true;
-            }
-            return self.dfs(potential, visited, result, depth + 1);
-        }).collect();
-        let res = tmp.into_iter().all(|v| v);
-        result.insert(cur_air, res);
-        visited.remove(&cur_air);
-        res
+        cube.x < self.xmin - 1 || cube.y < self.ymin - 1|| cube.z < self.zmin - 1
+        || cube.x > self.xmax + 1 || cube.y > self.ymax + 1 || cube.z > self.zmax + 1
     }
 
     fn bfs(&mut self) {
-        let mut map: HashSet<Cube> = HashSet::new();
-        map.insert((self.xmin - 1, self.ymin - 1, self.zmin - 1));
-        let mut q = VecDeque::from(vec![(self.xmin - 1, self.ymin - 1, self.zmin - 1)]);
-        while !q.is_empty() {
-            let cube = q.pop_front().unwrap();
-            for n in Session::get_neighbours(&cube) {
-                if self.cubes.contains(&n) || map.contains(&n) || self.check_boundary(&n) {
-                    continue;
-                }
-                map.insert(n);
-                q.push_back(n);
-            }
-        }
+        let start = Point3::new(self.xmin - 1, self.ymin - 1, self.zmin - 1);
+        let outside = search::bfs(
+            [start],
+            |cube| cube.neighbours6().into_iter().filter(|n| !self.cubes.contains(n) && !self.check_boundary(n)),
+            |_| false,
+        );
+        let map = outside.distances();
         let mut set = HashSet::new();
         let (xmin, ymin, zmin) = (self.xmin, self.ymin, self.zmin);
         let (xmax, ymax, zmax) = (self.xmax, self.ymax, self.zmax);
         for x in xmin - 1..xmax + 1 {
             for y in ymin - 1..ymax + 1 {
                 for z in zmin - 1..zmax + 1 {
-                    let cube = (x, y, z);
-                    if !map.contains(&cube) && !self.cubes.contains(&cube) {
+                    let cube = Point3::new(x, y, z);
+                    if !map.contains_key(&cube) && !self.cubes.contains(&cube) {
                         set.insert(cube);
                     }
                 }
             }
         }
-        //println!("{:?}", set);
+        aoc_core::debug!("air trapped", cubes = set.len());
         self.air_trapped = set;
     }
 
+    /// Find the air that cannot reach the outside.
     /// DFS exceeds recursion limit
     pub fn get_air_trapped(&mut self) {
         self.bfs();
     }
 }
 
+/// A lumpy droplet in a 20 cube box at `size` 1, the box growing with the
+/// cube root of `size`. Most of the space inside is lava, leaving air
+/// pockets of all shapes for part 2
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let side = aoc_core::gen::scaled(20, size, 3) as i64;
+    let mid = side as f64 / 2.0;
+    let mut cubes = vec![];
+    for x in 1..=side {
+        for y in 1..=side {
+            for z in 1..=side {
+                let dist = ((x as f64 - mid).powi(2) + (y as f64 - mid).powi(2) + (z as f64 - mid).powi(2)).sqrt();
+                if dist < mid * (0.75 + 0.25 * rng.f64()) && rng.chance(0.8) {
+                    cubes.push(format!("{},{},{}\n", x, y, z));
+                }
+            }
+        }
+    }
+    rng.shuffle(&mut cubes);
+    cubes.concat()
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: u8 = 18;
+    type Input = Scan;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        let (cubes, xmax, ymax, zmax, xmin, ymin, zmin) = input.clone();
+        Session::new(cubes, xmax, ymax, zmax, xmin, ymin, zmin).count_surfaces().into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let (cubes, xmax, ymax, zmax, xmin, ymin, zmin) = input.clone();
+        let mut session = Session::new(cubes, xmax, ymax, zmax, xmin, ymin, zmin);
+        session.get_air_trapped();
+        session.count_surfaces_2().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let (inp, xmax, ymax, zmax, xmin, ymin, zmin) = parse_input(read_input("in.test").unwrap());
-        let mut session = Session::new(inp, xmax, ymax, zmax, xmin, ymin, zmin);
-        let part1 = session.count_surfaces();
-        session.get_air_trapped();
-        let part2 = session.count_surfaces_2();
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day18>("in.test");
     }
 
     #[test]
     fn actual() {
-        let (inp, xmax, ymax, zmax, xmin, ymin, zmin) = parse_input(read_input("in.1").unwrap());
-        let mut session = Session::new(inp, xmax, ymax, zmax, xmin, ymin, zmin);
-        let part1 = session.count_surfaces();
-        session.get_air_trapped();
-        let part2 = session.count_surfaces_2();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
+        aoc_core::expected::check::<Day18>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day18>("1,1,1\n2,2\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "2,2"));
     }
 }
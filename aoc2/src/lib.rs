@@ -1,9 +1,17 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 2: Rock Paper Scissors.
+//!
+//! A strategy guide parses to [`Round`]s: the opponent's [`RPS`] and the
+//! [`PlaceHolder`] for the second column, which part 1 reads as a move and
+//! part 2 as an [`Outcome`].
+
 use std::collections::HashMap;
 use std::str::FromStr;
 
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
+
+/// A move
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Copy)]
 pub enum RPS {
     Rock,
@@ -11,6 +19,7 @@ pub enum RPS {
     Scissor,
 }
 
+/// The second column, whose meaning depends on the part
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum PlaceHolder {
     X,
@@ -18,6 +27,7 @@ pub enum PlaceHolder {
     Z
 }
 
+/// How a round ends for us
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Outcome {
     Win,
@@ -25,42 +35,39 @@ pub enum Outcome {
     Draw
 }
 
-type Round = (RPS, PlaceHolder);
+/// One line of the strategy guide
+pub type Round = (RPS, PlaceHolder);
 
 impl FromStr for RPS {
-    type Err = ();
+    type Err = &'static str;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "A" => Ok(RPS::Rock),
             "B" => Ok(RPS::Paper),
             "C" => Ok(RPS::Scissor),
-            _ => Err(()),
+            _ => Err("expected A, B or C"),
         }
     }
 }
 
 impl FromStr for PlaceHolder {
-    type Err = ();
+    type Err = &'static str;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "X" => Ok(PlaceHolder::X),
             "Y" => Ok(PlaceHolder::Y),
             "Z" => Ok(PlaceHolder::Z),
-            _ => Err(())
+            _ => Err("expected X, Y or Z")
         }
     }
 }
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
-
-fn parse_input(input: Vec<String>) -> Vec<Round> {
-    input.into_iter().map(|v| {
-        let vals: Vec<&str> = v.trim().split(" ").collect();
-        (RPS::from_str(vals[0]).unwrap(), PlaceHolder::from_str(vals[1]).unwrap())
+fn parse_input(input: &Input) -> Result<Vec<Round>, ParseError> {
+    input.numbered_lines().map(|(n, v)| {
+        let Some((a, b)) = v.trim().split_once(' ') else {
+            return Err(ParseError::new(n, v, "expected two moves"));
+        };
+        Ok((parse_at(n, v, a)?, parse_at(n, v, b)?))
     })
     .collect()
 }
@@ -74,84 +81,77 @@ fn get_points_for_outcome() -> HashMap<Outcome, u64> {
 }
 
 fn play_round(
-    pA: &RPS,
-    pB: &RPS,
+    theirs: &RPS,
+    ours: &RPS,
     points_for_outcome: &HashMap<Outcome, u64>,
     points_for_piece: &HashMap<RPS, u64>) -> u64 
 {
     let mut score = 0;
-    score += points_for_piece.get(pB).unwrap();
-    let outcome = match pA == pB {
+    score += points_for_piece.get(ours).unwrap();
+    let outcome = match theirs == ours {
         true => Outcome::Draw,
         false => {
-            let mut out;
-            if *pB == RPS::Scissor && *pA == RPS::Paper
-                || *pB == RPS::Rock && *pA == RPS::Scissor
-                || *pB == RPS::Paper && *pA == RPS::Rock
+            if *ours == RPS::Scissor && *theirs == RPS::Paper
+                || *ours == RPS::Rock && *theirs == RPS::Scissor
+                || *ours == RPS::Paper && *theirs == RPS::Rock
             {
-                out = Outcome::Win
+                Outcome::Win
             }
             else {
-                out = Outcome::Lose;
+                Outcome::Lose
             }
-            out
         }
     };
     score += points_for_outcome.get(&outcome).unwrap();
-    //println!("pA: {:?}, pB: {:?}, score: {}", pA, pB, score);
+    aoc_core::trace!("round played", theirs = ?theirs, ours = ?ours, outcome = ?outcome, score = score);
     score
 }
 
-fn play(input: &Vec<Round>) -> u64 {
-    let options = vec![
+fn play(input: &[Round]) -> u64 {
+    let options = [
         (RPS::Rock, RPS::Paper, RPS::Scissor),
-        //(RPS::Rock, RPS::Scissor, RPS::Paper),
-        //(RPS::Paper, RPS::Rock, RPS::Scissor),
-        //(RPS::Paper, RPS::Scissor, RPS::Rock),
-        //(RPS::Scissor, RPS::Rock, RPS::Paper),
-        //(RPS::Scissor, RPS::Paper, RPS::Rock)
     ];
     let points_for_piece = get_points_for_piece();
     let points_for_outcome = get_points_for_outcome();
-    let mut results: Vec<u64> = options.iter()
+    let results: Vec<u64> = options.iter()
         .map(|option| input.iter().map(|round| {
-                let pB = match round.1 {
+                let ours = match round.1 {
                     PlaceHolder::X => option.0,
                     PlaceHolder::Y => option.1,
                     PlaceHolder::Z => option.2
                 };
-                play_round(&round.0, &pB, &points_for_outcome, &points_for_piece)
+                play_round(&round.0, &ours, &points_for_outcome, &points_for_piece)
             }).sum()
         ).collect();
     *results.iter().max().unwrap()
 }
 
-fn get_piece_for_outcome(pA: &RPS, outcome: Outcome) -> &RPS {
+fn get_piece_for_outcome(theirs: &RPS, outcome: Outcome) -> &RPS {
     match outcome {
-        Outcome::Draw => pA,
+        Outcome::Draw => theirs,
         Outcome::Win => {
-            match pA {
-                &RPS::Rock => &RPS::Paper,
-                &RPS::Paper => &RPS::Scissor,
-                &RPS::Scissor => &RPS::Rock
+            match *theirs {
+                RPS::Rock => &RPS::Paper,
+                RPS::Paper => &RPS::Scissor,
+                RPS::Scissor => &RPS::Rock
             }
         },
         Outcome::Lose => {
-            match pA {
-                &RPS::Rock => &RPS::Scissor,
-                &RPS::Paper => &RPS::Rock,
-                &RPS::Scissor => &RPS::Paper
+            match *theirs {
+                RPS::Rock => &RPS::Scissor,
+                RPS::Paper => &RPS::Rock,
+                RPS::Scissor => &RPS::Paper
             }
         }
     }
 }
 
-fn play_2(input: &Vec<Round>) -> u64 {
+fn play_2(input: &[Round]) -> u64 {
     let get_outcome = |placeholder: &PlaceHolder| -> Outcome {
-        match placeholder {
-            &PlaceHolder::X => Outcome::Lose,
-            &PlaceHolder::Y => Outcome::Draw,
-            &PlaceHolder::Z => Outcome::Win
+        match *placeholder {
+            PlaceHolder::X => Outcome::Lose,
+            PlaceHolder::Y => Outcome::Draw,
+            PlaceHolder::Z => Outcome::Win
         }
     };
     let points_for_piece = get_points_for_piece();
@@ -165,27 +165,51 @@ fn play_2(input: &Vec<Round>) -> u64 {
     }).sum()
 }
 
+/// 2500 rounds per `size`
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    (0..2500 * size).map(|_| format!("{} {}\n", rng.pick(&["A", "B", "C"]), rng.pick(&["X", "Y", "Z"]))).collect()
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u8 = 2;
+    type Input = Vec<Round>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        play(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        play_2(input).into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let input = parse_input(read_input("in.test").unwrap());
-        println!("Test 1: {}", play(&input));
-        println!("Test 2: {}", play_2(&input));
+        aoc_core::expected::check::<Day2>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let input = parse_input(read_input("in.1").unwrap());
-        let part1 = play(&input);
-        let part2 = play_2(&input);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day2>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day2>("A Y\nB Q\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 3, "Q"));
     }
 }
@@ -1,52 +1,61 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::HashMap;
-use std::cmp::{min, max};
-
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+//! Day 20: Grove Positioning System.
+//!
+//! A [`Session`] is the encrypted file, mixed in place. Numbers go in as
+//! [`Key`]s so that duplicates keep their place in the original order;
+//! [`Session::from_numbers`] makes them up from the plain numbers.
+//!
+//! ```
+//! use aoc20::Session;
+//!
+//! let mut file = Session::from_numbers([1, 2, -3, 3, -2, 0, 4]);
+//! assert_eq!(file.part_one(1), 3);
+//! ```
+
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
 /// To handle duplicates, use the value and the original index in the array
-type Key = (i64, usize);
+pub type Key = (i64, usize);
 
-fn parse_input(inp: Vec<String>) -> Vec<Key> {
-    inp.into_iter().enumerate().map(|(i, v)| (v.parse::<i64>().unwrap(), i)).collect()
+fn parse_input(inp: &Input) -> Result<Vec<Key>, ParseError> {
+    let keys: Vec<Key> = inp.numbered_lines().map(|(n, v)| Ok((parse_at(n, v, v.trim())?, n - 1))).collect::<Result<_, _>>()?;
+    if !keys.iter().any(|k| k.0 == 0) {
+        return Err(ParseError::new(1, inp.lines().next().unwrap_or(""), "no 0 to count from"));
+    }
+    Ok(keys)
 }
 
-struct Session {
+/// The numbers in their original order, and as mixed so far
+pub struct Session {
     arr: Vec<Key>,
-    map: HashMap<Key, usize>,
+    /// Where each key is in `new_arr`, kept up to date by `do_one`
+    #[cfg(test)]
+    map: std::collections::HashMap<Key, usize>,
     new_arr: Vec<Key>,
-    zero_ind: Key,
 }
 
 impl Session {
     pub fn new(inp: Vec<Key>) -> Self {
-        let mut map = HashMap::new();
-        let mut zero_ind = (0, 0);
-        for (i, k) in inp.iter().enumerate() {
-            map.insert(*k, i);
-            if k.0 == 0 {
-                zero_ind = *k;
-            }
-        }
         Session {
             arr: inp.clone(),
-            map: map,
+            #[cfg(test)]
+            map: inp.iter().enumerate().map(|(i, k)| (*k, i)).collect(),
             new_arr: inp,
-            zero_ind: zero_ind
         }
     }
 
+    /// Key each number by its position
+    pub fn from_numbers(numbers: impl IntoIterator<Item = i64>) -> Self {
+        Session::new(numbers.into_iter().enumerate().map(|(i, v)| (v, i)).collect())
+    }
+
     /// Trying to replicate what rem_euclid gives for free
+    #[cfg(test)]
     fn do_one(&mut self, index: usize) {
         let (x, _) = self.arr[index];
         let cur_index = *self.map.get(&(x, index)).unwrap();
-        //println!("Val: {}, cur_index: {}", x, cur_index);
+        aoc_core::trace!("moving", value = x, from = cur_index);
         if x == 0 {
             return;
         }
@@ -54,7 +63,7 @@ impl Session {
             true => -1,
             false => 1
         };
-        let x = (x.abs() as usize % self.arr.len()) as i64 * sign ;
+        let x = (x.unsigned_abs() as usize % self.arr.len()) as i64 * sign ;
         if x > 0 {
             let xu = x as usize;
             if xu + cur_index < self.arr.len() - 1 {
@@ -77,7 +86,7 @@ impl Session {
                 self.new_arr[new_ind] = (x, index);
             }
         } else {
-            let xu = x.abs() as usize;
+            let xu = x.unsigned_abs() as usize;
             if cur_index > xu {
                 let begin = (cur_index as i64 + x) as usize;
                 let end = cur_index;
@@ -91,7 +100,7 @@ impl Session {
                 self.new_arr[begin] = (x, index);
             } else {
                 let new_ind = (((x + cur_index as i64).rem_euclid(self.arr.len() as i64)) - 1).rem_euclid(self.arr.len() as i64);
-                //println!("New index: {}", new_ind);
+                aoc_core::trace!("wrapped backwards", to = new_ind);
                 let new_ind = new_ind as usize;
                 for i in cur_index + 1..new_ind+1 {
                     let val = self.new_arr[i];
@@ -108,59 +117,123 @@ impl Session {
     fn do_one_easy(&mut self, original_index: usize) {
         let index = self.new_arr.iter().position(|x| x.1 == original_index).unwrap();
         let x = self.new_arr[index].0;
-        //println!("Val: {}, cur_index: {}", x, cur_index);
         if x == 0 {
             return;
         }
         let new_ind = index as i64 + x;
         let new_ind = new_ind.rem_euclid(self.new_arr.len() as i64 - 1);
+        aoc_core::trace!("moving", value = x, from = index, to = new_ind);
 
         let tmp = self.new_arr.remove(index);
         self.new_arr.insert(new_ind as usize, tmp);
     }
 
+    /// Mix the file `rounds` times, then add up the numbers 1000, 2000 and
+    /// 3000 after the 0
     pub fn part_one(&mut self, rounds: usize) -> i64 {
-        //println!("Init arr: {:?}", self.new_arr);
-        for _ in 0..rounds {
+        aoc_core::trace!("start", arr = ?self.new_arr);
+        for round in 0..rounds {
             for i in 0..self.arr.len() {
                 self.do_one_easy(i);
-                //println!("new_arr: {:?}", self.new_arr);
             }
+            aoc_core::debug!("round mixed", round = round + 1);
         }   
         let zero_ind = self.new_arr.iter().position(|x| x.0 == 0).unwrap();
         let thou = (zero_ind + 1000) % self.arr.len();
         let thou2 = (zero_ind + 2000) % self.arr.len();
         let thou3 = (zero_ind + 3000) % self.arr.len();
-        //println!("1: {:?}, 2: {:?}, 3: {:?}", self.new_arr[thou], self.new_arr[thou2], self.new_arr[thou3]);
+        aoc_core::debug!("grove coordinates", zero = zero_ind, first = self.new_arr[thou].0, second = self.new_arr[thou2].0, third = self.new_arr[thou3].0);
         self.new_arr[thou].0 + self.new_arr[thou2].0 + self.new_arr[thou3].0
     }
 }
 
+/// 5000 numbers per `size`, one of them 0
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let mut numbers: Vec<i64> = (1..5000 * size).map(|_| match rng.range(-10000..=9999) {
+        0 => 10000,
+        v => v,
+    }).collect();
+    numbers.insert(rng.below(numbers.len() + 1), 0);
+    numbers.iter().map(|v| format!("{}\n", v)).collect()
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    const DAY: u8 = 20;
+    type Input = Vec<Key>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        Session::new(input.clone()).part_one(1).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let keyed = input.iter().map(|&(k1, k2)| (k1 * 811589153, k2)).collect();
+        Session::new(keyed).part_one(10).into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let inp = parse_input(read_input("in.test").unwrap());
-        let inp_2 = inp.iter().map(|&(k1, k2)| (k1 * 811589153, k2)).collect();
-        let mut session = Session::new(inp);
-        let part1 = session.part_one(1);
-        let mut session2 = Session::new(inp_2);
-        let part2 = session2.part_one(10);
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day20>("in.test");
     }
 
     #[test]
     fn actual() {
-        let inp = parse_input(read_input("in.1").unwrap());
-        let inp_2 = inp.iter().map(|&(k1, k2)| (k1 * 811589153, k2)).collect();
-        let mut session = Session::new(inp);
-        let part1 = session.part_one(1);
-        let mut session2 = Session::new(inp_2);
-        let part2 = session2.part_one(10);
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
+        aoc_core::expected::check::<Day20>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day20>("1\nx\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "x"));
+        assert_eq!(aoc_core::expected::parse_error::<Day20>("1\n2\n").message, "no 0 to count from");
+    }
+
+    /// `scale` numbers and a 0, some of them longer than the list to make them wrap
+    fn small_list(rng: &mut Rng, scale: usize) -> String {
+        let reach = 2 * scale as i64 + 2;
+        let mut numbers: Vec<i64> = (0..scale).map(|_| rng.range(-reach..=reach)).collect();
+        numbers.insert(rng.below(scale + 1), 0);
+        numbers.iter().map(|v| format!("{}\n", v)).collect()
+    }
+
+    /// The numbers after one round of `mix`, starting from the 0
+    fn mixed(keys: &[Key], mix: fn(&mut Session, usize)) -> Vec<i64> {
+        let mut session = Session::new(keys.to_vec());
+        for i in 0..keys.len() {
+            mix(&mut session, i);
+        }
+        let zero = session.new_arr.iter().position(|k| k.0 == 0).unwrap();
+        session.new_arr[zero..].iter().chain(&session.new_arr[..zero]).map(|k| k.0).collect()
+    }
+
+    /// `do_one` wraps around the whole length of the list instead of one less,
+    /// and files the number it moved under the wrapped value, so a 3 next to
+    /// the 0 is enough to throw it
+    #[test]
+    fn do_one_disagrees() {
+        let found = aoc_core::differential::find(
+            0..40,
+            1..=12,
+            small_list,
+            |input| parse_input(input).ok(),
+            |keys| mixed(keys, Session::do_one),
+            |keys| mixed(keys, Session::do_one_easy),
+        ).expect("do_one agrees with do_one_easy now, so check that it keeps doing so");
+        assert_eq!(found.input, "0\n3\n");
+        assert_eq!((found.left, found.right), (Ok(vec![0, 1]), Ok(vec![0, 3])));
     }
 }
@@ -1,26 +1,48 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::{HashMap, VecDeque};
-
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+//! Day 21: Monkey Math.
+//!
+//! Every monkey has a [`WrapperJob`]: its [`Job`] and, once known, the number
+//! it yells. The jobs by monkey name make the [`Graph`]. A [`Session`] works
+//! out what `root` yells, and a [`SessionBinSearch`] what `humn` has to yell
+//! for both sides of `root` to match.
+//!
+//! ```
+//! use aoc21::{Graph, Job, Op, Session, WrapperJob};
+//!
+//! let mut jobs = Graph::new();
+//! jobs.insert("root".to_string(), WrapperJob::new(Job::Mul((Op::Node("a".to_string()), Op::Num(3)))));
+//! jobs.insert("a".to_string(), WrapperJob::new(Job::Val(Op::Num(4))));
+//! assert_eq!(Session::from_jobs(jobs).get_root(), Ok(12));
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-type ValueType = i64;
+pub type ValueType = i64;
 
+/// Where the part 2 search looks, unless `aoc.toml` says otherwise
+const HUMN_MAX: ValueType = 388222446619011;
+
+/// A number, or the name of the monkey to wait for
 #[derive(Debug, Clone)]
-enum Op {
+pub enum Op {
     Num(ValueType),
     Node(String)
 }
 
-type DoubleArg = (Op, Op);
+pub type DoubleArg = (Op, Op);
+
+/// A monkey whose job cannot be done, and why
+pub type Fault = (String, &'static str);
+
+const DIV_BY_ZERO: &str = "division by zero";
+const OVERFLOW: &str = "result does not fit in 64 bits";
 
+/// What a monkey yells: a number, or what two others yell put together
 #[derive(Debug, Clone)]
-enum Job {
+pub enum Job {
     Val(Op),
     Add(DoubleArg),
     Sub(DoubleArg),
@@ -28,8 +50,9 @@ enum Job {
     Div(DoubleArg)
 }
 
+/// A monkey's job, and the number it yells once the job can be done
 #[derive(Debug, Clone)]
-struct WrapperJob {
+pub struct WrapperJob {
     job: Job,
     value: Option<ValueType>
 }
@@ -37,10 +60,11 @@ struct WrapperJob {
 impl WrapperJob {
     pub fn new(job: Job) -> Self {
         let mut wj = WrapperJob {
-            job: job,
+            job,
             value: None
         };
-        wj.evaluate();
+        // A job that cannot be done is reported when the monkeys are filled in
+        let _ = wj.evaluate();
         wj
     }
 
@@ -52,9 +76,11 @@ impl WrapperJob {
         self.job.clone()
     }
 
-    pub fn evaluate(&mut self) {
+    /// Work out the value if every operand is a number by now, failing if
+    /// that divides by zero or overflows
+    pub fn evaluate(&mut self) -> Result<(), &'static str> {
         if self.get_value().is_some() {
-            return;
+            return Ok(());
         }
         match self.get_job() {
             Job::Val(op) => {
@@ -64,25 +90,29 @@ impl WrapperJob {
             },
             Job::Add((o1, o2)) => {
                 if let (Op::Num(v1), Op::Num(v2)) = (o1, o2) {
-                    self.value = Some(v1 + v2);
+                    self.value = Some(v1.checked_add(v2).ok_or(OVERFLOW)?);
                 }
             },
             Job::Sub((o1, o2)) => {
                 if let (Op::Num(v1), Op::Num(v2)) = (o1, o2) {
-                    self.value = Some(v1 - v2);
+                    self.value = Some(v1.checked_sub(v2).ok_or(OVERFLOW)?);
                 }
             },
             Job::Mul((o1, o2)) => {
                 if let (Op::Num(v1), Op::Num(v2)) = (o1, o2) {
-                    self.value = Some(v1 * v2);
+                    self.value = Some(v1.checked_mul(v2).ok_or(OVERFLOW)?);
                 }
             },
             Job::Div((o1, o2)) => {
                 if let (Op::Num(v1), Op::Num(v2)) = (o1, o2) {
-                    self.value = Some(v1 / v2);
+                    if v2 == 0 {
+                        return Err(DIV_BY_ZERO);
+                    }
+                    self.value = Some(v1.checked_div(v2).ok_or(OVERFLOW)?);
                 }
             }
         };
+        Ok(())
     }
 
     fn get_position(&self, nn: &str) -> usize {
@@ -115,10 +145,10 @@ impl WrapperJob {
         }
     }
 
+    /// Put `val` in place of the operand naming monkey `node`
     pub fn replace(&mut self, node: &str, val: ValueType) {
         let num = Op::Num(val);
         let pos = self.get_position(node);
-        //println!("position: {}", pos);
         match self.get_job() {
             Job::Add((o1, o2)) => {
                 match pos == 0 {
@@ -149,23 +179,51 @@ impl WrapperJob {
     }
 }
 
-type Connections = HashMap<String, Vec<String>>;
+/// The monkeys waiting on each monkey
+pub type Connections = HashMap<String, Vec<String>>;
 
-type Graph = HashMap<String, WrapperJob>;
+/// Every monkey's job, by name
+pub type Graph = HashMap<String, WrapperJob>;
 
-fn parse_input(inp: Vec<String>) -> (Connections, Graph) {
+/// Who waits on whom in `graph`
+fn connect(graph: &Graph) -> Connections {
+    let mut connections = Connections::new();
+    for (parent, wj) in graph.iter() {
+        if let Job::Add((o1, o2)) | Job::Sub((o1, o2)) | Job::Mul((o1, o2)) | Job::Div((o1, o2)) = &wj.job {
+            for op in [o1, o2] {
+                if let Op::Node(child) = op {
+                    connections.entry(child.to_owned()).or_insert(vec![]).push(parent.to_owned());
+                }
+            }
+        }
+    }
+    connections
+}
+
+fn parse_input(inp: &Input) -> Result<(Connections, Graph), ParseError> {
     let mut connections = HashMap::new();
     let mut graph = HashMap::new();
-    for line in inp.into_iter() {
-        let l_r: Vec<&str> = line.split(":").collect();
-        let parent = l_r[0].to_string();
-        let r = l_r[1].trim();
+    // Every monkey waited on, in case one never shows up
+    let mut waited_on = Vec::new();
+    // Where each monkey doing sums is, and its operator
+    let mut sums = HashMap::new();
+    for (n, line) in inp.numbered_lines() {
+        let Some((parent, r)) = line.split_once(":") else {
+            return Err(ParseError::new(n, line, "expected `name: job`"));
+        };
+        let parent = parent.to_string();
+        let r = r.trim();
         if let Ok(v) = r.parse::<ValueType>() {
             graph.insert(parent, WrapperJob::new(Job::Val(Op::Num(v))));
             continue;
         }
         let rhs: Vec<&str> = r.split_whitespace().collect();
-        let (lop, op, rop) = (rhs[0].to_string(), rhs[1], rhs[2].to_string());
+        let [l, op, r] = rhs[..] else {
+            return Err(ParseError::at(n, line, r, "expected a number or `a op b`"));
+        };
+        waited_on.extend([(n, line, l), (n, line, r)]);
+        sums.insert(parent.clone(), (n, line, op));
+        let (lop, rop) = (l.to_string(), r.to_string());
         connections.entry(lop.to_owned()).and_modify(|v: &mut Vec<String>| v.push(parent.to_owned())).or_insert(vec![parent.to_owned()]);
         connections.entry(rop.to_owned()).and_modify(|v: &mut Vec<String>| v.push(parent.to_owned())).or_insert(vec![parent.to_owned()]);
         match op {
@@ -173,13 +231,29 @@ fn parse_input(inp: Vec<String>) -> (Connections, Graph) {
             "-" => graph.insert(parent, WrapperJob::new(Job::Sub((Op::Node(lop), Op::Node(rop))))),
             "*" => graph.insert(parent, WrapperJob::new(Job::Mul((Op::Node(lop), Op::Node(rop))))),
             "/" => graph.insert(parent, WrapperJob::new(Job::Div((Op::Node(lop), Op::Node(rop))))),
-            _ => panic!("Unknown op"),
+            _ => return Err(ParseError::at(n, line, op, "expected +, -, * or /")),
         };
     }
-    (connections, graph)
+    if let Some(&(n, line, name)) = waited_on.iter().find(|(_, _, name)| !graph.contains_key(*name)) {
+        return Err(ParseError::at(n, line, name, "no such monkey"));
+    }
+    if !graph.contains_key("root") {
+        return Err(ParseError::new(1, inp.lines().next().unwrap_or(""), "no root monkey"));
+    }
+    // Work part 1 out now, so that a job that cannot be done is an input error
+    let mut session = Session::new(connections.clone(), graph.clone());
+    if let Err((monkey, message)) = session.fill_graph() {
+        let (n, line, op) = sums[&monkey];
+        return Err(ParseError::at(n, line, op, message));
+    }
+    if let Some(&(n, line, _)) = sums.get("root").filter(|_| session.graph["root"].get_value().is_none()) {
+        return Err(ParseError::new(n, line, "root never gets a number; the monkeys wait on each other"));
+    }
+    Ok((connections, graph))
 }
 
-struct Session {
+/// The monkeys, with `connections` saying who waits on whom in `graph`
+pub struct Session {
     connections: Connections,
     graph: Graph
 }
@@ -187,21 +261,34 @@ struct Session {
 impl Session {
     pub fn new(connections: Connections, graph: Graph) -> Self {
         Session {
-            connections: connections,
-            graph: graph
+            connections,
+            graph
         }
     }
 
+    /// The monkeys doing `jobs`, working out who waits on whom
+    pub fn from_jobs(jobs: Graph) -> Self {
+        Session::new(connect(&jobs), jobs)
+    }
+
+    /// As [`new`](Session::new), with `humn` yelling `v`
     pub fn new_humn(connections: Connections, mut graph: Graph, v: ValueType) -> Self {
         graph.insert("humn".to_owned(), WrapperJob::new(Job::Val(Op::Num(v))));
         Session {
-            connections: connections,
-            graph: graph
+            connections,
+            graph
         }
     }
 
-    fn get_starting_points(&self) -> VecDeque<String> {
-        self.graph.iter().filter(|(k, v)| v.get_value().is_some()).map(|(k, v)| k.to_owned()).collect()
+    fn get_starting_points(&mut self) -> Result<VecDeque<String>, Fault> {
+        let mut q = VecDeque::new();
+        for (k, v) in self.graph.iter_mut() {
+            v.evaluate().map_err(|e| (k.to_owned(), e))?;
+            if v.get_value().is_some() {
+                q.push_back(k.to_owned());
+            }
+        }
+        Ok(q)
     }
 
     /// If a node has an evaluated expression, then we can consider that visited
@@ -209,87 +296,242 @@ impl Session {
     /// When doing the replacement, if we detect both to be Nums, we can add to visited and use these parents
     /// as our next starting points. Continue iterations until we exhaust everything.
     /// Finally, we can return the value of root alone.
-    fn fill_graph(&mut self) {
-        let mut q = self.get_starting_points();
-        //println!("Starting with: {:?}", q);
+    /// Stops at the first monkey whose job cannot be done.
+    fn fill_graph(&mut self) -> Result<(), Fault> {
+        let mut q = self.get_starting_points()?;
+        aoc_core::debug!("filling graph", starting = q.len());
         while !q.is_empty() {
             let cur_node = q.pop_front().unwrap();
             let v = self.graph.get(&cur_node).unwrap().get_value().unwrap();
-            //println!("Current node: {}, val: {}", cur_node, v);
-            for con in self.connections.get(&cur_node) {
-                for c in con {
-                    let p_wrapperjob = self.graph.get_mut(c).unwrap();
-                    //println!("Current job: {:?}", p_wrapperjob);
-                    if p_wrapperjob.get_value().is_some() {
-                        continue;
-                    }
-                    p_wrapperjob.replace(&cur_node, v);
-                    //println!("Replaced job: {:?}", p_wrapperjob);
-                    p_wrapperjob.evaluate();
-                    if p_wrapperjob.get_value().is_some() {
-                        //println!("Evaluated node {}, value: {}", c, p_wrapperjob.get_value().unwrap());
-                        q.push_back(c.to_owned());
-                    }
+            aoc_core::trace!("node known", node = cur_node.as_str(), value = v, queue = q.len());
+            for c in self.connections.get(&cur_node).into_iter().flatten() {
+                let p_wrapperjob = self.graph.get_mut(c).unwrap();
+                if p_wrapperjob.get_value().is_some() {
+                    continue;
+                }
+                p_wrapperjob.replace(&cur_node, v);
+                p_wrapperjob.evaluate().map_err(|e| (c.to_owned(), e))?;
+                if p_wrapperjob.get_value().is_some() {
+                    aoc_core::trace!("node evaluated", node = c.as_str(), value = p_wrapperjob.get_value().unwrap());
+                    q.push_back(c.to_owned());
                 }
             }
         }
+        Ok(())
     }
 
-    pub fn get_root(&mut self) -> ValueType {
-        self.fill_graph();
-        self.graph.get("root").unwrap().get_value().unwrap()
+    /// What `root` yells
+    pub fn get_root(&mut self) -> Result<ValueType, Fault> {
+        self.fill_graph()?;
+        self.graph.get("root").unwrap().get_value().ok_or(("root".to_owned(), "never gets a number"))
     }
 
-    pub fn get_root_equal(&mut self) -> DoubleArg {
-        self.fill_graph();
+    /// The two numbers `root` puts together
+    pub fn get_root_equal(&mut self) -> Result<DoubleArg, Fault> {
+        self.fill_graph()?;
         match self.graph.get("root").unwrap().get_job() {
             Job::Add(v) | Job::Sub(v) |
-            Job::Mul(v) | Job::Div(v) => return v,
+            Job::Mul(v) | Job::Div(v) => Ok(v),
             _ => panic!("Should be a doublearg"),
-        };
-    }
-
-    pub fn print_all(&self) {
-        for (k, v) in self.graph.iter() {
-            println!("Key: {}, Val: {}", k, v.get_value().unwrap());
         }
     }
 }
 
-struct SessionBinSearch {
+/// Searches for the number `humn` has to yell, from 0 to `humn_max`
+pub struct SessionBinSearch {
     graph: Graph,
-    connections: Connections
+    connections: Connections,
+    humn_max: ValueType
 }
 
 impl SessionBinSearch {
-    pub fn new(connections: Connections, graph: Graph) -> Self {
+    pub fn new(connections: Connections, graph: Graph, humn_max: ValueType) -> Self {
         SessionBinSearch {
-            connections: connections,
-            graph: graph
+            connections,
+            graph,
+            humn_max
         }
     }
 
-    /// Example and actual differ in direction i.e if o1 > o2, for example, h = m -1 
-    /// but for actual, l = m + 1
+    pub fn from_jobs(jobs: Graph, humn_max: ValueType) -> Self {
+        SessionBinSearch::new(connect(&jobs), jobs, humn_max)
+    }
+
+    /// How the left side of root compares to the right side when humn yells `v`.
+    /// Parsing checked the monkeys' jobs for the input's own humn only, so
+    /// one that cannot be done for `v` still panics
+    fn root_diff(&self, v: ValueType) -> Ordering {
+        let mut cur_session = Session::new_humn(self.connections.clone(), self.graph.clone(), v);
+        match cur_session.get_root_equal() {
+            Ok((Op::Num(o1), Op::Num(o2))) => o1.cmp(&o2),
+            Ok(_) => panic!("root was not evaluated"),
+            Err((monkey, e)) => panic!("with humn yelling {}, {}: {}", v, monkey, e),
+        }
+    }
+
+    /// The difference is monotonic in humn, but it rises for some inputs (the example)
+    /// and falls for others, so the direction is taken from the ends of the range.
+    /// Integer division lets several values balance root; the smallest one is the answer.
+    /// The search covers `0..=humn_max`
     pub fn run_binsearch(&self) -> ValueType {
         let mut l = 0;
-        let mut h = 388222446619011;
-        while l <= h {
+        let mut h = self.humn_max;
+        let rising = self.root_diff(l) < self.root_diff(h);
+        while l < h {
             let m = l + (h  - l) / 2;
-            let mut cur_session = Session::new_humn( self.connections.clone(), self.graph.clone(), m);
-            let job = cur_session.get_root_equal();
-            if let (Op::Num(o1), Op::Num(o2)) = job {
-                if o1 > o2 {
-                    l = m + 1;
-                } else if o1 < o2 {
-                    h  = m - 1;
-                } else {
-                    return m - 1;
-                }
+            let d = self.root_diff(m);
+            if (rising && d.is_ge()) || (!rising && d.is_le()) {
+                h = m;
+            } else {
+                l = m + 1;
             }
-            
         }
-        panic!("No answer found");
+        if self.root_diff(l).is_ne() {
+            panic!("No answer found");
+        }
+        l
+    }
+}
+
+/// Monkeys for `random_input`: every job handed out so far, and the names taken
+struct Troop<'a> {
+    rng: &'a mut Rng,
+    jobs: Vec<String>,
+    names: HashSet<String>,
+    name_len: usize,
+}
+
+impl<'a> Troop<'a> {
+    fn hire(&mut self, job: String) -> String {
+        let name = loop {
+            let name = self.rng.word(self.name_len, 'a'..='z');
+            if name != "root" && name != "humn" && self.names.insert(name.clone()) {
+                break name;
+            }
+        };
+        self.jobs.push(format!("{}: {}", name, job));
+        name
+    }
+
+    /// A monkey that ends up yelling `value`, which must be positive. Big values
+    /// are built up a digit at a time, as `q * d + r`, so that the numbers
+    /// yelled at the bottom stay small
+    fn yell(&mut self, value: i128) -> String {
+        if value <= 20 {
+            if value >= 2 && self.rng.chance(0.3) {
+                let a = self.rng.range(1..=value as i64 - 1) as i128;
+                let (a, b) = (self.yell(a), self.yell(value - a));
+                return self.hire(format!("{} + {}", a, b));
+            }
+            return self.hire(value.to_string());
+        }
+        if value < 1 << 40 && self.rng.chance(0.1) {
+            let d = self.rng.range(2..=5) as i128;
+            let (a, b) = (self.yell(value * d), self.yell(d));
+            return self.hire(format!("{} / {}", a, b));
+        }
+        let d = self.rng.range(2..=9) as i128;
+        let (q, r) = (self.yell(value / d), self.yell(d));
+        let (q, r_name) = match self.rng.chance(0.5) {
+            true => (q, r),
+            false => (r, q),
+        };
+        let product = self.hire(format!("{} * {}", q, r_name));
+        match value % d {
+            0 => product,
+            rest => {
+                let rest = self.yell(rest);
+                self.hire(format!("{} + {}", product, rest))
+            }
+        }
+    }
+}
+
+/// 70 steps from `humn` up to `root` per `size`, each with its own tree of
+/// monkeys working out a number, some 1600 monkeys in all. Every value the
+/// part 2 search can give `humn` keeps every monkey well inside an `i64`, and
+/// divisions never flatten out what `humn` yells
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    // How big any monkey's number may get
+    const LIMIT: i128 = 1 << 60;
+    let answer = rng.range(100_000_000_000..=10_000_000_000_000) as i128;
+    let part1_humn = rng.range(1..=5000);
+    let mut troop = Troop {
+        rng,
+        jobs: vec![format!("humn: {}", part1_humn)],
+        names: HashSet::new(),
+        name_len: if size <= 30 { 4 } else { 6 },
+    };
+    // The monkey on the path, what it yells when humn yells the answer, and
+    // the least and most it yells over the whole search
+    let (mut path, mut value, mut lo, mut hi) = ("humn".to_string(), answer, 0, HUMN_MAX as i128);
+    for _ in 0..70 * size {
+        // The operation, the number the other side yells, and whether that
+        // side comes first
+        let (op, c, first) = match troop.rng.below(5) {
+            0 => ('+', troop.rng.range(1..=1_000_000) as i128, troop.rng.chance(0.5)),
+            1 => ('-', value + troop.rng.range(1..=1000) as i128, true),
+            2 if value > 1 => ('-', troop.rng.range(1..=(value - 1).min(1_000_000) as i64) as i128, false),
+            3 => ('*', troop.rng.range(2..=9) as i128, troop.rng.chance(0.5)),
+            _ => match (2..=9).find(|&c| value % c == 0 && (hi - lo) / c >= HUMN_MAX as i128) {
+                Some(c) => ('/', c, false),
+                None => continue,
+            },
+        };
+        let apply = |v: i128| match (op, first) {
+            ('+', _) => v + c,
+            ('-', true) => c - v,
+            ('-', false) => v - c,
+            ('*', _) => v * c,
+            _ => v / c,
+        };
+        let (a, b) = (apply(lo), apply(hi));
+        if a.abs().max(b.abs()) >= LIMIT {
+            continue;
+        }
+        (value, lo, hi) = (apply(value), a.min(b), a.max(b));
+        let other = troop.yell(c);
+        let job = match first {
+            true => format!("{} {} {}", other, op, path),
+            false => format!("{} {} {}", path, op, other),
+        };
+        path = troop.hire(job);
+    }
+    let other = troop.yell(value);
+    let root = match troop.rng.chance(0.5) {
+        true => format!("root: {} + {}", path, other),
+        false => format!("root: {} + {}", other, path),
+    };
+    troop.jobs.push(root);
+    let mut jobs = troop.jobs;
+    rng.shuffle(&mut jobs);
+    jobs.iter().map(|j| format!("{}\n", j)).collect()
+}
+
+pub struct Day21;
+
+impl Solution for Day21 {
+    const DAY: u8 = 21;
+    /// The monkeys, and the most `humn` may yell in part 2
+    type Input = (Connections, Graph, ValueType);
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        let (connections, graph) = parse_input(input).map_err(|e| input.locate(e))?;
+        Ok((connections, graph, input.param_or("humn_max", HUMN_MAX)?))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        let (connections, graph, _) = input;
+        Session::new(connections.clone(), graph.clone()).get_root().expect("checked by parse").into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let (connections, graph, humn_max) = input;
+        SessionBinSearch::new(connections.clone(), graph.clone(), *humn_max).run_binsearch().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
 
@@ -297,31 +539,34 @@ impl SessionBinSearch {
 mod tests {
     use super::*;
 
-    //#[test]
+    #[test]
     fn it_works() {
-        let (connections, graph) = parse_input(read_input("in.test").unwrap());
-        let mut session_bin_search = SessionBinSearch::new(connections.clone(), graph.clone());
-        let mut session = Session::new(connections, graph);
-        let part1 = session.get_root();
-        let part2 = session_bin_search.run_binsearch();
-        //session.print_all();
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day21>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let (connections, graph) = parse_input(read_input("in.1").unwrap());
-        let mut session_bin_search = SessionBinSearch::new(connections.clone(), graph.clone());
-        let mut session = Session::new(connections, graph);
-        let part1 = session.get_root();
-        let part2 = session_bin_search.run_binsearch();
-        let elapsed = now.elapsed();
-        //session.print_all();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:?}", elapsed);
+        aoc_core::expected::check::<Day21>("in.1");
+    }
+
+    #[test]
+    fn puzzle_defaults() {
+        // No parameters attached, as when aoc.toml is missing
+        let parsed = Day21::parse(&Input::from_path("in.test").unwrap()).unwrap();
+        assert_eq!(Day21::part2(&parsed), Answer::from(301));
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day21>("root: aaaa + bbbb\naaaa: 5\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 14, "bbbb"));
+        assert_eq!(aoc_core::expected::parse_error::<Day21>("aaaa: 5\n").message, "no root monkey");
+
+        let e = aoc_core::expected::parse_error::<Day21>("root: aaaa + bbbb\naaaa: 5\nbbbb: aaaa / cccc\ncccc: 0\n");
+        assert_eq!((e.line, e.column, e.message.as_str()), (3, 12, "division by zero"));
+        let e = aoc_core::expected::parse_error::<Day21>("root: aaaa * aaaa\naaaa: 5000000000\n");
+        assert_eq!((e.line, e.column, e.message.as_str()), (1, 12, "result does not fit in 64 bits"));
+        let e = aoc_core::expected::parse_error::<Day21>("root: aaaa + bbbb\naaaa: bbbb + bbbb\nbbbb: aaaa + aaaa\n");
+        assert_eq!(e.line, 1);
     }
 }
@@ -1,68 +1,41 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 23: Unstable Diffusion.
+//!
+//! [`Elves`] is where every elf stands. A [`Session`] spreads them out a round
+//! per [`step`](Simulation::step), turning through the directions they look
+//! in as the puzzle says.
+
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::cmp::{min, max};
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::geom::{Dir8, Point2};
+use aoc_core::render::{Discard, Recorder};
+use aoc_core::simulation::{Observed, Simulation};
+use aoc_core::{Answer, Grid, Input, Part, Solution};
+use aoc_core::gen::Rng;
 
-/// Assume bottom left is origin in our case
-fn parse_input(inp: Vec<String>) -> Elves {
+fn parse_input(input: &Input) -> aoc_core::Result<Elves> {
+    let map = Grid::parse(input, |c| matches!(c, '#' | '.').then_some(c))?;
     let mut set = HashSet::new();
-    let height = inp.len() - 1;
-    for (i, v) in inp.into_iter().enumerate() {
-        for (j, c) in v.chars().enumerate() {
-            match c {
-                '#' => { set.insert((j as Val, (height - i) as Val)); },
-                _ => (),
-            };
-        }
-    }
-    Elves::new(set)
-}
-
-type Val = i32;
-type Pos = (Val, Val);
-
-enum Dir {
-    N,
-    S,
-    E,
-    W,
-    NE,
-    NW,
-    SE,
-    SW
-}
-
-impl Dir {
-    fn all() -> Vec<Self> {
-        vec![Dir::N, Dir::S, Dir::E, Dir::W, Dir::NE, Dir::NW, Dir::SE, Dir::SW]
+    for p in map.find_all(|&c| c == '#') {
+        let Some(pos) = Pos::from_pos(p) else {
+            let line = input.lines().nth(p.0).unwrap_or("");
+            return Err(input.parse_error(p.0 + 1, line, "the map is too large"));
+        };
+        set.insert(pos);
     }
-
-    fn north() -> Vec<Self> {
-        vec![Dir::N, Dir::NE, Dir::NW]
-    }
-
-    fn south() -> Vec<Self> {
-        vec![Dir::S, Dir::SE, Dir::SW]
-    }
-
-    fn east() -> Vec<Self> {
-        vec![Dir::E, Dir::SE, Dir::NE]
-    }
-
-    fn west() -> Vec<Self> {
-        vec![Dir::W, Dir::SW, Dir::NW]
+    if set.is_empty() {
+        return Err(input.parse_error(1, input.lines().next().unwrap_or(""), "expected at least one elf"));
     }
+    Ok(Elves::new(set))
 }
 
+pub type Val = i32;
+/// x to the right, y down, as the map is drawn
+pub type Pos = Point2<Val>;
+
+/// The positions of the elves
 #[derive(Debug, Clone)]
-struct Elves {
+pub struct Elves {
     map: HashSet<Pos>
 }
 
@@ -73,22 +46,42 @@ impl Elves {
         }
     }
 
+    /// How many elves there are
     pub fn length(&self) -> usize {
         self.map.len()
     }
 
-    pub fn get_empty(&self) -> usize {
+    /// Top left and bottom right corners of the smallest rectangle holding every elf
+    fn bounds(&self) -> (Pos, Pos) {
         let (mut minx, mut maxx, mut miny, mut maxy) = (Val::MAX, Val::MIN, Val::MAX, Val::MIN);
         for val in self.map.iter() {
-            minx = min(minx, val.0);
-            maxx = max(maxx, val.0);
-            miny = min(miny, val.1);
-            maxy = max(maxy, val.1);
+            minx = min(minx, val.x);
+            maxx = max(maxx, val.x);
+            miny = min(miny, val.y);
+            maxy = max(maxy, val.y);
         }
-        let total_points = ((maxy - miny + 1) * (maxx - minx + 1)) as usize;
+        (Pos::new(minx, miny), Pos::new(maxx, maxy))
+    }
+
+    /// Ground tiles in the smallest rectangle holding every elf
+    pub fn get_empty(&self) -> usize {
+        let (low, high) = self.bounds();
+        let total_points = ((high.y - low.y + 1) * (high.x - low.x + 1)) as usize;
         total_points - self.length()
     }
 
+    /// The smallest rectangle holding every elf, elves as `#`
+    pub fn render(&self) -> Grid<char> {
+        let (low, high) = self.bounds();
+        let (width, height) = ((high.x - low.x + 1) as usize, (high.y - low.y + 1) as usize);
+        Grid::from_fn(width, height, |(r, c)| {
+            match self.map.contains(&(low + Pos::new(c as Val, r as Val))) {
+                true => '#',
+                false => '.',
+            }
+        })
+    }
+
     pub fn get_map(&self) -> &HashSet<Pos> {
         &self.map
     }
@@ -101,68 +94,19 @@ impl Elves {
         self.map.insert(point);
     }
 
-    fn get_neighbour(point: &Pos, direction: Dir) -> Pos {
-        let &(x, y) = point;
-        match direction {
-            Dir::N => (x, y + 1),
-            Dir::NE => (x + 1, y + 1),
-            Dir::NW => (x - 1, y + 1),
-            Dir::E => (x + 1, y),
-            Dir::W => (x - 1, y),
-            Dir::S => (x, y - 1),
-            Dir::SE => (x + 1, y - 1),
-            Dir::SW => (x - 1, y - 1)
-        }
-    }
-
-    fn get_neighbours(point: &Pos, directions: Vec<Dir>) -> Vec<Pos> {
-        directions.into_iter().map(|d| Elves::get_neighbour(point, d)).collect()
-    }
-
-    /*
-    pub fn get_all_neighbours(point: &Pos) -> Vec<Pos> {
-        Elves::get_neighbours(point, Dir::all())
-    }
-
-    pub fn get_north_neighbours(point: Pos) -> Vec<Pos> {
-        Elves::get_neighbours(point, Dir::north())
-    }
-
-    pub fn get_south_neighbours(point: Pos) -> Vec<Pos> {
-        Elves::get_neighbours(point, Dir::south())
-    }
-
-    pub fn get_east_neighbours(point: Pos) -> Vec<Pos> {
-        Elves::get_neighbours(point, Dir::east())
-    }
-
-    pub fn get_west_neighbours(point: Pos) -> Vec<Pos> {
-        Elves::get_neighbours(point, Dir::west())
-    }
-    */
-
-    pub fn is_move_possible(&self, points: &Vec<Pos>) -> bool {
+    /// Nobody on any of `points`
+    pub fn is_move_possible(&self, points: &[Pos]) -> bool {
         points.iter().all(|p| !self.map.contains(p))
     }
 
+    /// Nobody around `point`, so the elf there stays put
     pub fn check_end(&self, point: &Pos) -> bool {
-        self.is_move_possible(&Elves::get_neighbours(point, Dir::all()))
+        self.is_move_possible(&point.neighbours8())
     }
 
-    pub fn check_north(&self, point: &Pos) -> bool {
-        self.is_move_possible(&Elves::get_neighbours(point, Dir::north()))
-    }
-
-    pub fn check_south(&self, point: &Pos) -> bool {
-        self.is_move_possible(&Elves::get_neighbours(point, Dir::south()))
-    }
-
-    pub fn check_east(&self, point: &Pos) -> bool {
-        self.is_move_possible(&Elves::get_neighbours(point, Dir::east()))
-    }
-
-    pub fn check_west(&self, point: &Pos) -> bool {
-        self.is_move_possible(&Elves::get_neighbours(point, Dir::west()))
+    /// Nobody in `direction` or either diagonal next to it
+    pub fn check(&self, point: &Pos, direction: Dir8) -> bool {
+        self.is_move_possible(&direction.fan().map(|d| point.step(d)))
     }
 }
 
@@ -171,16 +115,19 @@ enum State {
     End
 }
 
-struct Session {
+/// The elves spreading out, one round a step, until a round in which none of them moves
+pub struct Session {
     cur_pos: Elves,
-    cur_dir: VecDeque<Dir>
+    cur_dir: VecDeque<Dir8>,
+    rounds: usize
 }
 
 impl Session {
     pub fn new(inp: Elves) -> Self {
         Session {
             cur_pos: inp,
-            cur_dir: VecDeque::from(vec![Dir::N, Dir::S, Dir::W, Dir::E]),
+            cur_dir: VecDeque::from(vec![Dir8::N, Dir8::S, Dir8::W, Dir8::E]),
+            rounds: 0
         }
     }
 
@@ -193,41 +140,15 @@ impl Session {
                 continue;
             }
             for d in self.cur_dir.iter() {
-                let new_position = match d {
-                    Dir::N => {
-                        match self.cur_pos.check_north(elf) {
-                            true => Some((elf.0, elf.1 + 1)),
-                            false => None,
-                        }
-                    },
-                    Dir::S => {
-                        match self.cur_pos.check_south(elf) {
-                            true => Some((elf.0, elf.1 - 1)),
-                            false => None,
-                        }
-                    },
-                    Dir::W => {
-                        match self.cur_pos.check_west(elf) {
-                            true => Some((elf.0 - 1, elf.1)),
-                            false => None
-                        }
-                    },
-                    Dir::E => {
-                        match self.cur_pos.check_east(elf) {
-                            true => Some((elf.0 + 1, elf.1)),
-                            false => None,
-                        }
-                    },
-                    _ => None,
+                let new_position = match self.cur_pos.check(elf, *d) {
+                    true => Some(elf.step(*d)),
+                    false => None,
                 };
-                match new_position {
-                    Some(new_pos) => {
-                        new_positions.entry(new_pos)
-                            .and_modify(|v| v.push(*elf))
-                            .or_insert(vec![*elf]);
-                        break;
-                    },
-                    None => (),
+                if let Some(new_pos) = new_position {
+                    new_positions.entry(new_pos)
+                        .and_modify(|v| v.push(*elf))
+                        .or_insert(vec![*elf]);
+                    break;
                 };
             }
         }
@@ -235,8 +156,9 @@ impl Session {
             return State::End;
         }
         new_positions.retain(|_, v| v.len() == 1);
+        aoc_core::debug!("round", first_dir = ?self.cur_dir[0], proposing = self.cur_pos.length() - count, moving = new_positions.len());
         for (k, v) in new_positions.into_iter() {
-            //println!("Moving {:?} to {:?}", &v[0], k);
+            aoc_core::trace!("elf moved", from = ?v[0], to = ?k);
             self.cur_pos.remove(&v[0]);
             self.cur_pos.insert(k);
         }
@@ -245,29 +167,80 @@ impl Session {
         State::Continue
     }
 
-    pub fn run_rounds(&mut self, num_rounds: usize) {
-        for i in 0..num_rounds {
-            //println!("Round: {}", i);
-            match self.run_round() {
-                State::Continue => (),
-                State::End => break,
-            };
-        }
+    /// See [`Elves::get_empty`]
+    pub fn count_empty(&self) -> usize {
+        self.cur_pos.get_empty()
     }
+}
 
-    pub fn run_till_end(&mut self, start_round: usize) -> usize {
-        let mut count = start_round;
-        loop {
-            match self.run_round() {
-                State::Continue => count = count + 1,
-                State::End => break,
-            };
+impl Simulation for Session {
+    type Snapshot = Grid<char>;
+
+    /// Run a round; false if no elf moved in it
+    fn step(&mut self) -> bool {
+        match self.run_round() {
+            State::Continue => {
+                self.rounds += 1;
+                true
+            },
+            State::End => false,
         }
-        count
     }
 
-    pub fn count_empty(&self) -> usize {
-        self.cur_pos.get_empty()
+    /// Rounds in which some elf moved
+    fn steps(&self) -> usize {
+        self.rounds
+    }
+
+    fn snapshot(&self) -> Grid<char> {
+        self.cur_pos.render()
+    }
+}
+
+/// A square grove of 75 by 75 at `size` 1, growing with the square root of
+/// `size`, about half of it elves
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let side = aoc_core::gen::scaled(75, size, 2);
+    let mut out = String::with_capacity(side * (side + 1));
+    for _ in 0..side {
+        out.extend((0..side).map(|_| if rng.chance(0.49) { '#' } else { '.' }));
+        out.push('\n');
+    }
+    out
+}
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    const DAY: u8 = 23;
+    type Input = Elves;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        Self::record(input, Part::One, &mut Discard).unwrap()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        Self::record(input, Part::Two, &mut Discard).unwrap()
+    }
+
+    fn record(input: &Self::Input, part: Part, recorder: &mut dyn Recorder) -> Option<Answer> {
+        let mut session = Observed::recording(Session::new(input.clone()), recorder);
+        Some(match part {
+            Part::One => {
+                session.run_to(10);
+                session.get().count_empty().into()
+            }
+            // The first round in which no elf moves is the one after the last step
+            Part::Two => (session.run_to_end() + 1).into(),
+        })
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
 
@@ -277,27 +250,30 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let input = parse_input(read_input("in.test").unwrap());
-        let mut session = Session::new(input.clone());
-        session.run_rounds(10);
-        let part1 = session.count_empty();
-        let part2 = session.run_till_end(10);
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2 + 1);
+        aoc_core::expected::check::<Day23>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let input = parse_input(read_input("in.1").unwrap());
-        let mut session = Session::new(input);
-        session.run_rounds(10);
-        let part1 = session.count_empty();
-        let part2 = session.run_till_end(10);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2 + 1);
-        println!("Elapsed: {:?}", elapsed);
+        aoc_core::expected::check::<Day23>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day23>("..#\n.x.\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "x"));
+        assert_eq!(aoc_core::expected::parse_error::<Day23>("...\n").message, "expected at least one elf");
+        assert_eq!(aoc_core::expected::parse_error::<Day23>("").line, 1);
+    }
+
+    #[test]
+    fn small_example() {
+        let elves = Day23::parse(&Input::from(".....\n..##.\n..#..\n.....\n..##.\n.....\n")).unwrap();
+        let mut session = Session::new(elves);
+        assert!(session.step());
+        assert_eq!(session.snapshot().to_string(), "##\n..\n#.\n.#\n#.");
+        assert_eq!(session.run_to_end(), 2);
+        assert_eq!(session.snapshot().to_string(), "..#..\n....#\n#....\n....#\n.....\n..#..");
+        assert_eq!(session.steps(), 3);
     }
 }
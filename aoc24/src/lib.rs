@@ -1,54 +1,43 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::{HashSet, VecDeque};
-use std::cmp::min;
+//! Day 24: Blizzard Basin.
+//!
+//! A [`Session`] is the valley with its [`Blizzard`]s, moved on a minute per
+//! [`step`](Simulation::step), and finds the quickest way through them.
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use std::collections::HashSet;
+use std::cmp::min;
 
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
-enum Dir {
-    U,
-    D,
-    L,
-    R
-}
+use aoc_core::geom::Dir4;
+use aoc_core::{budget, search};
+use aoc_core::render::{Discard, Recorder};
+use aoc_core::simulation::Simulation;
+use aoc_core::{Answer, Grid, Input, Part, Solution};
+use aoc_core::gen::Rng;
 
-type Coord = (usize, usize);
+/// `(row, column)`, with the top left wall at `(0, 0)`
+pub type Coord = (usize, usize);
 
-#[derive(Debug, Eq, Hash, Clone)]
-struct Blizzard {
+/// A blizzard where it is and the way it blows
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Blizzard {
     pub pos: Coord,
-    pub dir: Dir
-}
-
-impl PartialEq for Blizzard {
-    fn eq(&self, other: &Blizzard) -> bool {
-        self.pos == other.pos && self.dir == other.dir
-    }
+    pub dir: Dir4
 }
 
 impl Blizzard {
-    pub fn new(init_pos: Coord, dir: Dir) -> Self {
+    pub fn new(init_pos: Coord, dir: Dir4) -> Self {
         Blizzard {
             pos: init_pos,
-            dir: dir
+            dir
         }
     }
 
+    /// Move on by a minute, wrapping around at the walls: `rwall` is the
+    /// column of the right wall and `dwall` the row of the bottom wall.
     /// uwall and lwall are row = 0 and col = 0 respectively
     pub fn update_loc(&mut self, rwall: usize, dwall: usize) {
         let (r, c) = self.pos;
-        let (mut nr, mut nc) = match self.dir {
-            Dir::U => (r - 1, c),
-            Dir::D => (r + 1, c),
-            Dir::L => (r, c - 1),
-            Dir::R => (r, c + 1)
-        };
+        let (dr, dc) = self.dir.offset();
+        let (mut nr, mut nc) = (r.wrapping_add_signed(dr), c.wrapping_add_signed(dc));
         if nr == 0 {
             nr = dwall - 1;
         }
@@ -67,24 +56,25 @@ impl Blizzard {
 
 /// Return Start coord, end coord, and set of blizzard locations
 /// Coords are in the usual matrix row-col i.e top left is 0,0. First row is 0,x, Second column is (y, 1)
-fn parse_input(inp: Vec<String>) -> (Vec<Blizzard>, usize, usize) {
-    let mut list = Vec::new();
-    let (nrows, ncols) = (inp.len(), inp[0].len());
-    for (i, val) in inp.into_iter().enumerate() {
-        for (j, c) in val.chars().enumerate() {
-            match c {
-                '>' => { list.push(Blizzard::new((i, j), Dir::R)); },
-                '<' => { list.push(Blizzard::new((i, j), Dir::L)); },
-                'v' => { list.push(Blizzard::new((i, j), Dir::D)); },
-                '^' => { list.push(Blizzard::new((i, j), Dir::U)); },
-                _ => (),
-            }
-        }
+fn parse_input(input: &Input) -> aoc_core::Result<(Vec<Blizzard>, usize, usize)> {
+    let map = Grid::parse(input, |c| match c {
+        '>' => Some(Some(Dir4::E)),
+        '<' => Some(Some(Dir4::W)),
+        'v' => Some(Some(Dir4::S)),
+        '^' => Some(Some(Dir4::N)),
+        '#' | '.' => Some(None),
+        _ => None,
+    })?;
+    if map.height() < 3 || map.width() < 3 {
+        return Err(input.parse_error(1, input.lines().next().unwrap_or(""), "expected a valley inside walls"));
     }
-    (list, nrows, ncols)
+    let list = map.iter().filter_map(|(pos, dir)| dir.map(|d| Blizzard::new(pos, d))).collect();
+    Ok((list, map.height(), map.width()))
 }
 
-struct Session {
+/// The blizzards moving through the valley, one minute a step, along with
+/// the search for a way through them
+pub struct Session {
     nrows: usize,
     ncols: usize,
     blizzards: Vec<Vec<Blizzard>>, // Holds all blizzards per iteration
@@ -92,6 +82,7 @@ struct Session {
 }
 
 impl Session {
+    /// The valley is `nrows` by `ncols` counting the walls
     pub fn new(blizzards: Vec<Blizzard>, nrows: usize, ncols: usize) -> Self {
         let mut set = HashSet::new();
         for v in blizzards.iter() {
@@ -99,12 +90,17 @@ impl Session {
         }
         Session {
             blizzards: vec![blizzards],
-            nrows: nrows,
-            ncols: ncols,
+            nrows,
+            ncols,
             blizzard_locations: vec![set]
         }
     }
 
+    /// The gap in the top wall and the one in the bottom wall
+    pub fn entrance_and_exit(&self) -> (Coord, Coord) {
+        ((0, 1), (self.nrows - 1, self.ncols - 2))
+    }
+
     fn update_all_locs(&mut self) {
         let (rwall, dwall) = (self.ncols - 1, self.nrows - 1);
         let mut clone = self.blizzards[self.blizzards.len() - 1].clone();
@@ -117,17 +113,8 @@ impl Session {
         self.blizzard_locations.push(set);
     }
 
-    fn generate_new_blizzards(&mut self, iteration: usize) {
-        if iteration < self.blizzards.len() {
-            return;
-        }
-        for i in self.blizzards.len()..iteration+1 {
-            self.update_all_locs();
-        }
-    }
-
     fn check_validity(&self, cur_pos: Coord, cur_it: usize) -> bool {
-        self.blizzard_locations[cur_it].iter().all(|v| *v != cur_pos)
+        !self.blizzard_locations[cur_it].contains(&cur_pos)
     }
 
     fn get_new_positions(&self, pos: Coord) -> Vec<Coord> {
@@ -148,49 +135,160 @@ impl Session {
         vec
     }
 
-    pub fn find_shortest_path(&mut self, start: Coord, end: Coord, start_iter: usize) -> usize {
-        //println!("start: {:?} end: {:?}", start, end);
-        let IT_MAX = 750;
-        let mut visited = HashSet::new();
-        let mut q = VecDeque::from(vec![(start, start_iter)]); // Cur-coordinate, cur-iteration
-        while !q.is_empty() {
-            let (cur_loc, cur_it) = q.pop_front().unwrap();
-            if cur_it > IT_MAX {
-                continue;
-            }
-            if visited.contains(&(cur_loc, cur_it)) {
-                continue;
+    /// The valley at `iteration` as the puzzle draws it, with the expedition as `E`
+    fn render(&self, iteration: usize, expedition: Option<Coord>) -> Grid<char> {
+        let mut grid = Grid::from_fn(self.ncols, self.nrows, |(r, c)| {
+            match r == 0 || r == self.nrows - 1 || c == 0 || c == self.ncols - 1 {
+                true => '#',
+                false => '.',
             }
-            // Generate blizzard locations for this iteration
-            self.generate_new_blizzards(cur_it);
-            // Add to visited
-            visited.insert((cur_loc, cur_it));
-            // Check if this is a valid position without any blizzards
-            if !self.check_validity(cur_loc, cur_it) {
-                //println!("here");
-                continue;
-            }
-            for (r, c) in self.get_new_positions(cur_loc) {
-                if (r, c) == end {
-                    // Since we need the shortest time to get an answer,
-                    // the firts one is the answer
-                    return cur_it + 1;
-                }
-                if r == 0 || r == self.nrows - 1 || c == 0 || c == self.ncols - 1 {
-                    continue;
-                }
-                q.push_back(((r, c), cur_it + 1));
+        });
+        grid[(0, 1)] = '.';
+        grid[(self.nrows - 1, self.ncols - 2)] = '.';
+        for blizzard in self.blizzards[iteration].iter() {
+            let cell = &mut grid[blizzard.pos];
+            *cell = match *cell {
+                '.' => match blizzard.dir {
+                    Dir4::N => '^',
+                    Dir4::S => 'v',
+                    Dir4::E => '>',
+                    Dir4::W => '<',
+                },
+                '2'..='8' => (*cell as u8 + 1) as char,
+                _ => '2',
+            };
+        }
+        if let Some(expedition) = expedition {
+            grid[expedition] = 'E';
+        }
+        grid
+    }
+
+    /// Minutes after which the blizzards are back where they started
+    fn period(&self) -> usize {
+        let (h, w) = (self.nrows - 2, self.ncols - 2);
+        let gcd = (1..=min(h, w)).rev().find(|d| h % d == 0 && w % d == 0).unwrap_or(1);
+        h * w / gcd
+    }
+
+    /// Time at which `end` is first reached, leaving `start` at `start_iter`,
+    /// with a frame for each minute after `start_iter`. `None` if it cannot
+    /// be reached, or the budget ran out first
+    pub fn find_shortest_path(&mut self, start: Coord, end: Coord, start_iter: usize, recorder: &mut dyn Recorder) -> Option<usize> {
+        // Minutes a whole period apart look the same, so the search is over
+        // places and the minute within the period, and always ends
+        let (nrows, ncols, period) = (self.nrows, self.ncols, self.period());
+        let inside = |(r, c): Coord| r > 0 && r < nrows - 1 && c > 0 && c < ncols - 1;
+        let found = search::bfs(
+            [(start, start_iter % period)], // Cur-coordinate, cur-iteration within the period
+            |&(cur_loc, cur_it)| {
+                let next_it = (cur_it + 1) % period;
+                // Generate blizzard locations for the next iteration
+                self.run_to(next_it);
+                let mut moves = self.get_new_positions(cur_loc);
+                moves.retain(|&pos| pos == end || inside(pos));
+                // Wait
+                moves.push(cur_loc);
+                moves
+                    .into_iter()
+                    .filter(|&pos| self.check_validity(pos, next_it))
+                    .map(|pos| (pos, next_it))
+                    .collect::<Vec<_>>()
+            },
+            |&(cur_loc, _)| cur_loc == end,
+        );
+        let path = found.path()?;
+        for (i, &(pos, it)) in path.iter().enumerate() {
+            if i > 0 || start_iter == 0 {
+                recorder.capture(|| self.render(it, Some(pos)));
             }
-            // Wait
-            q.push_back((cur_loc, cur_it + 1));
         }
-        usize::MAX
+        Some(start_iter + path.len() - 1)
+    }
+
+    /// Time at which `end` is reached the third time, going there, back to
+    /// `start` for the snacks, and there again
+    pub fn orchestrate(&mut self, start: Coord, end: Coord, recorder: &mut dyn Recorder) -> Option<usize> {
+        let val1 = self.find_shortest_path(start, end, 0, recorder)?;
+        let val2 = self.find_shortest_path(end, start, val1, recorder)?;
+        self.find_shortest_path(start, end, val2, recorder)
+    }
+}
+
+impl Simulation for Session {
+    type Snapshot = Grid<char>;
+
+    /// Move the blizzards on by a minute
+    fn step(&mut self) -> bool {
+        self.update_all_locs();
+        true
+    }
+
+    /// Minutes the blizzards have moved for
+    fn steps(&self) -> usize {
+        self.blizzards.len() - 1
+    }
+
+    /// The valley at the last minute worked out
+    fn snapshot(&self) -> Grid<char> {
+        self.render(self.steps(), None)
+    }
+}
+
+/// A valley 35 by 100 inside its walls at `size` 1, both sides growing with
+/// the square root of `size`, nine in ten of its squares with a blizzard. As in
+/// the real ones, no blizzard blows up or down through the gaps in the walls
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let (height, width) = (aoc_core::gen::scaled(35, size, 2), aoc_core::gen::scaled(100, size, 2));
+    let mut out = format!("#.{}\n", "#".repeat(width));
+    for _ in 0..height {
+        out.push('#');
+        for c in 0..width {
+            let winds: &[char] = match c == 0 || c == width - 1 {
+                true => &['<', '>'],
+                false => &['<', '>', '^', 'v'],
+            };
+            out.push(match rng.chance(0.9) {
+                true => *rng.pick(winds),
+                false => '.',
+            });
+        }
+        out.push_str("#\n");
+    }
+    out + &"#".repeat(width) + ".#\n"
+}
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    const DAY: u8 = 24;
+    /// Blizzards and the size of the valley including walls
+    type Input = (Vec<Blizzard>, usize, usize);
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input)
     }
 
-    pub fn orchestrate(&mut self, start: Coord, end: Coord) -> usize {
-        let val1 = self.find_shortest_path(start, end, 0);
-        let val2 = self.find_shortest_path(end, start, val1);
-        self.find_shortest_path(start, end, val2)
+    fn part1(input: &Self::Input) -> Answer {
+        Self::record(input, Part::One, &mut Discard).unwrap()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        Self::record(input, Part::Two, &mut Discard).unwrap()
+    }
+
+    fn record(input: &Self::Input, part: Part, recorder: &mut dyn Recorder) -> Option<Answer> {
+        let (blizzards, nrows, ncols) = input.clone();
+        let mut session = Session::new(blizzards, nrows, ncols);
+        let (start, end) = session.entrance_and_exit();
+        Some(budget::found(match part {
+            Part::One => session.find_shortest_path(start, end, 0, recorder),
+            Part::Two => session.orchestrate(start, end, recorder),
+        }))
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
 
@@ -200,27 +298,29 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let (blizzards, nrows, ncols) = parse_input(read_input("in.test").unwrap());
-        //println!("blizzards: {:?}", blizzards);
-        let mut session = Session::new(blizzards, nrows, ncols);
-        let part1 = session.find_shortest_path((0, 1), (nrows - 1, ncols - 2), 0);
-        let part2 = session.orchestrate((0, 1), (nrows - 1, ncols - 2));
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day24>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let (blizzards, nrows, ncols) = parse_input(read_input("in.1").unwrap());
-        //println!("blizzards: {:?}", blizzards);
+        aoc_core::expected::check::<Day24>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day24>("#.###\n#.x.#\n###.#\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 3, "x"));
+        assert_eq!(aoc_core::expected::parse_error::<Day24>("").message, "expected a valley inside walls");
+    }
+
+    #[test]
+    fn blizzards_come_back() {
+        let (blizzards, nrows, ncols) = Day24::parse(&Input::from_path("in.test").unwrap()).unwrap();
         let mut session = Session::new(blizzards, nrows, ncols);
-        let part1 = session.find_shortest_path((0, 1), (nrows - 1, ncols - 2), 0);
-        let part2 = session.orchestrate((0, 1), (nrows - 1, ncols - 2));
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:?}", elapsed);
+        let start = session.snapshot();
+        assert_eq!(session.run_to(session.period() - 1), 11);
+        assert_ne!(session.snapshot(), start);
+        assert!(session.step());
+        assert_eq!(session.snapshot(), start);
     }
 }
@@ -1,16 +1,40 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 3: Rucksack Reorganization.
+//!
+//! The public surface is [`Day3`] alone; a rucksack parses to its items as a
+//! `Vec<char>`.
+
 use std::collections::HashSet;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-fn parse_input(input: Vec<String>) -> Vec<Vec<char>> {
-    input.iter().map(|rucksack| rucksack.chars().collect()).collect()
+/// Each rucksack's compartments share an item, and each group of three
+/// rucksacks has a badge they all carry
+fn parse_input(input: &Input) -> Result<Vec<Vec<char>>, ParseError> {
+    let rucksacks: Vec<Vec<char>> = input.numbered_lines().map(|(n, rucksack)| {
+        if let Some((i, c)) = rucksack.char_indices().find(|(_, c)| !c.is_ascii_alphabetic()) {
+            return Err(ParseError::at(n, rucksack, &rucksack[i..i + c.len_utf8()], "items are letters"));
+        }
+        let (left, right) = rucksack.split_at(rucksack.len() / 2);
+        if left.len() != right.len() {
+            return Err(ParseError::new(n, rucksack, "the two compartments need the same number of items"));
+        }
+        if !left.chars().any(|c| right.contains(c)) {
+            return Err(ParseError::new(n, rucksack, "the compartments share no item"));
+        }
+        Ok(rucksack.chars().collect())
+    }).collect::<Result<_, _>>()?;
+    let lines: Vec<(usize, &str)> = input.numbered_lines().collect();
+    for (group, first) in rucksacks.chunks(3).zip(lines.chunks(3)) {
+        let (n, line) = first[0];
+        if group.len() < 3 {
+            return Err(ParseError::new(n, line, "rucksacks come in groups of three"));
+        }
+        if !group[0].iter().any(|c| group[1].contains(c) && group[2].contains(c)) {
+            return Err(ParseError::new(n, line, "this group of three shares no item"));
+        }
+    }
+    Ok(rucksacks)
 }
 
 fn get_priority(c: char) -> u32 {
@@ -24,7 +48,7 @@ fn get_hashed_set(round: &[char]) -> HashSet<char> {
     round.iter().cloned().collect()
 }
 
-fn get_priorities(rounds: &Vec<Vec<char>>) -> u32 {
+fn get_priorities(rounds: &[Vec<char>]) -> u32 {
     rounds.iter().map(|round| {
         let length = round.len();
         let (left, right) = round.split_at(length/2);
@@ -33,7 +57,7 @@ fn get_priorities(rounds: &Vec<Vec<char>>) -> u32 {
     }).sum()
 }
 
-fn get_badge_priorities(rounds: &Vec<Vec<char>>) -> u32 {
+fn get_badge_priorities(rounds: &[Vec<char>]) -> u32 {
     rounds.chunks(3)
         .map(|group| {
             let mut sets: Vec<HashSet<char>> = group.iter().map(|val| get_hashed_set(val)).collect();
@@ -46,29 +70,76 @@ fn get_badge_priorities(rounds: &Vec<Vec<char>>) -> u32 {
         }).sum()
 }
 
+/// 100 groups of three rucksacks per `size`. Each group draws its items from
+/// its own shuffle of the letters, so that the badge is the only item all three
+/// share and each rucksack has exactly one item in both compartments
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let mut letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+    let mut out = String::new();
+    for _ in 0..100 * size {
+        rng.shuffle(&mut letters);
+        let badge = letters[0];
+        for pool in letters[1..].chunks(17) {
+            let (shared, left_only, right_only) = (pool[0], &pool[1..9], &pool[9..]);
+            let half = rng.range(8..=16) as usize;
+            let mut left = vec![shared, badge];
+            left.extend((2..half).map(|_| *rng.pick(left_only)));
+            let mut right = vec![shared];
+            right.extend((1..half).map(|_| *rng.pick(right_only)));
+            rng.shuffle(&mut left);
+            rng.shuffle(&mut right);
+            out.extend(left.into_iter().chain(right));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u8 = 3;
+    type Input = Vec<Vec<char>>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        get_priorities(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        get_badge_priorities(input).into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let input = parse_input(read_input("in.test").unwrap());
-        let part1 = get_priorities(&input);
-        let part2 = get_badge_priorities(&input);
-        println!("test 1: {}", part1);
-        println!("test 2: {}", part2);
+        aoc_core::expected::check::<Day3>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let input = parse_input(read_input("in.1").unwrap());
-        let part1 = get_priorities(&input);
-        let part2 = get_badge_priorities(&input);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("test 2: {}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day3>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day3>("x\n");
+        assert_eq!((e.line, e.message.as_str()), (1, "the two compartments need the same number of items"));
+        let e = aoc_core::expected::parse_error::<Day3>("abab\nabcd\n");
+        assert_eq!((e.line, e.message.as_str()), (2, "the compartments share no item"));
+        let e = aoc_core::expected::parse_error::<Day3>("aa\nbb\ncc\n");
+        assert_eq!((e.line, e.message.as_str()), (1, "this group of three shares no item"));
+        assert_eq!(aoc_core::expected::parse_error::<Day3>("aa\naa\naa\naa\n").line, 4);
     }
 }
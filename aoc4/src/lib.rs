@@ -1,63 +1,94 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::cmp::{min, max};
-
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+//! Day 4: Camp Cleanup.
+//!
+//! The public surface is [`Day4`] and the [`Group`] of section ranges each
+//! line parses to.
+
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, IntervalSet, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-type Group = Vec<(u32, u32)>;
+/// The first and last section of each elf in a pair
+pub type Group = Vec<(u32, u32)>;
 
-fn parse_input(input: Vec<String>) -> Vec<Group> {
-    input.into_iter()
-        .map(|s| s.split(",")
-            .map(|one| {
-                let v = one.split("-").map(|v| v.parse::<u32>().unwrap()).collect::<Vec<u32>>();
-                (v[0], v[1])
-                })
-            .collect::<Group>())
+fn parse_input(input: &Input) -> Result<Vec<Group>, ParseError> {
+    input.numbered_lines()
+        .map(|(n, s)| {
+            let pair = s.split_once(",").ok_or_else(|| ParseError::new(n, s, "expected two ranges"))?;
+            [pair.0, pair.1].into_iter()
+                .map(|one| {
+                    let (first, last) = one.split_once("-").ok_or_else(|| ParseError::at(n, s, one, "expected a range"))?;
+                    Ok((parse_at(n, s, first)?, parse_at(n, s, last)?))
+                    })
+                .collect::<Result<Group, ParseError>>()
+        })
         .collect()
 }
 
-fn check_subsumed(inp: &Vec<Group>) -> usize {
-    inp.iter().filter(|val| 
-        val[0].0 >= val[1].0 && val[0].1 <= val[1].1 ||
-        val[1].0 >= val[0].0 && val[1].1 <= val[0].1
-    ).count()
+fn sections(&(first, last): &(u32, u32)) -> IntervalSet<u32> {
+    IntervalSet::single(first, last)
 }
 
-fn check_overlap(inp: &Vec<Group>) -> usize {
-    inp.iter().filter(|val| 
-        min(val[0].1, val[1].1) >= max(val[0].0, val[1].0))
+fn check_subsumed(inp: &[Group]) -> usize {
+    inp.iter().filter(|val| {
+        let (a, b) = (sections(&val[0]), sections(&val[1]));
+        a.is_superset(&b) || b.is_superset(&a)
+    }).count()
+}
+
+fn check_overlap(inp: &[Group]) -> usize {
+    inp.iter().filter(|val| !sections(&val[0]).is_disjoint(&sections(&val[1])))
     .count()
 }
 
+/// 1000 pairs of elves per `size`, sections numbered 1 to 99
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let mut range = || {
+        let (a, b) = (rng.range(1..=99), rng.range(1..=99));
+        format!("{}-{}", a.min(b), a.max(b))
+    };
+    (0..1000 * size).map(|_| format!("{},{}\n", range(), range())).collect()
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u8 = 4;
+    type Input = Vec<Group>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        check_subsumed(input).into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        check_overlap(input).into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let inp = parse_input(read_input("in.test").unwrap());
-        let part1 = check_subsumed(&inp);
-        let part2 = check_overlap(&inp);
-        println!("test 1: {}", part1);
-        println!("test 2: {}", part2);
+        aoc_core::expected::check::<Day4>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let inp = parse_input(read_input("in.1").unwrap());
-        let part1 = check_subsumed(&inp);
-        let part2 = check_overlap(&inp);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day4>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day4>("2-4,6-8\n2-4,6x8\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 5, "6x8"));
     }
 }
@@ -1,46 +1,50 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+//! Day 5: Supply Stacks.
+//!
+//! A [`Ship`] holds the stacks of crates and the [`Instr`] moves to make, and
+//! plays them with either crane.
+
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
 
-#[derive(Debug)]
-struct Instr {
+/// Move `quantity` crates from one stack to another
+#[derive(Debug, Clone)]
+pub struct Instr {
     quantity: usize,
     from: usize,
     to: usize,
 }
 
 impl Instr {
+    /// Stacks are numbered from 1, as in the puzzle
     pub fn new(quantity: usize, from: usize, to: usize) -> Self {
         Instr {
-            quantity: quantity,
+            quantity,
             from: from - 1,
             to: to - 1
         }
     }
 }
 
-#[derive(Debug)]
-struct Ship {
+/// The stacks, bottom crate first, and the moves still to make
+#[derive(Debug, Clone)]
+pub struct Ship {
     crates: Vec<Vec<u8>>,
     instructions: Vec<Instr>
 }
 
 impl Ship {
-    pub fn new(stacks: Vec<&str>, instructions: Vec<Instr>) -> Self {
+    /// Each stack is a string of crate letters, bottom crate first
+    pub fn new<S: AsRef<str>>(stacks: impl IntoIterator<Item = S>, instructions: Vec<Instr>) -> Self {
         Ship {
-            crates: stacks.into_iter().map(|stack| stack.as_bytes().to_vec()).collect(),
-            instructions: instructions
+            crates: stacks.into_iter().map(|stack| stack.as_ref().as_bytes().to_vec()).collect(),
+            instructions
         }
     }
 
+    /// Top crates once the CrateMover 9000 has moved them one at a time
     pub fn simulate_part1(&self) -> String {
-        let mut crates_copy: Vec<Vec<u8>> = self.crates.iter().cloned().collect();
+        let mut crates_copy: Vec<Vec<u8>> = self.crates.to_vec();
         for instr in self.instructions.iter() {
             for _ in 0..instr.quantity {
                 let val = crates_copy[instr.from].pop().unwrap();
@@ -50,6 +54,8 @@ impl Ship {
         Ship::get_as_string(&crates_copy)
     }
 
+    /// Top crates once the CrateMover 9001 has moved them all at once. This
+    /// plays the moves on the ship itself
     pub fn simulate_part2(&mut self) -> String {
         for instr in self.instructions.iter() {
             let ind = self.crates[instr.from].len() - instr.quantity;
@@ -59,26 +65,125 @@ impl Ship {
         Ship::get_as_string(&self.crates)
     }
 
-    fn get_as_string(crates: &Vec<Vec<u8>>) -> String {
+    fn get_as_string(crates: &[Vec<u8>]) -> String {
         String::from_utf8(crates.iter().map(|v| v[v.len() - 1]).collect()).unwrap()
     }
 }
 
-fn parse_input(input: Vec<String>) -> Vec<Instr> {
+/// Read the `move N from A to B` lines after the drawing of `stacks`, which
+/// must only move crates that are there and leave a crate on every stack
+fn parse_input(input: &Input, stacks: &[String]) -> Result<Vec<Instr>, ParseError> {
+    let mut heights: Vec<usize> = stacks.iter().map(|s| s.len()).collect();
     let mut res = Vec::new();
     let mut flag = false;
-    for val in input.into_iter() {
+    for (n, val) in input.numbered_lines() {
         if !flag && !val.starts_with("m") {
             continue;
         }
         flag = true;
         let tmp: Vec<&str> = val.split(" ").collect();
-        res.push(Instr::new(
-            tmp[1].parse::<usize>().unwrap(),
-            tmp[3].parse::<usize>().unwrap(),
-            tmp[5].parse::<usize>().unwrap()));
+        let ["move", quantity, "from", from, "to", to] = tmp[..] else {
+            return Err(ParseError::new(n, val, "expected `move N from A to B`"));
+        };
+        let stack = |s: &str| -> Result<usize, ParseError> {
+            match parse_at(n, val, s)? {
+                i @ 1.. if i <= stacks.len() => Ok(i),
+                _ => Err(ParseError::at(n, val, s, format!("no stack {}", s))),
+            }
+        };
+        let (count, from, to) = (parse_at(n, val, quantity)?, stack(from)?, stack(to)?);
+        if heights[from - 1] < count {
+            return Err(ParseError::at(n, val, quantity, format!("stack {} only has {} crates", from, heights[from - 1])));
+        }
+        heights[from - 1] -= count;
+        heights[to - 1] += count;
+        res.push(Instr::new(count, from, to));
+    }
+    if let Some(empty) = heights.iter().position(|&h| h == 0) {
+        let (n, labels) = input.numbered_lines().take_while(|(_, l)| !l.trim().is_empty()).last().unwrap_or((1, ""));
+        let label = labels.split_whitespace().nth(empty).unwrap_or(labels);
+        return Err(ParseError::at(n, labels, label, format!("stack {} ends up with no crate on top", empty + 1)));
+    }
+    Ok(res)
+}
+
+/// Read the starting stacks from the drawing above the moves, bottom crate first
+fn parse_stacks(input: &Input) -> Result<Vec<String>, ParseError> {
+    let drawing: Vec<&str> = input.lines().take_while(|l| !l.trim().is_empty()).collect();
+    let (labels, rows) = drawing.split_last().ok_or_else(|| ParseError::new(1, "", "expected a drawing of the stacks"))?;
+    for (i, label) in labels.split_whitespace().enumerate() {
+        if label != (i + 1).to_string() {
+            return Err(ParseError::at(drawing.len(), labels, label, format!("expected stack number {} under the drawing", i + 1)));
+        }
+    }
+    let mut stacks = vec![String::new(); labels.split_whitespace().count()];
+    for row in rows.iter().rev() {
+        for (i, stack) in stacks.iter_mut().enumerate() {
+            match row.as_bytes().get(4 * i + 1) {
+                Some(c) if c.is_ascii_alphabetic() => stack.push(*c as char),
+                _ => (),
+            }
+        }
+    }
+    Ok(stacks)
+}
+
+/// Nine stacks of up to `8 * size` crates and 500 moves per `size`. The moves
+/// are played as they are made and always leave a crate behind, so that every
+/// stack has a top crate at the end
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    // At least ten crates on nine stacks, so some stack always has one to spare
+    let mut stacks: Vec<Vec<char>> = (0..9).map(|i| {
+        let height = rng.range(1 + (i == 0) as i64..=8 * size as i64);
+        rng.word(height as usize, 'A'..='Z').chars().collect()
+    }).collect();
+    let height = stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for level in (0..height).rev() {
+        let row: Vec<String> = stacks.iter().map(|s| match s.get(level) {
+            Some(c) => format!("[{}]", c),
+            None => "   ".to_string(),
+        }).collect();
+        out += row.join(" ").trim_end();
+        out.push('\n');
+    }
+    out += " 1   2   3   4   5   6   7   8   9\n\n";
+    for _ in 0..500 * size {
+        let spare: Vec<usize> = (0..9).filter(|&i| stacks[i].len() > 1).collect();
+        let from = *rng.pick(&spare);
+        let to = (from + 1 + rng.below(8)) % 9;
+        let left = rng.range(1..=stacks[from].len() as i64 - 1) as usize;
+        let quantity = stacks[from].len() - left;
+        let moved = stacks[from].split_off(left);
+        stacks[to].extend(moved);
+        out += &format!("move {} from {} to {}\n", quantity, from + 1, to + 1);
+    }
+    out
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u8 = 5;
+    type Input = Ship;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        let stacks = parse_stacks(input).map_err(|e| input.locate(e))?;
+        let instrs = parse_input(input, &stacks).map_err(|e| input.locate(e))?;
+        Ok(Ship::new(stacks, instrs))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        input.simulate_part1().into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        input.clone().simulate_part2().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
-    res
 }
 
 #[cfg(test)]
@@ -87,37 +192,21 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let inp = parse_input(read_input("in.test").unwrap());
-        let crates = vec!["ZN", "MCD", "P"];
-        let mut ship = Ship::new(crates, inp);
-        let part1 = ship.simulate_part1();
-        let part2 = ship.simulate_part2();
-        println!("Part 1: {:?}", part1);
-        println!("Part 2: {:?}", part2);
+        aoc_core::expected::check::<Day5>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let inp = parse_input(read_input("in.1").unwrap());
-        let crates = vec![
-            "RSLFQ",
-            "NZQGPT",
-            "SMQB",
-            "TGZJHCBQ",
-            "PHMBNFS",
-            "PCQNSLVG",
-            "WCF",
-            "QHGZWVPM",
-            "GZDLCNR"
-        ];
-        let mut ship = Ship::new(crates, inp);
-        let part1 = ship.simulate_part1();
-        let part2 = ship.simulate_part2();
-        let elapsed = now.elapsed();
-        println!("Part 1: {:?}", part1);
-        println!("Part 2: {:?}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day5>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day5>("x\n");
+        assert_eq!((e.line, e.column, e.message.as_str()), (1, 1, "expected stack number 1 under the drawing"));
+        let e = aoc_core::expected::parse_error::<Day5>("[A]\n 1   2\n\nmove 1 from 1 to 2\nmove 2 from 1 to 2\n");
+        assert_eq!((e.line, e.column, e.message.as_str()), (5, 6, "stack 1 only has 0 crates"));
+        let e = aoc_core::expected::parse_error::<Day5>("[A]\n 1   2\n\nmove 1 from 1 to 2\n");
+        assert_eq!((e.line, e.column, e.message.as_str()), (2, 2, "stack 1 ends up with no crate on top"));
     }
 }
@@ -1,15 +1,23 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::HashSet;
-
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
+//! Day 6: Tuning Trouble.
+//!
+//! The public surface is [`Day6`] alone; the datastream is the input text
+//! itself.
+
+
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
+
+/// Datastreams are made of lowercase letters only; the marker search relies on it
+fn check_input(inp: &Input) -> Result<(), ParseError> {
+    for (n, ex) in inp.numbered_lines() {
+        if let Some((i, c)) = ex.char_indices().find(|(_, c)| !c.is_ascii_lowercase()) {
+            return Err(ParseError::at(n, ex, &ex[i..i + c.len_utf8()], "expected a lowercase letter"));
+        }
+    }
+    Ok(())
 }
 
-fn get_first_marker_index(inp: &Vec<String>, num_distinct: usize) -> Vec<usize> {
+fn get_first_marker_index(inp: &Input, num_distinct: usize) -> Vec<usize> {
     let mut res = vec![];
     let get_ones = |val: &[u8]| -> usize {
         let mut accum: usize = 0;
@@ -19,25 +27,11 @@ fn get_first_marker_index(inp: &Vec<String>, num_distinct: usize) -> Vec<usize>
         accum.count_ones() as usize
     };
 
-    for ex in inp.iter() {
+    for ex in inp.lines() {
         for (i, val) in ex.as_bytes().windows(num_distinct).enumerate() {
-            // Slowest -> Hash the slice and compare lengths - 1ms in release
-            //let set: HashSet<u8> = val.iter().cloned().collect();
-            //if set.len() == num_distinct {
-            //    res.push(i + num_distinct);
-            //    break;
-            //}
-
-            // Use Bitmap -> compare number of true values with num_distinct -> 500us in release
-            //let mut bitmap = vec![false; 26];
-            //let count = bitmap.iter().filter(|v| **v).count();
-            //if count == num_distinct {
-            //    res.push(i + num_distinct);
-            //    break;
-            //}
-
-            // Idea from /r/adventofcode -> Use XoR -> 100us in release
-            if get_ones(&val) == num_distinct {
+            // Idea from /r/adventofcode -> Use XoR -> 100us in release,
+            // against 1ms for hashing the slice and 500us for a bitmap
+            if get_ones(val) == num_distinct {
                 res.push(i + num_distinct);
                 break;
             }
@@ -47,30 +41,75 @@ fn get_first_marker_index(inp: &Vec<String>, num_distinct: usize) -> Vec<usize>
     res
 }
 
+/// The example file holds several datastreams; report every marker in that case
+fn markers_answer(markers: Vec<usize>) -> Answer {
+    match markers.len() {
+        1 => markers[0].into(),
+        _ => markers.iter().map(|m| m.to_string()).collect::<Vec<String>>().join(",").into(),
+    }
+}
+
+/// One datastream of 4096 letters per `size`. It starts out with three letters
+/// only, moves on to thirteen so that the packet marker turns up there, then has
+/// fourteen distinct letters for the message marker, then anything
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let len = 4096 * size;
+    let packet = len * rng.range(25..=45) as usize / 100;
+    let message = len * rng.range(55..=85) as usize / 100;
+    let mut letters: Vec<char> = ('a'..='z').collect();
+    let mut out = String::with_capacity(len + 1);
+    rng.shuffle(&mut letters);
+    out.extend((0..packet).map(|_| *rng.pick(&letters[..3])));
+    rng.shuffle(&mut letters);
+    out.extend((packet..message).map(|_| *rng.pick(&letters[..13])));
+    rng.shuffle(&mut letters);
+    out.extend(&letters[..14]);
+    out.extend((message + 14..len).map(|_| *rng.pick(&letters)));
+    out.push('\n');
+    out
+}
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    const DAY: u8 = 6;
+    type Input = Input;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        check_input(input).map_err(|e| input.locate(e))?;
+        Ok(input.clone())
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        markers_answer(get_first_marker_index(input, 4))
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        markers_answer(get_first_marker_index(input, 14))
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let inp1 = read_input("in.test").unwrap();
-        let inp2 = read_input("in.test.2").unwrap();
-        let part1 = get_first_marker_index(&inp1, 4);
-        let part2 = get_first_marker_index(&inp2, 14);
-        println!("Test 1: {:?}", part1);
-        println!("Test 2: {:?}", part2);
+        aoc_core::expected::check::<Day6>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let inp = read_input("in.1").unwrap();
-        let part1 = get_first_marker_index(&inp, 4);
-        let part2 = get_first_marker_index(&inp, 14);
-        let elapsed = now.elapsed();
-        println!("Part 1: {:?}", part1);
-        println!("Part 2: {:?}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day6>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day6>("abc1\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 4, "1"));
     }
 }
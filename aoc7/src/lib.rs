@@ -1,22 +1,35 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 7: No Space Left On Device.
+//!
+//! A [`Session`] rebuilds the file system from the terminal output and owns
+//! its tree of [`Folder`]s; the [`Device`] adds the disk the puzzle asks about.
+//!
+//! ```
+//! use aoc_core::Input;
+//! use aoc7::Session;
+//!
+//! let session = Session::new();
+//! session.construct_graph(&Input::from("$ cd /\n$ ls\ndir a\n14848514 b.txt\n")).unwrap();
+//! assert_eq!(session.get_size(), 14848514);
+//! assert_eq!(session.root().size(), 14848514);
+//! ```
+
 use std::collections::HashMap;
 use std::rc::{Rc, Weak};
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
 use std::cmp::min;
 
+use aoc_core::error::parse_at;
+use aoc_core::{Answer, Input, ParseError, Solution};
+use aoc_core::gen::Rng;
+
+/// The puzzle's disk, unless `aoc.toml` says otherwise
 const TOT_DISK_SPACE: usize = 70000000;
 const FREE_SPACE_NEEDED: usize = 30000000;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
-
 #[derive(Debug)]
 struct AFile {
+    /// Only shown by `Debug`
+    #[allow(dead_code)]
     name: String,
     size: usize,
 }
@@ -25,21 +38,19 @@ impl AFile {
     pub fn new(name: &str, size: usize) -> Self {
         AFile {
             name: name.to_string(),
-            size: size
+            size
         }
     }
 
     pub fn get_size(&self) -> usize {
         self.size
     }
-
-    pub fn get_name(&self) -> String {
-        self.name.to_string()
-    }
 }
 
+/// A directory and everything below it. Folders only exist inside a
+/// [`Session`], which hands them out through [`Session::root`]
 #[derive(Debug)]
-struct Folder {
+pub struct Folder {
     name: String,
     files: Vec<AFile>,
     children: HashMap<String, Rc<RefCell<Folder>>>,
@@ -48,7 +59,7 @@ struct Folder {
 }
 
 impl Folder {
-    pub fn new(name: &str, parent: Weak<RefCell<Folder>>) -> Rc<RefCell<Self>> {
+    fn new(name: &str, parent: Weak<RefCell<Folder>>) -> Rc<RefCell<Self>> {
         Rc::new(
             RefCell::new(
                 Folder {
@@ -62,29 +73,36 @@ impl Folder {
         )
     }
 
-    pub fn add_file(&mut self, name: &str, size: usize) {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Size of everything below, as of the last [`get_size`](Folder::get_size)
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn add_file(&mut self, name: &str, size: usize) {
         self.files.push(AFile::new(name, size));
     }
 
-    pub fn add_directory(&mut self, name: &str, parent: Weak<RefCell<Folder>>) {
+    fn add_directory(&mut self, name: &str, parent: Weak<RefCell<Folder>>) {
         if self.children.contains_key(name) {
             return;
         }
         self.children.insert(name.to_string(), Folder::new(name, parent));
     }
 
-    pub fn get_parent(&self) -> Weak<RefCell<Folder>> {
+    fn get_parent(&self) -> Weak<RefCell<Folder>> {
         self.parent.clone()
     }
 
-    pub fn get_child(&self, name: &str) -> Rc<RefCell<Folder>> {
+    fn get_child(&self, name: &str) -> Rc<RefCell<Folder>> {
         self.children.get(name).unwrap().clone()
     }
 
-    pub fn update_size(&mut self, size: usize) {
-        self.size = size;
-    }
-
+    /// Add up the size of everything below, and remember it for this folder
+    /// and every folder below
     pub fn get_size(&mut self) -> usize {
         let mut size = 0;
         for file in self.files.iter() {
@@ -93,11 +111,12 @@ impl Folder {
         for dir in self.children.values() {
             size += dir.borrow_mut().get_size();
         }
-        //println!("{} {}", self.name, size);
+        aoc_core::trace!("dir sized", name = self.name.as_str(), size = size);
         self.size = size;
         size
     }
 
+    /// Total size of the folders from here down that hold at most 100000
     pub fn get_num_below_100000(&self) -> usize {
         let mut val = match self.size <= 100000 {
             true => self.size,
@@ -109,6 +128,8 @@ impl Folder {
         val
     }
 
+    /// Size of the smallest folder from here down that frees
+    /// `required_space`, or `usize::MAX` if there is none
     pub fn get_smallest_to_delete(&self, required_space: usize) -> usize {
         if self.size < required_space {
             return usize::MAX;
@@ -124,36 +145,47 @@ impl Folder {
     }
 }
 
-struct Session {
+/// A file system, starting out as an empty `/`
+pub struct Session {
     root_dir: Rc<RefCell<Folder>>
 }
 
 impl Session {
-    pub fn new(root: Rc<RefCell<Folder>>) -> Self {
+    pub fn new() -> Self {
         Session {
-            root_dir: root.clone()
+            root_dir: Folder::new("", Weak::new())
         }
     }
 
-    pub fn construct_graph(&self, input: Vec<String>) {
+    pub fn root(&self) -> Ref<'_, Folder> {
+        self.root_dir.borrow()
+    }
+
+    /// Add what the `cd` and `ls` commands in `input` found. Sizes are not
+    /// known until [`get_size`](Session::get_size) adds them up
+    pub fn construct_graph(&self, input: &Input) -> Result<(), ParseError> {
         let mut cur_dir = Rc::clone(&self.root_dir);
-        for val in input.into_iter() {
+        for (n, val) in input.numbered_lines() {
             if !val.starts_with("$") {
-                if !val.starts_with("dir") {
-                    let line: Vec<&str> = val.split(" ").collect();
-                    cur_dir.as_ref().borrow_mut().add_file(line[1], line[0].parse::<usize>().unwrap());
+                let Some((size, name)) = val.split_once(" ") else {
+                    return Err(ParseError::new(n, val, "expected `dir NAME` or `SIZE NAME`"));
+                };
+                if size != "dir" {
+                    cur_dir.as_ref().borrow_mut().add_file(name, parse_at(n, val, size)?);
                 } else {
-                    let line: Vec<&str> = val.split(" ").collect();
-                    cur_dir.as_ref().borrow_mut().add_directory(line[1], Rc::downgrade(&cur_dir));
+                    cur_dir.as_ref().borrow_mut().add_directory(name, Rc::downgrade(&cur_dir));
                 }
             } else {
                 let command_line: Vec<&str> = val.split(" ").collect();
-                if command_line[1] == "ls" {
-                    continue;
-                }
-                let dir = command_line[2];
+                let dir = match command_line[..] {
+                    ["$", "ls"] => continue,
+                    ["$", "cd", dir] => dir,
+                    _ => return Err(ParseError::new(n, val, "expected `$ ls` or `$ cd DIR`")),
+                };
                 if dir == ".." {
-                    let next_dir = cur_dir.as_ref().borrow().get_parent().upgrade().unwrap();
+                    let Some(next_dir) = cur_dir.as_ref().borrow().get_parent().upgrade() else {
+                        return Err(ParseError::at(n, val, dir, "already at the top"));
+                    };
                     cur_dir = next_dir;
                     continue;
                 }
@@ -162,8 +194,10 @@ impl Session {
                 cur_dir = next_dir;
             }
         }
+        Ok(())
     }
 
+    /// Total size of the file system, see [`Folder::get_size`]
     pub fn get_size(&self) -> usize {
         self.root_dir.borrow_mut().get_size()
     }
@@ -177,43 +211,151 @@ impl Session {
     }
 }
 
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+/// About 180 directories and 300 files per `size`, browsed depth first. File
+/// sizes are spread over several orders of magnitude and add up to between
+/// 42 and 68 million, so that part 2 has something to delete
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    #[derive(Clone)]
+    enum Entry {
+        Dir(usize),
+        /// Share of the total disk use
+        File(f64),
+    }
+    let (num_dirs, num_files) = (180 * size, 300 * size);
+    let mut entries: Vec<Vec<(String, Entry)>> = vec![vec![]; num_dirs];
+    let mut add = |rng: &mut Rng, dir: usize, entry: Entry| loop {
+        let len = rng.range(1..=8) as usize;
+        let mut name = rng.word(len, 'a'..='z');
+        if matches!(entry, Entry::File(_)) && rng.chance(0.5) {
+            let ext = rng.range(1..=3) as usize;
+            name = format!("{}.{}", name, rng.word(ext, 'a'..='z'));
+        }
+        if entries[dir].iter().all(|(n, _)| *n != name) {
+            entries[dir].push((name, entry));
+            break;
+        }
+    };
+    for dir in 1..num_dirs {
+        let parent = rng.below(dir);
+        add(rng, parent, Entry::Dir(dir));
+    }
+    for _ in 0..num_files {
+        let dir = rng.below(num_dirs);
+        let weight = 10f64.powf(rng.f64() * 3.0);
+        add(rng, dir, Entry::File(weight));
+    }
+    let total_weight: f64 = entries.iter().flatten().map(|(_, e)| match e {
+        Entry::File(weight) => *weight,
+        Entry::Dir(_) => 0.0,
+    }).sum();
+    let scale = rng.range(42_000_000..=68_000_000) as f64 / total_weight;
+
+    fn browse(rng: &mut Rng, entries: &[Vec<(String, Entry)>], dir: usize, scale: f64, out: &mut String) {
+        let mut listing: Vec<String> = entries[dir].iter().map(|(name, entry)| match entry {
+            Entry::Dir(_) => format!("dir {}", name),
+            Entry::File(weight) => format!("{} {}", ((weight * scale) as usize).max(1), name),
+        }).collect();
+        rng.shuffle(&mut listing);
+        out.push_str("$ ls\n");
+        for line in listing {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        for (name, entry) in &entries[dir] {
+            if let Entry::Dir(child) = entry {
+                out.push_str(&format!("$ cd {}\n", name));
+                browse(rng, entries, *child, scale, out);
+                out.push_str("$ cd ..\n");
+            }
+        }
+    }
+    let mut out = String::from("$ cd /\n");
+    browse(rng, &entries, 0, scale, &mut out);
+    out
+}
+
+/// The file system along with the size of the disk it is on and how much of
+/// it has to be free for the update
+pub struct Device {
+    session: Session,
+    disk_space: usize,
+    space_needed: usize,
+}
+
+impl Device {
+    /// Sizes `session` so that it is ready to be asked about
+    pub fn new(session: Session, disk_space: usize, space_needed: usize) -> Self {
+        session.get_size();
+        Device { session, disk_space, space_needed }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Size of the smallest folder that frees enough space for the update
+    pub fn smallest_to_delete(&self) -> usize {
+        let unused_space = self.disk_space.saturating_sub(self.session.get_size());
+        let required_space = self.space_needed.saturating_sub(unused_space);
+        self.session.get_smallest_to_delete(required_space)
+    }
+}
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u8 = 7;
+    type Input = Device;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        let session = Session::new();
+        session.construct_graph(input).map_err(|e| input.locate(e))?;
+        Ok(Device::new(session, input.param_or("disk_space", TOT_DISK_SPACE)?, input.param_or("space_needed", FREE_SPACE_NEEDED)?))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        input.session.get_num_below_100000().into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        input.smallest_to_delete().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let input = read_input("in.test").unwrap();
-        let root = Folder::new("", Weak::new());
-        let session = Session::new(root);
-        session.construct_graph(input);
-        let total_size = session.get_size();
-        let unused_space = TOT_DISK_SPACE - total_size;
-        let required_space = FREE_SPACE_NEEDED - unused_space;
-        let part1 = session.get_num_below_100000();
-        let part2 = session.get_smallest_to_delete(required_space);
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day7>("in.test");
     }
 
     #[test]
     fn actual() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let input = read_input("in.1").unwrap();
-        let root = Folder::new("", Weak::new());
-        let session = Session::new(root);
-        session.construct_graph(input);
-        let total_size = session.get_size();
-        let unused_space = TOT_DISK_SPACE - total_size;
-        let required_space = FREE_SPACE_NEEDED - unused_space;
-        let part1 = session.get_num_below_100000();
-        let part2 = session.get_smallest_to_delete(required_space);
-        let part1 = session.get_num_below_100000();
-        let part2 = session.get_smallest_to_delete(required_space);
-        let elapsed = now.elapsed();
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+        aoc_core::expected::check::<Day7>("in.1");
+    }
+
+    #[test]
+    fn puzzle_defaults() {
+        // No parameters attached, as when aoc.toml is missing
+        let parsed = Day7::parse(&Input::from_path("in.test").unwrap()).unwrap();
+        assert_eq!(Day7::part2(&parsed), Answer::from(24933642));
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day7>("$ cd /\n$ ls\nfoo bar\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (3, 1, "foo"));
     }
 }
@@ -1,20 +1,26 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
+//! Day 8: Treetop Tree House.
+//!
+//! The forest is a [`Grid`] of [`Tree`]s; [`generate_visibility`] looks along
+//! every row and column and fills in what each tree can see and be seen from.
+
 use std::collections::HashMap;
 
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+use aoc_core::grid::Pos;
+use aoc_core::{Answer, Grid, Input, Solution};
+use aoc_core::gen::Rng;
 
-fn parse_input(inp: Vec<String>) -> Vec<Vec<Tree>> {
-    inp.into_iter().map(|line| line.bytes().map(|b| Tree::new(b - b'0')).collect()).collect()
+fn parse_input(inp: &Input) -> aoc_core::Result<Grid<Tree>> {
+    let forest = Grid::parse(inp, |c| c.to_digit(10).map(|h| Tree::new(h as u8)))?;
+    if forest.height() == 0 {
+        return Err(inp.parse_error(1, "", "expected a grid of tree heights"));
+    }
+    Ok(forest)
 }
 
+/// A tree's height, and once the forest has been looked over, which sides it
+/// is visible from and how far it sees each way
 #[derive(Debug, Clone)]
-struct Tree {
+pub struct Tree {
     pub height: u8,
     pub visible: HashMap<Direction, bool>,
     pub scenic_distances: Vec<usize>,
@@ -23,7 +29,7 @@ struct Tree {
 impl Tree {
     pub fn new(height: u8) -> Self {
         Tree {
-            height: height,
+            height,
             visible: HashMap::new(),
             scenic_distances: Vec::new(),
         }
@@ -33,10 +39,12 @@ impl Tree {
         self.visible.insert(direction, visibility);
     }
 
+    /// Visible from outside the forest on any side
     pub fn is_visible(&self) -> bool {
         self.visible.values().any(|&x| x)
     }
 
+    /// The scenic score: how far it sees each way, multiplied
     pub fn get_distance(&self) -> usize {
         if self.scenic_distances.len() < 4 {
             return 0;
@@ -56,123 +64,111 @@ impl Tree {
     }
 }
 
+/// A side of the forest
 #[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
-enum Direction {
+pub enum Direction {
     Left,
     Right,
     Top,
     Bottom
 }
 
-fn process_direction(forest: &mut Vec<Vec<Tree>>, direction: Direction) {
-    let end_idx = match direction {
-        Direction::Left | Direction::Right => forest[0].len(),
-        Direction::Bottom | Direction::Top => forest.len()
-    };
-    let start_idx = match direction {
-        Direction::Left | Direction::Top => 0,
-        Direction::Bottom => forest.len(),
-        Direction::Right => forest[0].len()
+/// Look from every tree towards `direction`: it is visible from that side if
+/// nothing on the way is as tall, and it sees as far as the first tree that is
+fn process_direction(forest: &mut Grid<Tree>, direction: Direction) {
+    let step = match direction {
+        Direction::Left => (0, -1),
+        Direction::Right => (0, 1),
+        Direction::Top => (-1, 0),
+        Direction::Bottom => (1, 0),
     };
-    let range = match direction {
-        Direction::Left | Direction::Top => (0..end_idx).collect::<Vec<usize>>(),
-        Direction::Right | Direction::Bottom => (0..end_idx).rev().collect::<Vec<usize>>()
-    };
-    let iterations = match direction {
-        Direction::Left | Direction::Top => (0..forest.len()).collect::<Vec<usize>>(),
-        Direction::Right | Direction::Bottom => (0..forest[0].len()).collect::<Vec<usize>>()
-    };
-    //println!("Direction: {:?}, iteration: {:?}, range: {:?}", direction, iterations, range);
-    for it in iterations.iter() {
-        let mut cur_max: Option<u8> = None;
-        for ind in range.iter() {
-            let (left_ind, right_ind) = match direction {
-                Direction::Left | Direction::Right => (*it, *ind),
-                Direction::Top | Direction::Bottom => (*ind, *it)
-            };
-            let height = forest[left_ind][right_ind].height;
-            if cur_max.is_none() {
-                cur_max = Some(height);
-                forest[left_ind][right_ind].mark_visibility(direction, true);
-                //println!("Left Ind: {}, Right Ind: {}, cur_max: {:?}, tree: {:?}", left_ind, right_ind, cur_max, forest[left_ind][right_ind]);
-                continue;
-            }
-            if cur_max.unwrap() < height {
-                cur_max = Some(height);
-                forest[left_ind][right_ind].mark_visibility(direction, true);
+    for pos in forest.positions().collect::<Vec<Pos>>() {
+        let height = forest[pos].height;
+        let mut val = 0;
+        let mut blocked = false;
+        for other in forest.ray(pos, step) {
+            val += 1;
+            if forest[other].height >= height {
+                blocked = true;
+                break;
             }
-            //println!("Left Ind: {}, Right Ind: {}, cur_max: {:?}, tree: {:?}", left_ind, right_ind, cur_max, forest[left_ind][right_ind]);
-            let mut val = 0;
-            match direction {
-                Direction::Left => {
-                    for i in (0..*ind).rev() {
-                        val += 1;
-                        if forest[*it][i].height >= height {
-                            break;
-                        }
-                    }
-                },
-                Direction::Right => {
-                    for i in *ind+1..forest[0].len() {
-                        val += 1;
-                        if forest[*it][i].height >= height {
-                            break;
-                        }
-                    }
-                },
-                Direction::Top => {
-                    for j in (0..*ind).rev() {
-                        val += 1;
-                        if forest[j][*it].height >= height {
-                            break;
-                        }
-                    }
-                },
-                Direction::Bottom => {
-                    for j in *ind+1..forest.len() {
-                        val += 1;
-                        if forest[j][*it].height >= height {
-                            break;
-                        }
-                    }
-                }
-            };
-            forest[left_ind][right_ind].add_distance(val);
         }
+        if !blocked {
+            forest[pos].mark_visibility(direction, true);
+        }
+        forest[pos].add_distance(val);
     }
 }
 
-fn generate_visibility(forest: &mut Vec<Vec<Tree>>) {
+/// Look along the forest from every side, marking each tree in it
+pub fn generate_visibility(forest: &mut Grid<Tree>) {
     for direction in [Direction::Left, Direction::Right, Direction::Top, Direction::Bottom].into_iter() {
         process_direction(forest, direction);
     }
 }
 
+/// A square forest with 99 trees a side at `size` 1 and as many times the
+/// trees beyond, taller towards the middle like the real ones
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let side = aoc_core::gen::scaled(99, size, 2);
+    let mid = (side as f64 - 1.0) / 2.0;
+    let mut out = String::with_capacity(side * (side + 1));
+    for r in 0..side {
+        for c in 0..side {
+            let edge = ((r as f64 - mid).abs().max((c as f64 - mid).abs()) / mid.max(1.0)).min(1.0);
+            let height = (10.0 * (0.6 * rng.f64() + 0.4 * (1.0 - edge))) as u32;
+            out.push(char::from_digit(height.min(9), 10).unwrap());
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: u8 = 8;
+    type Input = Grid<Tree>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        let mut forest = input.clone();
+        generate_visibility(&mut forest);
+        forest.iter().filter(|(_, tree)| tree.is_visible()).count().into()
+    }
+
+    fn part2(input: &Self::Input) -> Answer {
+        let mut forest = input.clone();
+        generate_visibility(&mut forest);
+        forest.iter().map(|(_, tree)| tree.get_distance()).max().unwrap().into()
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_works() {
-        let mut input = parse_input(read_input("in.test").unwrap());
-        generate_visibility(&mut input);
-        let part1 = input.iter().cloned().into_iter().flatten().filter(|tree| tree.is_visible()).count();
-        let part2 = input.into_iter().flatten().map(|tree| tree.get_distance()).max().unwrap();
-        println!("Test 1: {}", part1);
-        println!("Test 2: {}", part2);
+        aoc_core::expected::check::<Day8>("in.test");
+    }
+
+    #[test]
+    fn actual() {
+        aoc_core::expected::check::<Day8>("in.1");
     }
 
     #[test]
-    fn actuak() {
-        use std::time::Instant;
-        let now = Instant::now();
-        let mut input = parse_input(read_input("in.1").unwrap());
-        generate_visibility(&mut input);
-        let part1 = input.iter().cloned().into_iter().flatten().filter(|tree| tree.is_visible()).count();
-        let part2 = input.into_iter().flatten().map(|tree| tree.get_distance()).max().unwrap();
-        let elapsed = now.elapsed();
-        println!("Part 1: {:?}", part1);
-        println!("Part 2: {}", part2);
-        println!("Elapsed: {:.2?}", elapsed);
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day8>("303\n2x5\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "x"));
+        assert_eq!(aoc_core::expected::parse_error::<Day8>("").message, "expected a grid of tree heights");
     }
 }
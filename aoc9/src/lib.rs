@@ -1,65 +1,40 @@
-use std::io::{BufRead, BufReader, ErrorKind};
-use std::io::Error;
-use std::fs::File;
-use std::collections::HashSet;
-
-fn read_input(filename: &str) -> Result<Vec<String>, Error> {
-    let f = File::open(filename).unwrap();
-    let f = BufReader::new(f);
-    f.lines().map(|l| l.and_then(|v| v.parse().map_err(|e| Error::new(ErrorKind::InvalidData, e)))).collect()
-}
+//! Day 9: Rope Bridge.
+//!
+//! A [`Session`] is a two-knot rope that follows the [`Instr`] moves and
+//! counts where its tail has been. It keeps the two ways of following them,
+//! and draws the rope after each step when following them one step at a time.
 
-#[derive(Debug, Copy, Clone)]
-enum Direction {
-    R,
-    L,
-    U,
-    D,
-    D45,
-    D135,
-    D225,
-    D315
-}
+use std::collections::HashSet;
 
-type Instr = (Direction, i32);
+use aoc_core::error::parse_at;
+use aoc_core::geom::{Dir4, Dir8, Point2};
+use aoc_core::render::{Discard, Recorder};
+use aoc_core::{Answer, Grid, Input, ParseError, Part, Solution};
+use aoc_core::gen::Rng;
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
-struct Pos {
-    pub i: i32,
-    pub j: i32
-}
+/// A direction for the head to move in and how many steps to take that way
+pub type Instr = (Dir4, i32);
 
-impl Pos {
-    pub fn new_origin() -> Self {
-        Pos {
-            i: 0,
-            j: 0
-        }
-    }
+type Pos = Point2<i32>;
 
-    pub fn new(i: i32, j: i32) -> Self {
-        Pos {
-            i: i,
-            j: j
-        }
-    }
-}
-
-fn parse_input(inp: Vec<String>) -> Vec<Instr> {
-    inp.into_iter().map(|line| {
-        let l: Vec<&str> = line.split(" ").collect();
-        let dir = match l[0] {
-            "R" => Direction::R,
-            "L" => Direction::L,
-            "U" => Direction::U,
-            "D" => Direction::D,
-            _ => panic!("Unexpected")
+fn parse_input(inp: &Input) -> Result<Vec<Instr>, ParseError> {
+    inp.numbered_lines().map(|(n, line)| {
+        let Some((d, steps)) = line.split_once(" ") else {
+            return Err(ParseError::new(n, line, "expected a direction and a distance"));
         };
-        (dir, l[1].parse::<i32>().unwrap())
+        let dir = match d {
+            "R" => Dir4::E,
+            "L" => Dir4::W,
+            "U" => Dir4::N,
+            "D" => Dir4::S,
+            _ => return Err(ParseError::at(n, line, d, "expected R, L, U or D"))
+        };
+        Ok((dir, parse_at::<u32>(n, line, steps)? as i32))
     }).collect()
 }
 
-struct Session {
+/// A rope of two knots, both starting at the origin
+pub struct Session {
     h: Pos,
     t: Pos,
     seen_pos: HashSet<Pos>
@@ -68,262 +43,213 @@ struct Session {
 impl Session {
     pub fn new() -> Self {
         Session {
-            h: Pos::new_origin(),
-            t: Pos::new_origin(),
+            h: Pos::default(),
+            t: Pos::default(),
             seen_pos: HashSet::new()
         }
     }
 
-    pub fn run_through(&mut self, instructions: &Vec<Instr>) {
+    /// Follow `instructions` a whole move at a time
+    pub fn run_through(&mut self, instructions: &[Instr]) {
         self.seen_pos.insert(self.h);
         let mut c = 0;
         for instr in instructions {
             c += 1;
-            //if c == 20 {
-            //    break;
-            //}
             let (dir, magn) = (instr.0, instr.1);
             let (h, t) = (self.h, self.t);
-            //println!("iter: {} h: {:?} t: {:?}", c, h, t,);
-            //println!("dir: {:?}, magn: {:?}", dir, magn);
-            match dir {
-                Direction::R => {
-                    let h_n = Pos::new(h.i + magn, h.j);
-                    if h.i == t.i && magn == 1 || h.i == t.i - 1 && (magn == 1 || magn == 2) {
-                        self.h = h_n;
-                        continue;
-                    }
-                    let t_n = Pos::new(h.i + magn - 1, h.j);
-                    let start_idx = t.i + 1;
-                    let end_idx = h.i + magn - 1;
-                    for r in start_idx..end_idx+1 {
-                        self.seen_pos.insert(Pos::new(r, h.j));
-                    }
-                    self.h = h_n;
-                    self.t = t_n;
-                },
-                Direction::L => {
-                    let h_n = Pos::new(h.i - magn, h.j);
-                    if h.i == t.i && magn == 1 || h.i == t.i + 1 && (magn == 1 || magn == 2) {
-                        self.h = h_n;
-                        continue;
-                    }
-                    let t_n = Pos::new(h.i - magn + 1, h.j);
-                    let end_idx = t.i - 1;
-                    let start_idx = h.i - magn + 1;
-                    for r in start_idx..end_idx+1 {
-                        self.seen_pos.insert(Pos::new(r, h.j));
-                    }
-                    self.h = h_n;
-                    self.t = t_n;
-                },
-                Direction::U => {
-                    let h_n = Pos::new(h.i, h.j + magn);
-                    if h.j == t.j - 1 && (magn == 1 || magn == 2) || h.j == t.j && magn == 1 {
-                        self.h = h_n;
-                        continue;
-                    }
-                    let t_n = Pos::new(h.i, h.j + magn - 1);
-                    let start_idx = t.j + 1;
-                    let end_idx = h.j + magn - 1;
-                    for r in start_idx..end_idx+1 {
-                        self.seen_pos.insert(Pos::new(h.i, r));
-                    }
-                    self.h = h_n;
-                    self.t = t_n;
-                },
-                Direction::D => {
-                    let h_n = Pos::new(h.i, h.j - magn);
-                    if h.j == t.j + 1 && (magn == 1 || magn == 2) || h.j == t.j && magn == 1 {
-                        self.h = h_n;
-                        continue;
-                    }
-                    let t_n = Pos::new(h.i, h.j - magn + 1);
-                    let end_idx = t.j - 1;
-                    let start_idx = h.j - magn + 1;
-                    for r in start_idx..end_idx+1 {
-                        self.seen_pos.insert(Pos::new(h.i, r));
-                    }
-                    self.h = h_n;
-                    self.t = t_n;
-                },
-                _ => (),
-            };
+            aoc_core::trace!("instruction", step = c, head = ?h, tail = ?t, dir = ?dir, steps = magn);
+            let d = dir.delta::<i32>();
+            let h_n = h + d * magn;
+            if h_n.chebyshev(t) <= 1 {
+                self.h = h_n;
+                continue;
+            }
+            // The tail falls in behind the head on the head's line, starting
+            // one step past where it is now along the move
+            let along = |p: Pos| p.x * d.x + p.y * d.y;
+            for k in along(t) - along(h) + 1..magn {
+                self.seen_pos.insert(h + d * k);
+            }
+            self.h = h_n;
+            self.t = h_n - d;
         }
     }
 
-    // Return the Direction that if applied to tail, 
+    // Return the direction that if applied to tail,
     //will make the tail move to its final position
-    pub fn move_one_step(&mut self, dir: Direction) -> Option<Direction> {
-        let (h, t) = (self.h, self.t);
-        let direction = match dir {
-            Direction::R => {
-                let h_n = Pos::new(h.i + 1, h.j);
-                if h.i == t.i || h.i == t.i - 1 {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            },
-            Direction::L => {
-                let h_n = Pos::new(h.i - 1, h.j);
-                if h.i == t.i || h.i == t.i + 1 {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            },
-            Direction::U => {
-                let h_n = Pos::new(h.i, h.j + 1);
-                if h.j == t.j - 1 || h.j == t.j {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            },
-            Direction::D => {
-                let h_n = Pos::new(h.i, h.j - 1);
-                if h.j == t.j + 1 || h.j == t.j {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            },
-            Direction::D45 => {
-                let h_n = Pos::new(h.i + 1, h.j + 1);
-                if h.i <= t.i && h.j <= t.j {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            },
-            Direction::D135 => {
-                let h_n = Pos::new(h.i - 1, h.j + 1);
-                if h.i >= t.i && h.j <= t.j {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            },
-            Direction::D225 => {
-                let h_n = Pos::new(h.i - 1, h.j - 1);
-                if h.i >= t.i && h.j >= t.j {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            },
-            Direction::D315 => {
-                let h_n = Pos::new(h.i + 1, h.j - 1);
-                if h.i <= t.i && h.j >= t.j {
-                    self.h = h_n;
-                    return None;
-                }
-                return self.helper(h_n);
-            }
-        };
-        direction
-    }
-
-    fn get_direction(old_t: Pos, new_t: Pos) -> Option<Direction> {
-        if new_t.i == old_t.i + 1 && new_t.j == old_t.j {
-            return Some(Direction::R);
-        }
-        if new_t.i == old_t.i - 1 && new_t.j == old_t.j {
-            return Some(Direction::L);
-        }
-        if new_t.i == old_t.i && new_t.j == old_t.j + 1 {
-            return Some(Direction::U);
-        }
-        if new_t.i == old_t.i && new_t.j == old_t.j - 1 {
-            return Some(Direction::D);
-        }
-        if new_t.i == old_t.i + 1 && new_t.j == old_t.j + 1 {
-            return Some(Direction::D45);
-        }
-        if new_t.i == old_t.i - 1 && new_t.j == old_t.j + 1 {
-            return Some(Direction::D135);
-        }
-        if new_t.i == old_t.i - 1 && new_t.j == old_t.j - 1 {
-            return Some(Direction::D225);
-        }
-        if new_t.i == old_t.i + 1 && new_t.j == old_t.j - 1 {
-            return Some(Direction::D315);
-        } else {
-            panic!("Should not be here");
+    fn move_one_step(&mut self, dir: Dir8) -> Option<Dir8> {
+        let h_n = self.h.step(dir);
+        if h_n.chebyshev(self.t) <= 1 {
+            self.h = h_n;
+            return None;
         }
+        self.helper(h_n)
     }
 
-    pub fn helper(&mut self, h_n: Pos) -> Option<Direction> {
-        let t_n = Pos::clone(&self.h);
-        let direction = Session::get_direction(self.t, t_n);
+    fn helper(&mut self, h_n: Pos) -> Option<Dir8> {
+        let t_n = self.h;
+        let direction = Dir8::ALL.into_iter().find(|&d| self.t.step(d) == t_n);
         self.seen_pos.insert(t_n);
         self.h = h_n;
         self.t = t_n;
-        return direction;
+        direction
+    }
+
+    /// Follow `instructions` one step at a time
+    pub fn process_multiple(&mut self, instructions: &[Instr]) {
+        self.process_recording(instructions, &mut Discard);
     }
 
-    pub fn process_multiple(&mut self, instructions: &Vec<Instr>) {
+    /// Follow `instructions` one step at a time, with a frame before the
+    /// first step and after each one
+    pub fn process_recording(&mut self, instructions: &[Instr], recorder: &mut dyn Recorder) {
+        let corners = Session::extent(instructions);
         self.seen_pos.insert(self.h);
-        for instr in instructions {
-            let (dir, magn) = (instr.0, instr.1);
-            for i in 0..magn {
-                self.move_one_step(dir);
+        recorder.capture(|| self.render(corners));
+        for &(dir, magn) in instructions {
+            for _ in 0..magn {
+                self.move_one_step(dir.into());
+                recorder.capture(|| self.render(corners));
             }
         }
     }
 
+    /// Opposite corners of the box the head stays in, and so the tail too
+    fn extent(instructions: &[Instr]) -> (Pos, Pos) {
+        let (mut low, mut high, mut h) = (Pos::default(), Pos::default(), Pos::default());
+        for &(dir, magn) in instructions {
+            h += dir.delta() * magn;
+            low = Point2::new(low.x.min(h.x), low.y.min(h.y));
+            high = Point2::new(high.x.max(h.x), high.y.max(h.y));
+        }
+        (low, high)
+    }
+
+    /// The box between `corners` as the puzzle draws it: the head, the tail,
+    /// `s` for the start and `#` where the tail has been
+    fn render(&self, (low, high): (Pos, Pos)) -> Grid<char> {
+        let (width, height) = ((high.x - low.x + 1) as usize, (high.y - low.y + 1) as usize);
+        Grid::from_fn(width, height, |(r, c)| {
+            let p = low + Point2::new(c as i32, r as i32);
+            if p == self.h {
+                'H'
+            } else if p == self.t {
+                'T'
+            } else if p == Pos::default() {
+                's'
+            } else if self.seen_pos.contains(&p) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+    }
+
+    /// Positions the tail has visited
     pub fn seen_count(&self) -> usize {
         self.seen_pos.len()
     }
+}
 
-    pub fn print(&self) {
-        println!("Current position: {:?} {:?}", self.h, self.t);
-        println!("Seen: {:?}", self.seen_pos);
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
     }
 }
 
-/*
-struct TenKnots {
-    sessions: Vec<Session>
+/// 2000 moves per `size`, getting longer towards the end as in the real input
+fn random_input(rng: &mut Rng, size: usize) -> String {
+    let moves = 2000 * size;
+    (0..moves).map(|i| {
+        let longest = 5 + 15 * i / moves;
+        format!("{} {}\n", rng.pick(&["R", "L", "U", "D"]), rng.range(1..=longest as i64))
+    }).collect()
 }
 
-impl TenKnots {
-    pub fn new() -> Self {
-        TenKnots {
-            sessions: vec![Session::new(); 9]
-        }
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: u8 = 9;
+    type Input = Vec<Instr>;
+
+    fn parse(input: &Input) -> aoc_core::Result<Self::Input> {
+        parse_input(input).map_err(|e| input.locate(e))
+    }
+
+    fn part1(input: &Self::Input) -> Answer {
+        Self::record(input, Part::One, &mut Discard).unwrap()
     }
 
-    pub fn run_through(&mut self) {
+    /// Positions the tail visits, with a frame after each step of the head
+    fn record(input: &Self::Input, part: Part, recorder: &mut dyn Recorder) -> Option<Answer> {
+        if part == Part::Two {
+            return None;
+        }
+        let mut session = Session::new();
+        session.process_recording(input, recorder);
+        Some(session.seen_count().into())
+    }
 
+    fn generate(rng: &mut Rng, size: usize) -> Option<String> {
+        Some(random_input(rng, size))
     }
 }
-*/
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_core::render::Frames;
 
     #[test]
     fn it_works() {
-        let inp = parse_input(read_input("in.test").unwrap());
-        let mut session = Session::new();
-        //session.run_through(&inp);
-        session.process_multiple(&inp);
-        let part1 = session.seen_count();
-        //session.print();
-        println!("Test 1: {}", part1);
+        aoc_core::expected::check::<Day9>("in.test");
     }
 
     #[test]
     fn actual() {
-        let inp = parse_input(read_input("in.1").unwrap());
+        aoc_core::expected::check::<Day9>("in.1");
+    }
+
+    #[test]
+    fn malformed() {
+        let e = aoc_core::expected::parse_error::<Day9>("R 4\nU x\n");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 3, "x"));
+    }
+
+    #[test]
+    fn one_step_at_a_time() {
+        let instructions = Day9::parse(&Input::from_path("in.test").unwrap()).unwrap();
+        let mut frames = Frames::new(1);
         let mut session = Session::new();
-        //session.run_through(&inp);
-        session.process_multiple(&inp);
-        let part1 = session.seen_count();
-        //session.print();
-        println!("Actual 1: {}", part1);
+        session.process_recording(&instructions, &mut frames);
+        assert_eq!(frames.frames().len(), 25);
+        let drawn = "\
+..##..
+...##.
+.TH##.
+....#.
+s###..";
+        assert_eq!(frames.frames()[24].to_string(), drawn);
+        assert_eq!(frames.frames()[0].to_string(), "......\n......\n......\n......\nH.....");
+    }
+
+    #[test]
+    fn whole_moves_agree() {
+        let moves = |rng: &mut Rng, scale: usize| (0..scale).map(|_| {
+            format!("{} {}\n", rng.pick(&["R", "L", "U", "D"]), rng.range(1..=4))
+        }).collect();
+        let visits = |instructions: &Vec<Instr>, run: fn(&mut Session, &[Instr])| {
+            let mut session = Session::new();
+            run(&mut session, instructions);
+            session.seen_count()
+        };
+        aoc_core::differential::check(
+            0..40,
+            1..=12,
+            moves,
+            |input| parse_input(input).ok(),
+            |instructions| visits(instructions, Session::run_through),
+            |instructions| visits(instructions, Session::process_multiple),
+        );
     }
 }